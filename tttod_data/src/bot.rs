@@ -0,0 +1,275 @@
+use crate::{
+    character::ATTRIBUTES, dice::choose, engine::is_alive, Challenge, ChallengeResult,
+    ClientToServerMessage, Condition, Dice, GameState, MentalCondition, Player, RuleSettings,
+    ServerToClientMessage,
+};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const NAMES: &[&str] = &[
+    "Professor Bones",
+    "Dr. Ada Finch",
+    "Lady Harriet Vane",
+    "Captain Rook",
+    "Old Mortimer",
+    "Sister Agnes",
+    "Jonas Quill",
+    "Mira Salt",
+];
+
+const ANSWERS: &[&str] = &[
+    "Nobody who has seen it has lived to tell.",
+    "It has been sleeping beneath the temple since before the first city was built.",
+    "A cult of masked priests, who still leave offerings at the gate.",
+    "The screams of the ones who came before us.",
+    "Gold, and the memories of everybody who touches it.",
+    "It was sealed away by a king who gave his own name to do so.",
+    "Its true form is a swarm of black moths that never stops moving.",
+    "The light of the full moon weakens it.",
+    "It wants to walk under the sun again.",
+    "A broken idol, its eyes filled with sand.",
+    "Whoever speaks its name is bound to serve it.",
+    "The drowned villages downriver are its doing.",
+];
+
+/// A player without a human behind it, it reacts to what the server sends like a client would.
+#[derive(Debug, Clone)]
+pub struct Bot {
+    player_id: Uuid,
+    players: HashMap<Uuid, Player>,
+    rules: RuleSettings,
+    /// Offered a challenge as a GM, which the server hasn't confirmed yet
+    offered: bool,
+    /// Accepted the challenge, waiting for it to be resolved
+    accepted: bool,
+}
+
+impl Bot {
+    pub fn new(player_id: Uuid) -> Self {
+        Self {
+            player_id,
+            players: HashMap::new(),
+            rules: RuleSettings::default(),
+            offered: false,
+            accepted: false,
+        }
+    }
+
+    pub fn player_id(&self) -> Uuid {
+        self.player_id
+    }
+
+    fn me(&self) -> Option<&Player> {
+        self.players.get(&self.player_id)
+    }
+
+    /// Returns what the bot answers to a message from the server.
+    pub fn react(
+        &mut self,
+        message: &ServerToClientMessage,
+        dice: &mut impl Dice,
+    ) -> Vec<ClientToServerMessage> {
+        match message {
+            ServerToClientMessage::PushState {
+                players,
+                game_state,
+                rules,
+                ..
+            } => {
+                self.players = players.clone();
+                self.rules = *rules;
+                self.follow(game_state, dice)
+            }
+            ServerToClientMessage::Questions { questions } => {
+                let answers = questions
+                    .iter()
+                    .map(|(_, answer)| {
+                        answer
+                            .clone()
+                            .unwrap_or_else(|| choose(ANSWERS, dice).to_string())
+                    })
+                    .collect();
+                vec![
+                    ClientToServerMessage::Answers { answers },
+                    ClientToServerMessage::ReadyForGame,
+                ]
+            }
+            ServerToClientMessage::AbortedChallenge => {
+                self.offered = false;
+                self.accepted = false;
+                Vec::new()
+            }
+            ServerToClientMessage::ChallengeResult(result) => self.decide(result),
+            _ => Vec::new(),
+        }
+    }
+
+    fn follow(
+        &mut self,
+        game_state: &GameState,
+        dice: &mut impl Dice,
+    ) -> Vec<ClientToServerMessage> {
+        let (named, ready, has_character) = match self.me() {
+            Some(me) => (!me.name.is_empty(), me.ready, me.stats.is_some()),
+            None => return Vec::new(),
+        };
+        match game_state {
+            GameState::PlayerSelection { .. } => {
+                let mut messages = Vec::new();
+                if !named {
+                    messages.push(ClientToServerMessage::SetPlayerName {
+                        name: format!("{} (Bot)", choose(NAMES, dice)),
+                    });
+                }
+                if !ready {
+                    messages.push(ClientToServerMessage::ReadyForGame);
+                }
+                messages
+            }
+            GameState::CharacterCreation => {
+                let mut messages = Vec::new();
+                if !has_character {
+                    messages.push(ClientToServerMessage::RollCharacter);
+                }
+                if !ready {
+                    messages.push(ClientToServerMessage::ReadyForGame);
+                }
+                messages
+            }
+            GameState::CharacterIntroduction if !ready => {
+                vec![ClientToServerMessage::ReadyForGame]
+            }
+            GameState::Room {
+                gm,
+                successes,
+                challenge,
+                ..
+            } => {
+                if *gm == self.player_id {
+                    if *successes >= self.rules.successes_needed {
+                        vec![ClientToServerMessage::ReadyForGame]
+                    } else {
+                        self.offer(challenge.as_ref(), &[*gm], dice)
+                            .map(|challenge| {
+                                vec![ClientToServerMessage::OfferChallenge { challenge }]
+                            })
+                            .unwrap_or_default()
+                    }
+                } else {
+                    self.accept(challenge.as_ref())
+                }
+            }
+            GameState::FinalBattle {
+                gms,
+                challenge,
+                remaining_clues,
+                ..
+            } => {
+                let mut gms: Vec<Uuid> = gms.iter().cloned().collect();
+                gms.sort();
+                // only one bot leads the final battle, so that they don't take turns replacing each other's challenges,
+                // without any GMs marked as bots (like when all players are scripted) the first one does
+                let leading_bot = gms
+                    .iter()
+                    .find(|player_id| self.players.get(player_id).is_some_and(|player| player.bot))
+                    .or_else(|| gms.first());
+                if leading_bot == Some(&self.player_id) {
+                    if remaining_clues.is_empty() {
+                        return Vec::new();
+                    }
+                    self.offer(challenge.as_ref(), &gms, dice)
+                        .map(|challenge| {
+                            vec![ClientToServerMessage::OfferChallengeFinal {
+                                challenge,
+                                clue_idx: dice.pick(remaining_clues.len()),
+                            }]
+                        })
+                        .unwrap_or_default()
+                } else {
+                    self.accept(challenge.as_ref())
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Picks a challenge for somebody who is still in the game, preferably a human.
+    fn offer(
+        &mut self,
+        current: Option<&Challenge>,
+        gms: &[Uuid],
+        dice: &mut impl Dice,
+    ) -> Option<Challenge> {
+        if current.is_some() {
+            self.offered = false;
+            return None;
+        }
+        if self.offered {
+            return None;
+        }
+        let mut candidates: Vec<(Uuid, bool)> = self
+            .players
+            .iter()
+            .filter(|(player_id, player)| !gms.contains(player_id) && is_alive(player))
+            .map(|(&player_id, player)| (player_id, player.bot))
+            .collect();
+        candidates.sort();
+        if candidates.iter().any(|(_, bot)| !bot) {
+            candidates.retain(|(_, bot)| !bot);
+        }
+        if candidates.is_empty() {
+            return None;
+        }
+        self.offered = true;
+        Some(Challenge {
+            player_id: choose(&candidates, dice).0,
+            attribute: *choose(ATTRIBUTES, dice),
+            speciality_applies: dice.pick(2) == 0,
+            reputation_applies: dice.pick(2) == 0,
+        })
+    }
+
+    fn accept(&mut self, challenge: Option<&Challenge>) -> Vec<ClientToServerMessage> {
+        match challenge {
+            Some(challenge) if challenge.player_id == self.player_id => {
+                if self.accepted {
+                    Vec::new()
+                } else {
+                    self.accepted = true;
+                    vec![ClientToServerMessage::ChallengeAccepted]
+                }
+            }
+            _ => {
+                self.accepted = false;
+                Vec::new()
+            }
+        }
+    }
+
+    /// Deals with a roll that the server is waiting for a decision about.
+    fn decide(&self, result: &ChallengeResult) -> Vec<ClientToServerMessage> {
+        let me = match self.me() {
+            Some(me) => me,
+            None => return Vec::new(),
+        };
+        let decision = if result.success {
+            if !result.can_use_artifact {
+                // nothing left to decide
+                return Vec::new();
+            }
+            // the artifact can only reroll, which isn't worth losing the success unless the evil is about to take over
+            if me.mental_condition == MentalCondition::Hale {
+                ClientToServerMessage::AcceptFate
+            } else {
+                ClientToServerMessage::UseArtifact
+            }
+        } else if result.can_use_artifact {
+            ClientToServerMessage::UseArtifact
+        } else if me.condition.take_hit() == Condition::Dead {
+            ClientToServerMessage::AcceptFate
+        } else {
+            ClientToServerMessage::TakeWound
+        };
+        vec![decision]
+    }
+}
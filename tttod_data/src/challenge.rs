@@ -1,4 +1,7 @@
-use crate::Attribute;
+use crate::{
+    engine::{check_can_use_artifact, check_success, possessed_dice},
+    ArtifactBoon, Attribute, Player,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -9,3 +12,116 @@ pub struct Challenge {
     pub speciality_applies: bool,
     pub reputation_applies: bool,
 }
+
+/// Probabilities between 0 and 1 of how a challenge ends up.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct Chances {
+    pub success: f64,
+    /// Rolling more than one 1 or more than one 2, which brings the ancient evil closer
+    pub possession: f64,
+}
+
+impl Chances {
+    fn add(&mut self, dice: &[u8], artifact: Option<ArtifactBoon>, probability: f64) {
+        if check_success(dice, artifact) {
+            self.success += probability;
+        }
+        if possessed_dice(dice) {
+            self.possession += probability;
+        }
+    }
+}
+
+/// What a player can expect from a challenge before accepting it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChallengeOdds {
+    /// Number of d6 rolled
+    pub dice: usize,
+    pub roll: Chances,
+    /// When using the artifact whenever the rules allow it, `None` if it has been used up already
+    pub with_artifact: Option<Chances>,
+}
+
+impl ChallengeOdds {
+    /// Works out the odds exactly, `None` if the player has no character yet.
+    pub fn new(player: &Player, challenge: &Challenge) -> Option<Self> {
+        let stats = player.stats.as_ref()?;
+        let dice = *stats.attributes.get(&challenge.attribute)? as usize
+            + challenge.speciality_applies as usize
+            + challenge.reputation_applies as usize;
+        let first_rolls = rolls(dice);
+
+        let mut roll = Chances::default();
+        for (dice, probability) in &first_rolls {
+            roll.add(dice, None, *probability);
+        }
+
+        let with_artifact = if player.artifact_used {
+            None
+        } else {
+            let artifact_boon = stats.artifact_boon;
+            let extra_rolls = if artifact_boon == ArtifactBoon::RollWithPlusTwo {
+                rolls(2)
+            } else {
+                vec![(Vec::new(), 1.0)]
+            };
+            let mut chances = Chances::default();
+            for (dice, probability) in &first_rolls {
+                if !check_can_use_artifact(dice, artifact_boon) {
+                    chances.add(dice, None, *probability);
+                } else if artifact_boon == ArtifactBoon::Reroll {
+                    chances.success += probability * roll.success;
+                    chances.possession += probability * roll.possession;
+                } else {
+                    for (extra_dice, extra_probability) in &extra_rolls {
+                        let results: Vec<u8> = dice.iter().chain(extra_dice).cloned().collect();
+                        chances.add(
+                            &results,
+                            Some(artifact_boon),
+                            probability * extra_probability,
+                        );
+                    }
+                }
+            }
+            Some(chances)
+        };
+
+        Some(Self {
+            dice,
+            roll,
+            with_artifact,
+        })
+    }
+}
+
+/// Every distinct roll of `count` dice (in ascending order), along with its probability.
+fn rolls(count: usize) -> Vec<(Vec<u8>, f64)> {
+    fn add_rolls(lowest: u8, left: usize, roll: &mut Vec<u8>, rolls: &mut Vec<Vec<u8>>) {
+        if left == 0 {
+            rolls.push(roll.clone());
+            return;
+        }
+        for face in lowest..=6 {
+            roll.push(face);
+            add_rolls(face, left - 1, roll, rolls);
+            roll.pop();
+        }
+    }
+    fn factorial(n: usize) -> f64 {
+        (1..=n).map(|n| n as f64).product()
+    }
+
+    let mut rolls = Vec::new();
+    add_rolls(1, count, &mut Vec::with_capacity(count), &mut rolls);
+    let outcomes = 6f64.powi(count as i32);
+    rolls
+        .into_iter()
+        .map(|roll| {
+            // the number of orders the same dice could have come up in
+            let orderings = (1..=6).fold(factorial(roll.len()), |orderings, face| {
+                orderings / factorial(roll.iter().filter(|&&die| die == face).count())
+            });
+            (roll, orderings / outcomes)
+        })
+        .collect()
+}
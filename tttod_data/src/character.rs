@@ -0,0 +1,141 @@
+use crate::{dice::choose, ArtifactBoon, Attribute, Dice, PlayerStats, Reputation, Speciality};
+
+const FIRST_NAMES: &[&str] = &[
+    "Ada",
+    "Amelia",
+    "Archibald",
+    "Beatrice",
+    "Cornelius",
+    "Edith",
+    "Ezra",
+    "Florence",
+    "Gideon",
+    "Harriet",
+    "Ignatius",
+    "Josephine",
+    "Lionel",
+    "Mabel",
+    "Mortimer",
+    "Octavia",
+    "Percival",
+    "Rosalind",
+    "Silas",
+    "Winifred",
+];
+
+const SURNAMES: &[&str] = &[
+    "Ashdown",
+    "Blackwood",
+    "Carrow",
+    "Finch",
+    "Gallowglass",
+    "Hartley",
+    "Mallory",
+    "Pembrook",
+    "Quill",
+    "Ravensworth",
+    "Salt",
+    "Thistlewaite",
+    "Vane",
+    "Whitlock",
+    "Wren",
+];
+
+const ARTIFACT_MATERIALS: &[&str] = &[
+    "Brass", "Obsidian", "Jade", "Silver", "Bone", "Amber", "Bronze", "Ivory", "Onyx", "Gold",
+];
+
+const ARTIFACT_OBJECTS: &[&str] = &[
+    "Compass", "Dagger", "Monocle", "Amulet", "Coin", "Idol", "Lantern", "Key", "Scarab", "Ring",
+    "Mirror", "Whistle",
+];
+
+/// Completed with one of the [`PLACES`].
+const ARTIFACT_ORIGINS: &[&str] = &[
+    "Found in a collapsed tomb near",
+    "Won in a game of cards in",
+    "Bought for far too little at a market in",
+    "Stolen from a museum in",
+    "A gift from a guide who vanished near",
+    "Dug up on the first expedition to",
+    "Taken from a rival's camp outside",
+];
+
+const PLACES: &[&str] = &[
+    "Cairo",
+    "Petra",
+    "Angkor",
+    "Timbuktu",
+    "Cusco",
+    "Alexandria",
+    "Samarkand",
+    "the Yucatán",
+    "the Gobi desert",
+];
+
+const SPECIALITIES: &[Speciality] = &[
+    Speciality::Religion,
+    Speciality::Linguistics,
+    Speciality::Architecture,
+    Speciality::WarAndWeaponry,
+    Speciality::GemsAndMetals,
+    Speciality::SecretSignsSymbols,
+    Speciality::Osteology,
+    Speciality::DeathAndBurial,
+];
+
+const REPUTATIONS: &[Reputation] = &[
+    Reputation::Ambitious,
+    Reputation::Genius,
+    Reputation::Ruthless,
+    Reputation::Senile,
+    Reputation::MadScientist,
+    Reputation::BornLeader,
+    Reputation::Rulebreaker,
+    Reputation::Obsessive,
+];
+
+const ARTIFACT_BOONS: &[ArtifactBoon] = &[
+    ArtifactBoon::Reroll,
+    ArtifactBoon::RollWithPlusTwo,
+    ArtifactBoon::SuccessOnFive,
+    ArtifactBoon::SuccessOnDoubles,
+];
+
+pub(crate) const ATTRIBUTES: &[Attribute] = &[
+    Attribute::Heroic,
+    Attribute::Booksmart,
+    Attribute::Streetwise,
+];
+
+impl PlayerStats {
+    /// A complete character, for bots and for players who'd rather not fill in the form themselves.
+    pub fn random(dice: &mut impl Dice) -> Self {
+        // every attribute needs at least one point, the other two go anywhere
+        let mut points = [1, 1, 1];
+        for _ in 0..2 {
+            points[dice.pick(points.len())] += 1;
+        }
+        Self {
+            name: format!("{} {}", choose(FIRST_NAMES, dice), choose(SURNAMES, dice)),
+            speciality: choose(SPECIALITIES, dice).clone(),
+            reputation: choose(REPUTATIONS, dice).clone(),
+            attributes: ATTRIBUTES
+                .iter()
+                .cloned()
+                .zip(points.iter().cloned())
+                .collect(),
+            artifact_name: format!(
+                "{} {}",
+                choose(ARTIFACT_MATERIALS, dice),
+                choose(ARTIFACT_OBJECTS, dice)
+            ),
+            artifact_origin: format!(
+                "{} {}",
+                choose(ARTIFACT_ORIGINS, dice),
+                choose(PLACES, dice)
+            ),
+            artifact_boon: *choose(ARTIFACT_BOONS, dice),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{dice::choose, Dice};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The questions from the original game, each with a few prompts to get an answer started.
+const ANCIENT_EVIL: &[(&str, &[&str])] = &[
+    (
+        "What is the source of my power?",
+        &[
+            "A heart that still beats, buried beneath the altar",
+            "The fear of everybody who has ever spoken my name",
+            "A bargain with the stars themselves",
+            "The blood of a dynasty of kings",
+            "A river that flows backwards under the temple",
+            "Every coin ever stolen from a grave",
+        ],
+    ),
+    (
+        "What is my greatest weakness and why?",
+        &[
+            "A song my mother sang to me",
+            "Running water, which remembers what I was",
+            "Mirrors, because I cannot bear my own face",
+            "The name I was born with",
+            "The innocence of a child",
+            "Salt from the sea I was born beside",
+        ],
+    ),
+    (
+        "What do I intend to do with the world once I conquer it?",
+        &[
+            "Bury every city beneath the sand",
+            "Make the sun rise only at my command",
+            "Turn every living thing into a statue for my halls",
+            "Undo history back to the day I was betrayed",
+            "Feed, and never stop",
+            "Rule as a god-king over a kingdom of the drowned",
+        ],
+    ),
+    (
+        "What created me and how?",
+        &[
+            "A priest who wanted to live forever",
+            "The last breath of a dying god",
+            "A ritual that went horribly right",
+            "A curse laid by a wronged queen",
+            "A meteor that fell before there were people",
+            "The collected hatred of an enslaved people",
+        ],
+    ),
+    (
+        "How do I defeat my enemies?",
+        &[
+            "I turn their friends against them",
+            "I show them their own deaths in their dreams",
+            "I wait, for I have all the time in the world",
+            "Swarms of creatures that crawl out of the walls",
+            "I take the shape of the ones they love",
+            "I steal their names, and with them their souls",
+        ],
+    ),
+    (
+        "What is most terrifying about me and why?",
+        &[
+            "My voice sounds like the victim's own",
+            "I do not have a face, only a mouth",
+            "I remember everybody I have ever consumed",
+            "Nobody can tell where my shadow ends",
+            "I am always polite",
+            "I smell like home",
+        ],
+    ),
+    (
+        "What motivates me and drives me forward?",
+        &[
+            "Revenge against the ones who sealed me away",
+            "Loneliness older than language",
+            "A promise I made to a lover long dead",
+            "Simple, endless hunger",
+            "The belief that I am saving the world",
+            "Boredom",
+        ],
+    ),
+    (
+        "What kept me sealed away all these years?",
+        &[
+            "Seven keys, scattered across seven kingdoms",
+            "A guardian who never sleeps",
+            "A riddle no one has ever solved",
+            "The prayers of a forgotten order of monks",
+            "A door that only opens from the outside",
+            "My own shame",
+        ],
+    ),
+    (
+        "What does my true form look like?",
+        &[
+            "A child made of polished bone",
+            "A tree whose roots are fingers",
+            "A swarm of black moths that never stops moving",
+            "A serpent wearing a golden mask",
+            "A pool of liquid night",
+            "A king on a throne, both made of the same stone",
+        ],
+    ),
+    (
+        "What do I promise to tempt others to obey me?",
+        &[
+            "The return of the ones they lost",
+            "Knowledge nobody else possesses",
+            "Gold, more than could ever be spent",
+            "Eternal youth",
+            "The downfall of their rivals",
+            "Forgiveness",
+        ],
+    ),
+];
+
+/// For questions without a table of their own, like the ones the players wrote.
+const ANY_QUESTION: &[&str] = &[
+    "Something that was lost long ago",
+    "A betrayal",
+    "A place no map shows",
+    "Something that should have stayed buried",
+    "A name nobody dares to say",
+    "A debt that was never paid",
+    "An animal, but wrong",
+    "The color of an old bruise",
+    "A sound heard only at night",
+    "A gift with a hidden price",
+];
+
+/// The questions the players answer about the ancient evil, one deck per kind of villain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deck {
+    #[serde(default)]
+    pub name: String,
+    pub questions: Vec<String>,
+    /// Prompts for answering the questions, by question
+    #[serde(default)]
+    pub inspiration: HashMap<String, Vec<String>>,
+}
+
+impl Default for Deck {
+    /// The questions from the original game.
+    fn default() -> Self {
+        Self {
+            name: "The Ancient Evil".to_owned(),
+            questions: ANCIENT_EVIL
+                .iter()
+                .map(|(question, _)| (*question).to_owned())
+                .collect(),
+            inspiration: ANCIENT_EVIL
+                .iter()
+                .map(|(question, prompts)| {
+                    (
+                        (*question).to_owned(),
+                        prompts.iter().map(|prompt| (*prompt).to_owned()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Deck {
+    pub fn info(&self) -> DeckInfo {
+        DeckInfo {
+            name: self.name.clone(),
+            questions: self.questions.len(),
+        }
+    }
+
+    /// A random prompt for answering the question, from a generic table if the deck has none for it.
+    pub fn roll_inspiration(&self, question: &str, dice: &mut impl Dice) -> String {
+        match self
+            .inspiration
+            .get(question)
+            .filter(|prompts| !prompts.is_empty())
+        {
+            Some(prompts) => choose(prompts, dice).clone(),
+            None => (*choose(ANY_QUESTION, dice)).to_owned(),
+        }
+    }
+}
+
+/// What the lobby shows about a deck.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeckInfo {
+    pub name: String,
+    /// Number of questions in the deck
+    pub questions: usize,
+}
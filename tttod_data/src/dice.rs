@@ -0,0 +1,55 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Source of all randomness in a game.
+pub trait Dice {
+    /// Rolls `count` six-sided dice.
+    fn roll_d6(&mut self, count: usize) -> Vec<u8>;
+    /// Picks a number in `0..upper`.
+    fn pick(&mut self, upper: usize) -> usize;
+    /// Fisher-Yates shuffle built on top of [`Dice::pick`], so that shuffles can be reproduced like rolls.
+    fn shuffle<T>(&mut self, items: &mut [T])
+    where
+        Self: Sized,
+    {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, self.pick(idx + 1));
+        }
+    }
+}
+
+pub(crate) fn choose<'a, T>(items: &'a [T], dice: &mut impl Dice) -> &'a T {
+    &items[dice.pick(items.len())]
+}
+
+/// Dice that produce the same sequence of results for the same seed.
+#[derive(Debug, Clone)]
+pub struct SeededDice {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+
+impl SeededDice {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+    pub fn from_entropy() -> Self {
+        Self::new(rand::thread_rng().gen())
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl Dice for SeededDice {
+    fn roll_d6(&mut self, count: usize) -> Vec<u8> {
+        (0..count).map(|_| self.rng.gen_range(1, 7)).collect()
+    }
+    fn pick(&mut self, upper: usize) -> usize {
+        // sampling a usize would give different results on 32 bit platforms
+        self.rng.gen_range(0, upper as u64) as usize
+    }
+}
@@ -0,0 +1,1696 @@
+use crate::{
+    ArtifactBoon, Challenge, ChallengeOdds, ChallengeResult, ClientToServerMessage, Condition,
+    Countdown, Deck, Dice, GameInfo, GameState, History, MentalCondition, Outcome, Player,
+    PlayerStats, Recap, RuleSettings, ServerToClientMessage, TakeoverRequest, REDACTED,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+mod takeover;
+#[cfg(test)]
+mod tests;
+mod timers;
+use timers::TimedPhase;
+
+/// Something that happened to a game, as seen by the rules.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    /// A client connected on behalf of the given player.
+    Connect { player_id: Uuid },
+    /// One of the clients of the given player went away.
+    Disconnect {
+        player_id: Uuid,
+        /// Seconds since the Unix epoch, the engine has no clock of its own
+        #[serde(default)]
+        at: u64,
+    },
+    Message {
+        player_id: Uuid,
+        message: ClientToServerMessage,
+    },
+    /// Time passed, only sent while [`GameEngine::is_counting_down`].
+    Tick { seconds: u64 },
+}
+
+/// What the host of a [`GameEngine`] has to do in response to an [`Event`].
+#[derive(Debug, Clone)]
+pub enum Effect {
+    /// Deliver the message to all clients of the given player.
+    Send {
+        player_id: Uuid,
+        message: ServerToClientMessage,
+    },
+    /// Deliver the message only to the client whose `Connect` caused this effect.
+    Reply(ServerToClientMessage),
+    /// Turn away the client whose `Connect` caused this effect.
+    Close,
+    /// Disconnect all clients of a player that was removed from the game.
+    DropClients { player_id: Uuid },
+    /// The game progressed in a way that is worth persisting.
+    Checkpoint,
+    /// The game is over, no further events will be accepted.
+    Finished { victory: bool },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Phase {
+    #[default]
+    PlayerSelection,
+    DefineEvil {
+        player_questions: HashMap<Uuid, Vec<(String, Option<String>)>>,
+    },
+    CharacterCreation,
+    CharacterIntroduction,
+    Temple(Room),
+    FinalBattle(FinalBattle),
+    Finished {
+        victory: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Room {
+    gms: Vec<Uuid>,
+    room_idx: usize,
+    successes: usize,
+    failures: usize,
+    challenge: Option<Challenge>,
+    challenge_result: Option<Vec<u8>>,
+    artifact_used: Option<ArtifactBoon>,
+}
+
+impl Room {
+    fn gm(&self) -> Uuid {
+        self.gms[self.room_idx]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FinalBattle {
+    gms: HashSet<Uuid>,
+    target_successes: usize,
+    successes: usize,
+    remaining_clues: Vec<String>,
+    challenge: Option<(Challenge, usize)>,
+    challenge_result: Option<Vec<u8>>,
+    artifact_used: Option<ArtifactBoon>,
+}
+
+/// The rules of the game, without any networking attached.
+///
+/// Every input is fed in as an [`Event`], every output is returned as a list of [`Effect`]s.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameEngine {
+    #[serde(default)]
+    rules: RuleSettings,
+    #[serde(default)]
+    deck: Deck,
+    /// Questions the players wrote themselves in the lobby, by author
+    #[serde(default)]
+    custom_questions: HashMap<Uuid, Vec<String>>,
+    players: HashMap<Uuid, Player>,
+    player_kick_votes: HashMap<Uuid, HashSet<Uuid>>,
+    /// The players don't want the game to show up in the list of open games
+    #[serde(default)]
+    unlisted: bool,
+    /// Everybody who isn't a player yet has to know this to connect
+    #[serde(default)]
+    password: Option<String>,
+    /// The questions about the ancient evil along with their answers
+    clues: Vec<(String, String)>,
+    phase: Phase,
+    /// Who GMed the rooms and how the challenges went, for the recap
+    #[serde(default)]
+    history: History,
+    /// Open connections, by player or by someone who waits to take over a player
+    #[serde(skip)]
+    connections: HashMap<Uuid, usize>,
+    #[serde(skip)]
+    takeovers: Vec<TakeoverRequest>,
+    /// For the players who have to get something done before the deadline of the current phase
+    #[serde(default)]
+    countdowns: HashMap<Uuid, Countdown>,
+    /// What the countdowns are for
+    #[serde(default)]
+    timed_phase: Option<TimedPhase>,
+}
+
+fn has_doubles(dice: &[u8]) -> bool {
+    let mut results = dice.to_vec();
+    results.sort_unstable();
+    results.windows(2).any(|pair| pair[0] == pair[1])
+}
+
+pub(crate) fn possessed_dice(dice: &[u8]) -> bool {
+    let ones = dice.iter().filter(|die| **die == 1).count();
+    if ones > 1 {
+        true
+    } else {
+        let twos = dice.iter().filter(|die| **die == 2).count();
+        twos > 1
+    }
+}
+
+pub(crate) fn check_success(dice: &[u8], artifact: Option<ArtifactBoon>) -> bool {
+    match artifact {
+        Some(ArtifactBoon::SuccessOnFive) => dice.contains(&5),
+        Some(ArtifactBoon::SuccessOnDoubles) => has_doubles(dice),
+        _ => dice.contains(&6),
+    }
+}
+
+/// Does not check whether the player has already used the artifact previously!
+pub(crate) fn check_can_use_artifact(dice: &[u8], artifact_boon: ArtifactBoon) -> bool {
+    let success = check_success(dice, None);
+    let possession = possessed_dice(dice);
+    if success && !possession {
+        false // no point in using it
+    } else if possession && artifact_boon == ArtifactBoon::Reroll {
+        true
+    } else if !success {
+        // check whether the artifact could make a difference
+        match artifact_boon {
+            ArtifactBoon::SuccessOnFive if !dice.contains(&5) => false,
+            ArtifactBoon::SuccessOnDoubles => has_doubles(dice),
+            _ => true,
+        }
+    } else {
+        false
+    }
+}
+
+pub(crate) fn is_alive(player: &Player) -> bool {
+    player.condition != Condition::Dead && player.mental_condition != MentalCondition::Possessed
+}
+
+fn dice_count(player: &Player, challenge: &Challenge) -> u8 {
+    player
+        .stats
+        .as_ref()
+        .unwrap()
+        .attributes
+        .get(&challenge.attribute)
+        .unwrap()
+        + if challenge.speciality_applies { 1 } else { 0 }
+        + if challenge.reputation_applies { 1 } else { 0 }
+}
+
+impl GameEngine {
+    pub fn new(rules: RuleSettings) -> Self {
+        Self {
+            rules,
+            ..Default::default()
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSettings {
+        &self.rules
+    }
+
+    pub fn players(&self) -> &HashMap<Uuid, Player> {
+        &self.players
+    }
+
+    pub fn is_unlisted(&self) -> bool {
+        self.unlisted
+    }
+
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+
+    /// A copy that can be journaled, without the password of the game.
+    pub fn redacted(&self) -> Self {
+        let mut engine = self.clone();
+        if let Some(password) = &mut engine.password {
+            *password = REDACTED.to_owned();
+        }
+        engine
+    }
+
+    /// Summary for the list of open games.
+    pub fn info(&self, name: &str) -> GameInfo {
+        GameInfo {
+            name: name.to_owned(),
+            phase: self.view(false).name().to_owned(),
+            players: self.players.len(),
+            max_players: self.rules.max_players,
+            joinable: matches!(self.phase, Phase::PlayerSelection)
+                && self.players.len() < self.rules.max_players,
+            private: self.password.is_some(),
+        }
+    }
+
+    /// The current state of the game, including the challenges only some of the players know about.
+    pub fn game_state(&self) -> GameState {
+        self.view(true)
+    }
+
+    /// The GM of the current room, or all of them during the final battle.
+    pub fn gms(&self) -> Vec<Uuid> {
+        match &self.phase {
+            Phase::Temple(room) => vec![room.gm()],
+            Phase::FinalBattle(battle) => {
+                let mut gms: Vec<Uuid> = battle.gms.iter().cloned().collect();
+                gms.sort();
+                gms
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// What someone who doesn't take part in the game may see of it.
+    pub fn public_state(&self) -> ServerToClientMessage {
+        self.state_message(self.view(false))
+    }
+
+    fn view(&self, knows_challenge: bool) -> GameState {
+        match &self.phase {
+            // the password only protects the game from people who aren't players
+            Phase::PlayerSelection => self.lobby_state(knows_challenge),
+            Phase::DefineEvil { .. } => GameState::DefineEvil,
+            Phase::CharacterCreation => GameState::CharacterCreation,
+            Phase::CharacterIntroduction => GameState::CharacterIntroduction,
+            Phase::Temple(room) => self.room_state(room, knows_challenge),
+            Phase::FinalBattle(battle) => Self::final_battle_state(battle, knows_challenge),
+            Phase::Finished { victory: true } => GameState::Victory,
+            Phase::Finished { victory: false } => GameState::Failure,
+        }
+    }
+
+    /// Feeds a single event into the game and returns what should happen as a consequence.
+    pub fn apply(&mut self, event: Event, dice: &mut impl Dice) -> Vec<Effect> {
+        let mut effects = Vec::new();
+        let disconnected_at = match &event {
+            Event::Disconnect { at, .. } => Some(*at),
+            _ => None,
+        };
+        match event {
+            Event::Tick { seconds } => self.tick(seconds, dice, &mut effects),
+            Event::Message {
+                player_id,
+                message:
+                    ClientToServerMessage::VoteSkip {
+                        player_id: skipped_id,
+                    },
+            } => self.vote_skip(player_id, skipped_id, dice, &mut effects),
+            event => self.dispatch(event, dice, &mut effects),
+        }
+        self.update_countdowns();
+        if self.update_presence(disconnected_at) && !matches!(self.phase, Phase::Finished { .. }) {
+            self.push_current_state(&mut effects);
+        }
+        self.refresh_pushed_states(&mut effects);
+        effects
+    }
+
+    fn dispatch(&mut self, event: Event, dice: &mut impl Dice, effects: &mut Vec<Effect>) {
+        if let Event::Disconnect { player_id, .. } = &event {
+            if let Some(connections) = self.connections.get_mut(player_id) {
+                *connections = connections.saturating_sub(1);
+            }
+        }
+        if self.handle_takeover(&event, dice, effects) {
+            return;
+        }
+        let presence_changed = match &event {
+            Event::Connect { player_id } | Event::Disconnect { player_id, .. } => {
+                self.players.contains_key(player_id)
+            }
+            Event::Message { .. } | Event::Tick { .. } => false,
+        };
+        self.phase = match std::mem::take(&mut self.phase) {
+            Phase::PlayerSelection => self.wait_for_players(event, dice, effects),
+            Phase::DefineEvil { player_questions } => {
+                self.define_evil(player_questions, event, dice, effects)
+            }
+            Phase::CharacterCreation => self.create_character(event, dice, effects),
+            Phase::CharacterIntroduction => self.introduce_characters(event, dice, effects),
+            Phase::Temple(room) => self.enter_temple(room, event, dice, effects),
+            Phase::FinalBattle(battle) => self.face_ancient_evil(battle, event, dice, effects),
+            Phase::Finished { victory } => self.finished(victory, event, effects),
+        };
+        if presence_changed {
+            self.update_takeovers(effects);
+        }
+    }
+
+    fn state_message(&self, game_state: GameState) -> ServerToClientMessage {
+        ServerToClientMessage::PushState {
+            players: self.players.clone(),
+            game_state,
+            rules: self.rules,
+            countdowns: self.countdowns.clone(),
+        }
+    }
+    fn push_state_all(&self, game_state: GameState, effects: &mut Vec<Effect>) {
+        let message = self.state_message(game_state);
+        for &player_id in self.players.keys() {
+            effects.push(Effect::Send {
+                player_id,
+                message: message.clone(),
+            });
+        }
+    }
+    /// Sends everybody the state of the game as they're allowed to see it, unless it's already on its way.
+    fn push_current_state(&self, effects: &mut Vec<Effect>) {
+        let pushed: HashSet<Uuid> = effects
+            .iter()
+            .filter_map(|effect| match effect {
+                Effect::Send {
+                    player_id,
+                    message: ServerToClientMessage::PushState { .. },
+                } => Some(*player_id),
+                _ => None,
+            })
+            .collect();
+        let challenged = match &self.phase {
+            Phase::Temple(room) => room.challenge.as_ref().map(|challenge| challenge.player_id),
+            Phase::FinalBattle(battle) => battle
+                .challenge
+                .as_ref()
+                .map(|(challenge, _)| challenge.player_id),
+            _ => None,
+        };
+        let gms = self.gms();
+        for &player_id in self.players.keys() {
+            if pushed.contains(&player_id) {
+                continue;
+            }
+            let game_state = match &self.phase {
+                // unlike spectators, everybody seated in the lobby may see the password
+                Phase::PlayerSelection => self.lobby_state(true),
+                _ => self.view(gms.contains(&player_id) || Some(player_id) == challenged),
+            };
+            Self::send_to(player_id, self.state_message(game_state), effects);
+        }
+    }
+    /// The phases don't know about presence and countdowns, so the states they pushed are brought up to date.
+    fn refresh_pushed_states(&self, effects: &mut [Effect]) {
+        for effect in effects.iter_mut() {
+            if let Effect::Send {
+                message:
+                    ServerToClientMessage::PushState {
+                        players,
+                        countdowns,
+                        ..
+                    },
+                ..
+            }
+            | Effect::Reply(ServerToClientMessage::PushState {
+                players,
+                countdowns,
+                ..
+            }) = effect
+            {
+                *players = self.players.clone();
+                *countdowns = self.countdowns.clone();
+            }
+        }
+    }
+    /// Marks who is connected, returns whether anybody came or went.
+    ///
+    /// `disconnected_at` is when the event being applied closed a connection.
+    fn update_presence(&mut self, disconnected_at: Option<u64>) -> bool {
+        let mut changed = false;
+        for (player_id, player) in self.players.iter_mut() {
+            let online = self.connections.get(player_id).cloned().unwrap_or(0) > 0;
+            if player.online != online {
+                player.online = online;
+                if !online {
+                    player.last_seen = disconnected_at.or(player.last_seen);
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+    fn send_to(player_id: Uuid, message: ServerToClientMessage, effects: &mut Vec<Effect>) {
+        effects.push(Effect::Send { player_id, message });
+    }
+    /// Registers another client for a known player, returns `false` for strangers.
+    fn connect_player(&mut self, player_id: Uuid) -> bool {
+        if self.players.contains_key(&player_id) {
+            *self.connections.entry(player_id).or_default() += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn reject_ongoing(effects: &mut Vec<Effect>) {
+        effects.push(Effect::Reply(ServerToClientMessage::GameIsOngoing));
+        effects.push(Effect::Close);
+    }
+    /// Player ids in a stable order, so that the dice have the same effect on every run.
+    fn player_ids(&self) -> Vec<Uuid> {
+        let mut player_ids: Vec<Uuid> = self.players.keys().cloned().collect();
+        player_ids.sort();
+        player_ids
+    }
+    /// Whether the player is played by the server.
+    pub fn is_bot(&self, player_id: &Uuid) -> bool {
+        self.players.get(player_id).is_some_and(|player| player.bot)
+    }
+    /// Dead and possessed characters have nothing left to say.
+    pub fn can_speak_in_character(&self, player_id: &Uuid) -> bool {
+        self.players.get(player_id).is_some_and(is_alive)
+    }
+    fn all_ready(&self) -> bool {
+        self.players.values().all(|player| player.ready)
+    }
+    fn reset_ready(&mut self) {
+        for player in self.players.values_mut() {
+            player.ready = false;
+        }
+    }
+    fn known_clues(&self, room_idx: usize) -> Vec<String> {
+        self.clues[0..room_idx]
+            .iter()
+            .map(|(_, clue)| clue.clone())
+            .collect()
+    }
+    fn lobby_state(&self, show_password: bool) -> GameState {
+        GameState::PlayerSelection {
+            player_kick_votes: self.player_kick_votes.clone(),
+            unlisted: self.unlisted,
+            password: self.password.clone().filter(|_| show_password),
+            deck: self.deck.info(),
+            custom_questions: self.custom_questions.values().map(Vec::len).sum(),
+        }
+    }
+    fn custom_questions_message(&self, player_id: &Uuid) -> ServerToClientMessage {
+        ServerToClientMessage::CustomQuestions {
+            questions: self
+                .custom_questions
+                .get(player_id)
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Questions available to be dealt out when the game starts.
+    fn question_count(&self) -> usize {
+        self.deck.questions.len() + self.custom_questions.values().map(Vec::len).sum::<usize>()
+    }
+
+    fn wait_for_players(
+        &mut self,
+        event: Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        match event {
+            Event::Connect { player_id } => {
+                if self.connect_player(player_id) {
+                    effects.push(Effect::Reply(self.state_message(self.lobby_state(true))));
+                    effects.push(Effect::Reply(self.custom_questions_message(&player_id)));
+                } else if self.players.len() >= self.rules.max_players {
+                    effects.push(Effect::Reply(ServerToClientMessage::GameIsFull));
+                    effects.push(Effect::Close);
+                } else {
+                    self.players.insert(player_id, Player::default());
+                    self.connections.insert(player_id, 1);
+                    self.push_state_all(self.lobby_state(true), effects);
+                }
+            }
+            Event::Disconnect { .. } | Event::Tick { .. } => {}
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::ReadyForGame => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        player.ready = true;
+                    }
+                    self.push_state_all(self.lobby_state(true), effects);
+                }
+                ClientToServerMessage::SetPlayerName { name } => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        player.name = name;
+                    }
+                    self.push_state_all(self.lobby_state(true), effects);
+                }
+                ClientToServerMessage::VoteKickPlayer {
+                    player_id: other_player_id,
+                } => {
+                    if player_id != other_player_id {
+                        let votes = self.player_kick_votes.entry(other_player_id).or_default();
+                        votes.insert(player_id);
+                        let connections = &self.connections;
+                        let players = &self.players;
+                        let online_voters: HashSet<_> = self
+                            .players
+                            .keys()
+                            .filter(|id| {
+                                **id != other_player_id
+                                    && connections.get(id).cloned().unwrap_or(0) > 0
+                                    && !players[id].bot
+                            })
+                            .cloned()
+                            .collect();
+                        let votes = votes.intersection(&online_voters).count();
+                        let voting_player_count = self
+                            .players
+                            .keys()
+                            .filter(|id| online_voters.contains(*id))
+                            .count();
+                        if votes >= voting_player_count {
+                            self.players.remove(&other_player_id);
+                            self.connections.remove(&other_player_id);
+                            self.custom_questions.remove(&other_player_id);
+                            for player_kick_votes in self.player_kick_votes.values_mut() {
+                                player_kick_votes.remove(&other_player_id);
+                            }
+                            effects.push(Effect::DropClients {
+                                player_id: other_player_id,
+                            });
+                        }
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::RevertVoteKickPlayer {
+                    player_id: other_player_id,
+                } => {
+                    if let Some(votes) = self.player_kick_votes.get_mut(&other_player_id) {
+                        votes.remove(&player_id);
+                    }
+                }
+                ClientToServerMessage::SeatBot { player_id: bot_id } => {
+                    if self.players.contains_key(&player_id)
+                        && !self.players.contains_key(&bot_id)
+                        && self.players.len() < self.rules.max_players
+                    {
+                        self.players.insert(
+                            bot_id,
+                            Player {
+                                bot: true,
+                                ..Default::default()
+                            },
+                        );
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::RemoveBot { player_id: bot_id } => {
+                    if self.players.contains_key(&player_id) && self.is_bot(&bot_id) {
+                        self.players.remove(&bot_id);
+                        self.connections.remove(&bot_id);
+                        self.custom_questions.remove(&bot_id);
+                        self.player_kick_votes.remove(&bot_id);
+                        effects.push(Effect::DropClients { player_id: bot_id });
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::SetUnlisted { unlisted } => {
+                    if self.players.contains_key(&player_id) {
+                        self.unlisted = unlisted;
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::AddCustomQuestion { question } => {
+                    let question = question.trim();
+                    if self.players.contains_key(&player_id) && !question.is_empty() {
+                        self.custom_questions
+                            .entry(player_id)
+                            .or_default()
+                            .push(question.to_owned());
+                        Self::send_to(
+                            player_id,
+                            self.custom_questions_message(&player_id),
+                            effects,
+                        );
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::RemoveCustomQuestion { idx } => {
+                    if let Some(questions) = self.custom_questions.get_mut(&player_id) {
+                        if idx < questions.len() {
+                            questions.remove(idx);
+                            Self::send_to(
+                                player_id,
+                                self.custom_questions_message(&player_id),
+                                effects,
+                            );
+                            self.push_state_all(self.lobby_state(true), effects);
+                        }
+                    }
+                }
+                ClientToServerMessage::SetDeck { deck } => {
+                    if self.players.contains_key(&player_id) {
+                        self.deck = deck;
+                        self.reset_ready();
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::SetRules { rules } => {
+                    // nobody who is already seated can be left without a place
+                    if self.players.contains_key(&player_id)
+                        && rules.is_valid()
+                        && rules.max_players >= self.players.len()
+                    {
+                        self.rules = rules;
+                        // everybody has to agree to play by the new rules
+                        self.reset_ready();
+                        self.push_state_all(self.lobby_state(true), effects);
+                    }
+                }
+                ClientToServerMessage::SetPassword { password }
+                    if self.players.contains_key(&player_id) =>
+                {
+                    self.password = password.filter(|password| !password.is_empty());
+                    self.push_state_all(self.lobby_state(true), effects);
+                }
+                _ => {}
+            },
+        }
+        if self.players.len() >= self.rules.min_players
+            && self.players.len() <= self.rules.max_players
+            && self.question_count() >= self.players.len() * self.rules.questions_per_player
+            && self.all_ready()
+        {
+            self.start_define_evil(dice, effects)
+        } else {
+            Phase::PlayerSelection
+        }
+    }
+
+    fn start_define_evil(&mut self, dice: &mut impl Dice, effects: &mut Vec<Effect>) -> Phase {
+        self.reset_ready();
+        self.player_kick_votes.clear();
+        self.push_state_all(GameState::DefineEvil, effects);
+
+        let mut custom_questions: Vec<(String, Option<Uuid>)> = self
+            .custom_questions
+            .drain()
+            .flat_map(|(author, questions)| {
+                questions
+                    .into_iter()
+                    .map(move |question| (question, Some(author)))
+            })
+            .collect();
+        // the map has no stable order
+        custom_questions.sort();
+        dice.shuffle(&mut custom_questions);
+        let mut deck_questions: Vec<(String, Option<Uuid>)> = self
+            .deck
+            .questions
+            .iter()
+            .map(|question| (question.clone(), None))
+            .collect();
+        dice.shuffle(&mut deck_questions);
+        // the ones the players wrote come first, so that all of them get answered
+        let mut pool: Vec<_> = custom_questions.into_iter().chain(deck_questions).collect();
+
+        let mut player_questions: HashMap<Uuid, Vec<(String, Option<String>)>> = HashMap::new();
+        for player_id in self.player_ids() {
+            let mut questions = Vec::new();
+            while questions.len() < self.rules.questions_per_player && !pool.is_empty() {
+                // nobody has to answer their own question, unless there's nothing else left
+                let idx = pool
+                    .iter()
+                    .position(|(_, author)| *author != Some(player_id))
+                    .unwrap_or(0);
+                questions.push((pool.remove(idx).0, None));
+            }
+            player_questions.insert(player_id, questions);
+        }
+
+        for (&player_id, questions) in &player_questions {
+            let payload: Vec<(String, Option<String>)> = questions
+                .iter()
+                .map(|(question, _)| (question.clone(), None))
+                .collect();
+            Self::send_to(
+                player_id,
+                ServerToClientMessage::Questions { questions: payload },
+                effects,
+            );
+        }
+        effects.push(Effect::Checkpoint);
+        Phase::DefineEvil { player_questions }
+    }
+
+    fn define_evil(
+        &mut self,
+        mut player_questions: HashMap<Uuid, Vec<(String, Option<String>)>>,
+        event: Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        match event {
+            Event::Connect { player_id } => {
+                if self.connect_player(player_id) {
+                    effects.push(Effect::Reply(self.state_message(GameState::DefineEvil)));
+                    if let Some(questions) = player_questions.get(&player_id) {
+                        let payload = questions
+                            .iter()
+                            .map(|(question, answer)| (question.clone(), answer.clone()))
+                            .collect();
+                        effects.push(Effect::Reply(ServerToClientMessage::Questions {
+                            questions: payload,
+                        }));
+                    }
+                } else {
+                    Self::reject_ongoing(effects);
+                }
+            }
+            Event::Disconnect { .. } | Event::Tick { .. } => {}
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::Answers { answers } => {
+                    if let Some(player) = self.players.get(&player_id) {
+                        if !player.ready {
+                            if let Some(questions) = player_questions.get_mut(&player_id) {
+                                let mut answer_iter = answers.into_iter();
+                                for question in questions.iter_mut() {
+                                    if let Some(answer) = answer_iter.next() {
+                                        if !answer.is_empty() {
+                                            question.1 = Some(answer);
+                                        } else {
+                                            question.1 = None;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::RollInspiration { question_idx } => {
+                    if let Some((question, _)) = player_questions
+                        .get(&player_id)
+                        .and_then(|questions| questions.get(question_idx))
+                    {
+                        let prompt = self.deck.roll_inspiration(question, dice);
+                        Self::send_to(
+                            player_id,
+                            ServerToClientMessage::Inspiration {
+                                question_idx,
+                                prompt,
+                            },
+                            effects,
+                        );
+                    }
+                }
+                ClientToServerMessage::ReadyForGame => {
+                    let mut ready = false;
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if !player.ready {
+                            if let Some(questions) = player_questions.get(&player_id) {
+                                ready = questions.iter().all(|(_, answer)| {
+                                    answer.as_ref().filter(|a| !a.is_empty()).is_some()
+                                });
+                                if ready {
+                                    player.ready = true;
+                                }
+                            }
+                        }
+                    }
+                    if ready {
+                        self.push_state_all(GameState::DefineEvil, effects);
+                        effects.push(Effect::Checkpoint);
+                    }
+                }
+                _ => {}
+            },
+        }
+        if self.all_ready() {
+            let mut player_questions: Vec<_> = player_questions.into_iter().collect();
+            player_questions.sort_by_key(|(player_id, _)| *player_id);
+            self.clues.extend(
+                player_questions
+                    .into_iter()
+                    .flat_map(|(_, questions)| questions)
+                    .filter_map(|(question, answer)| answer.map(|answer| (question, answer))),
+            );
+            dice.shuffle(&mut self.clues);
+            self.start_create_character(effects)
+        } else {
+            Phase::DefineEvil { player_questions }
+        }
+    }
+
+    fn start_create_character(&mut self, effects: &mut Vec<Effect>) -> Phase {
+        self.reset_ready();
+        self.push_state_all(GameState::CharacterCreation, effects);
+        effects.push(Effect::Checkpoint);
+        Phase::CharacterCreation
+    }
+
+    fn create_character(
+        &mut self,
+        event: Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        match event {
+            Event::Connect { player_id } => {
+                if self.connect_player(player_id) {
+                    effects.push(Effect::Reply(
+                        self.state_message(GameState::CharacterCreation),
+                    ));
+                } else {
+                    Self::reject_ongoing(effects);
+                }
+            }
+            Event::Disconnect { .. } | Event::Tick { .. } => {}
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::SetCharacter { stats } => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if !player.ready {
+                            player.stats = Some(stats);
+                        }
+                    }
+                    self.push_state_all(GameState::CharacterCreation, effects);
+                }
+                ClientToServerMessage::RollCharacter => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if !player.ready {
+                            player.stats = Some(PlayerStats::random(dice));
+                        }
+                    }
+                    self.push_state_all(GameState::CharacterCreation, effects);
+                }
+                ClientToServerMessage::ReadyForGame => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if player.stats.as_ref().map(|stats| stats.is_valid()) == Some(true) {
+                            player.ready = true;
+                            self.push_state_all(GameState::CharacterCreation, effects);
+                            effects.push(Effect::Checkpoint);
+                        }
+                    }
+                }
+                _ => {}
+            },
+        }
+        if self.all_ready() {
+            self.start_introduce_characters(effects)
+        } else {
+            Phase::CharacterCreation
+        }
+    }
+
+    fn start_introduce_characters(&mut self, effects: &mut Vec<Effect>) -> Phase {
+        self.reset_ready();
+        self.push_state_all(GameState::CharacterIntroduction, effects);
+        effects.push(Effect::Checkpoint);
+        Phase::CharacterIntroduction
+    }
+
+    fn introduce_characters(
+        &mut self,
+        event: Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        match event {
+            Event::Connect { player_id } => {
+                if self.connect_player(player_id) {
+                    effects.push(Effect::Reply(
+                        self.state_message(GameState::CharacterIntroduction),
+                    ));
+                } else {
+                    Self::reject_ongoing(effects);
+                }
+            }
+            Event::Disconnect { .. } | Event::Tick { .. } => {}
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::ReadyForGame => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        player.ready = true;
+                    }
+                    self.push_state_all(GameState::CharacterIntroduction, effects);
+                    effects.push(Effect::Checkpoint);
+                }
+                _ => {}
+            },
+        }
+        if self.all_ready() {
+            self.start_enter_temple(dice, effects)
+        } else {
+            Phase::CharacterIntroduction
+        }
+    }
+
+    fn start_enter_temple(&mut self, dice: &mut impl Dice, effects: &mut Vec<Effect>) -> Phase {
+        let mut gms = self.player_ids();
+        dice.shuffle(&mut gms);
+        let room = Room {
+            gms,
+            room_idx: 0,
+            successes: 0,
+            failures: 0,
+            challenge: None,
+            challenge_result: None,
+            artifact_used: None,
+        };
+        self.start_room(&room, effects);
+        effects.push(Effect::Checkpoint);
+        Phase::Temple(room)
+    }
+
+    fn start_room(&mut self, room: &Room, effects: &mut Vec<Effect>) {
+        self.history.enter_room(room.gm());
+        Self::send_to(
+            room.gm(),
+            ServerToClientMessage::PushClue {
+                clue: self.clues[room.room_idx].1.clone(),
+            },
+            effects,
+        );
+        self.push_room_state(room, effects);
+    }
+
+    fn push_room_state(&mut self, room: &Room, effects: &mut Vec<Effect>) {
+        let gm = room.gm();
+        let challenged = room.challenge.as_ref().map(|challenge| challenge.player_id);
+        let waiting_for = challenged.unwrap_or(gm);
+        for (&player_id, player) in self.players.iter_mut() {
+            player.ready = player_id != waiting_for;
+        }
+
+        for &player_id in self.players.keys() {
+            // only the GM and the challenged player know about the current challenge
+            let knows_challenge = player_id == gm || Some(player_id) == challenged;
+            Self::send_to(
+                player_id,
+                self.state_message(self.room_state(room, knows_challenge)),
+                effects,
+            );
+        }
+    }
+
+    fn room_state(&self, room: &Room, knows_challenge: bool) -> GameState {
+        GameState::Room {
+            room_idx: room.room_idx,
+            gm: room.gm(),
+            successes: room.successes,
+            failures: room.failures,
+            challenge: if knows_challenge {
+                room.challenge.clone()
+            } else {
+                None
+            },
+            known_clues: self.known_clues(room.room_idx),
+        }
+    }
+
+    /// The chances of a challenge, for the player who has been offered it.
+    fn challenge_odds(&self, challenge: &Challenge) -> Option<ServerToClientMessage> {
+        self.players
+            .get(&challenge.player_id)
+            .and_then(|player| ChallengeOdds::new(player, challenge))
+            .map(ServerToClientMessage::ChallengeOdds)
+    }
+
+    /// The result of the current challenge, for a player who reconnected while deciding what to do about it.
+    fn pending_challenge_result(
+        &self,
+        player_id: Uuid,
+        challenge_result: &[u8],
+        artifact_used: Option<ArtifactBoon>,
+    ) -> ServerToClientMessage {
+        let (artifact_boon, player_artifact_used) = self
+            .players
+            .get(&player_id)
+            .map(|player| {
+                (
+                    player.stats.as_ref().map(|stats| stats.artifact_boon),
+                    player.artifact_used,
+                )
+            })
+            .unwrap_or((None, true));
+        let can_use_artifact = artifact_boon
+            .map(|artifact_boon| {
+                !player_artifact_used && check_can_use_artifact(challenge_result, artifact_boon)
+            })
+            .unwrap_or(false);
+        ServerToClientMessage::ChallengeResult(ChallengeResult {
+            possession: possessed_dice(challenge_result),
+            success: check_success(challenge_result, artifact_used),
+            can_use_artifact,
+            rolls: challenge_result.to_vec(),
+        })
+    }
+
+    /// Rolls the dice for an accepted challenge.
+    ///
+    /// Returns the result message and whether the challenge has been resolved by this roll.
+    fn roll_challenge(
+        player: &mut Player,
+        challenge: &Challenge,
+        challenge_result: &mut Option<Vec<u8>>,
+        dice: &mut impl Dice,
+    ) -> (ChallengeResult, bool) {
+        let mut can_use_artifact = false;
+        let mut resolved = false;
+        let results = dice.roll_d6(dice_count(player, challenge) as _);
+        let success = check_success(&results, None);
+        let possession = possessed_dice(&results);
+        if success && !possession {
+            resolved = true;
+        } else if !player.artifact_used {
+            let artifact_boon = player.stats.as_ref().unwrap().artifact_boon;
+            if possession && artifact_boon == ArtifactBoon::Reroll {
+                can_use_artifact = true;
+            } else if !success {
+                // check whether the artifact could make a difference
+                match artifact_boon {
+                    ArtifactBoon::SuccessOnFive if !results.contains(&5) => {
+                        // can't use artifact
+                    }
+                    ArtifactBoon::SuccessOnDoubles => {
+                        can_use_artifact = has_doubles(&results);
+                    }
+                    _ => {
+                        can_use_artifact = true;
+                    }
+                }
+            }
+        }
+        if !success
+            || (!player.artifact_used
+                && player.stats.as_ref().unwrap().artifact_boon == ArtifactBoon::Reroll
+                && possession)
+        {
+            // the player can either use the artifact (if possible) or choose to
+            // take a hit to avoid the failure
+            // if it's just about possession, only the reroll artifact can help
+            *challenge_result = Some(results.clone());
+        }
+        (
+            ChallengeResult {
+                rolls: results,
+                success,
+                possession,
+                can_use_artifact,
+            },
+            resolved,
+        )
+    }
+
+    /// Applies the artifact to the previous roll, returns the new results.
+    fn use_artifact(
+        player: &mut Player,
+        challenge_result: Vec<u8>,
+        dice: &mut impl Dice,
+    ) -> ChallengeResult {
+        player.artifact_used = true;
+        let artifact_boon = player.stats.as_ref().unwrap().artifact_boon;
+        let results = match artifact_boon {
+            ArtifactBoon::Reroll => dice.roll_d6(challenge_result.len()),
+            ArtifactBoon::RollWithPlusTwo => challenge_result
+                .into_iter()
+                .chain(dice.roll_d6(2))
+                .collect(),
+            ArtifactBoon::SuccessOnFive | ArtifactBoon::SuccessOnDoubles => challenge_result,
+        };
+        ChallengeResult {
+            success: check_success(&results, Some(artifact_boon)),
+            possession: possessed_dice(&results),
+            rolls: results,
+            can_use_artifact: false,
+        }
+    }
+
+    fn enter_temple(
+        &mut self,
+        mut room: Room,
+        event: Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        let gm = room.gm();
+        let resolved_challenges = room.successes + room.failures;
+        let mut proceed = false;
+        let mut checkpoint = false;
+
+        match event {
+            Event::Connect { player_id } => {
+                if self.connect_player(player_id) {
+                    if player_id == gm {
+                        effects.push(Effect::Reply(ServerToClientMessage::PushClue {
+                            clue: self.clues[room.room_idx].1.clone(),
+                        }));
+                    }
+                    if let Some(current_challenge) = &room.challenge {
+                        if current_challenge.player_id == player_id {
+                            if let Some(challenge_result) = &room.challenge_result {
+                                effects.push(Effect::Reply(self.pending_challenge_result(
+                                    player_id,
+                                    challenge_result,
+                                    room.artifact_used,
+                                )));
+                            } else if let Some(odds) = self.challenge_odds(current_challenge) {
+                                effects.push(Effect::Reply(odds));
+                            }
+                        }
+                    }
+                } else {
+                    Self::reject_ongoing(effects);
+                }
+            }
+            Event::Disconnect { .. } | Event::Tick { .. } => return Phase::Temple(room),
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::RejectClue if player_id == gm => {
+                    if room.room_idx > 0
+                        && self.clues.len() > self.players.len()
+                        && room.successes + room.failures == 0
+                    {
+                        // clue doesn't fit with existing lore, remove it
+                        self.clues.remove(room.room_idx);
+                        Self::send_to(
+                            gm,
+                            ServerToClientMessage::PushClue {
+                                clue: self.clues[room.room_idx].1.clone(),
+                            },
+                            effects,
+                        );
+                        checkpoint = true;
+                    } else {
+                        // either there's no existing lore yet, or we don't have any more clues left to discard
+                        Self::send_to(
+                            player_id,
+                            ServerToClientMessage::ClueRejectionRejected,
+                            effects,
+                        );
+                    }
+                }
+                ClientToServerMessage::OfferChallenge { challenge }
+                    if room.successes < self.rules.successes_needed =>
+                {
+                    if player_id == gm && challenge.player_id != gm {
+                        if let Some(player) = self.players.get(&challenge.player_id) {
+                            if is_alive(player) {
+                                if let Some(odds) = self.challenge_odds(&challenge) {
+                                    Self::send_to(challenge.player_id, odds, effects);
+                                }
+                                room.challenge = Some(challenge);
+                            }
+                        }
+                    } else {
+                        log::error!(
+                            "Invalid message received: OfferChallenge from {} for {}",
+                            player_id,
+                            challenge.player_id
+                        );
+                    }
+                }
+                ClientToServerMessage::ChallengeAccepted => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if let Some(challenge) = &room.challenge {
+                            if challenge.player_id == player_id {
+                                let (result, resolved) = Self::roll_challenge(
+                                    player,
+                                    challenge,
+                                    &mut room.challenge_result,
+                                    dice,
+                                );
+                                self.history
+                                    .roll(challenge.clone(), None, result.rolls.clone());
+                                if resolved {
+                                    self.history.resolve(Outcome::Success);
+                                    room.successes += 1;
+                                    room.challenge = None;
+                                } else if result.success && !result.can_use_artifact {
+                                    // Nothing the player can do about this result
+                                    self.history.resolve(Outcome::Success);
+                                    room.successes += 1;
+                                    room.challenge = None;
+                                    player.mental_condition = player.mental_condition.take_hit();
+                                }
+                                Self::send_to(
+                                    player_id,
+                                    ServerToClientMessage::ChallengeResult(result),
+                                    effects,
+                                );
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::ChallengeRejected => {
+                    if (player_id == gm
+                        || room.challenge.as_ref().map(|challenge| challenge.player_id)
+                            == Some(player_id))
+                        && room.challenge_result.is_none()
+                    {
+                        room.challenge = None;
+                        Self::send_to(player_id, ServerToClientMessage::AbortedChallenge, effects);
+                        if player_id != gm {
+                            Self::send_to(gm, ServerToClientMessage::AbortedChallenge, effects);
+                        }
+                    }
+                }
+                ClientToServerMessage::AcceptFate => {
+                    if room.challenge.as_ref().map(|challenge| challenge.player_id)
+                        == Some(player_id)
+                    {
+                        if let Some(challenge_result) = room.challenge_result.take() {
+                            if check_success(&challenge_result, room.artifact_used.take()) {
+                                self.history.resolve(Outcome::Success);
+                                room.successes += 1;
+                            } else {
+                                self.history.resolve(Outcome::Failure);
+                                room.failures += 1;
+                            }
+                            if possessed_dice(&challenge_result) {
+                                if let Some(player) = self.players.get_mut(&player_id) {
+                                    player.mental_condition = player.mental_condition.take_hit();
+                                }
+                            }
+                            room.challenge = None;
+                        } else {
+                            log::error!(
+                                "Accepted fate, but there was no current challenge result available."
+                            );
+                        }
+                    } else {
+                        log::error!(
+                            "Accepted fate by someone who wasn't challenged at the moment."
+                        );
+                    }
+                }
+                ClientToServerMessage::TakeWound => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if let Some(challenge_result) = room.challenge_result.take() {
+                            self.history.resolve(Outcome::Wounded);
+                            player.condition = player.condition.take_hit();
+                            if possessed_dice(&challenge_result) {
+                                player.mental_condition = player.mental_condition.take_hit();
+                            }
+                            room.successes += 1;
+                            room.challenge = None;
+                            room.artifact_used = None;
+                        }
+                    }
+                }
+                ClientToServerMessage::UseArtifact => {
+                    if room
+                        .challenge
+                        .as_ref()
+                        .map(|challenge| challenge.player_id == player_id)
+                        .unwrap_or(false)
+                    {
+                        if let Some(player) = self.players.get_mut(&player_id) {
+                            if let Some(challenge_result) = room.challenge_result.take() {
+                                let result = Self::use_artifact(player, challenge_result, dice);
+                                self.history.reroll(result.rolls.clone());
+                                if result.success {
+                                    self.history.resolve(Outcome::Success);
+                                    room.successes += 1;
+                                    room.challenge = None;
+                                    if result.possession {
+                                        player.mental_condition =
+                                            player.mental_condition.take_hit();
+                                    }
+                                } else {
+                                    room.artifact_used =
+                                        Some(player.stats.as_ref().unwrap().artifact_boon);
+                                    room.challenge_result = Some(result.rolls.clone());
+                                }
+                                Self::send_to(
+                                    player_id,
+                                    ServerToClientMessage::ChallengeResult(result),
+                                    effects,
+                                );
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::ReadyForGame
+                    if player_id == gm && room.successes >= self.rules.successes_needed =>
+                {
+                    proceed = true;
+                }
+                _ => {}
+            },
+        }
+        if room.failures >= self.rules.failures_needed {
+            return self.end(false, effects);
+        }
+        let next_room = if proceed {
+            room.room_idx + 1
+        } else {
+            room.room_idx
+        };
+        let alive_players: Vec<Uuid> = self
+            .players
+            .iter()
+            .filter(|(_, player)| is_alive(player))
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        if alive_players.is_empty() // everybody is dead
+            || (alive_players.len() == 1 && room.gms[next_room..].contains(&alive_players[0]))
+        // there's only a single player left and that player is supposed to GM the current or a future room
+        {
+            return self.end(false, effects);
+        }
+        if proceed {
+            if next_room >= room.gms.len() {
+                return self.start_face_ancient_evil(dice, effects);
+            }
+            room = Room {
+                gms: room.gms,
+                room_idx: next_room,
+                successes: 0,
+                failures: 0,
+                challenge: None,
+                challenge_result: None,
+                artifact_used: None,
+            };
+            self.start_room(&room, effects);
+            effects.push(Effect::Checkpoint);
+        } else {
+            if checkpoint || room.successes + room.failures != resolved_challenges {
+                effects.push(Effect::Checkpoint);
+            }
+            self.push_room_state(&room, effects);
+        }
+        Phase::Temple(room)
+    }
+
+    fn start_face_ancient_evil(
+        &mut self,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        let mut gms: HashSet<_> = self
+            .players
+            .iter()
+            .filter(|(_, player)| !is_alive(player))
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        if gms.is_empty() {
+            let player_ids = self.player_ids();
+            gms.insert(player_ids[dice.pick(player_ids.len())]);
+        }
+        log::debug!("GMs are now {:?}", gms);
+        let mut recorded_gms: Vec<Uuid> = gms.iter().cloned().collect();
+        recorded_gms.sort();
+        self.history.face_ancient_evil(recorded_gms);
+        let battle = FinalBattle {
+            gms,
+            target_successes: self.rules.final_battle_target(self.players.len()),
+            successes: 0,
+            remaining_clues: self.known_clues(self.players.len()),
+            challenge: None,
+            challenge_result: None,
+            artifact_used: None,
+        };
+        self.push_final_battle_state(&battle, effects);
+        effects.push(Effect::Checkpoint);
+        Phase::FinalBattle(battle)
+    }
+
+    fn push_final_battle_state(&mut self, battle: &FinalBattle, effects: &mut Vec<Effect>) {
+        let challenged = battle
+            .challenge
+            .as_ref()
+            .map(|(challenge, _)| challenge.player_id);
+        for (player_id, player) in self.players.iter_mut() {
+            player.ready = if let Some(challenged) = challenged {
+                *player_id != challenged
+            } else {
+                !battle.gms.contains(player_id)
+            };
+        }
+
+        for &player_id in self.players.keys() {
+            // only the GMs and the challenged player know about the current challenge
+            let knows_challenge = battle.gms.contains(&player_id) || Some(player_id) == challenged;
+            Self::send_to(
+                player_id,
+                self.state_message(Self::final_battle_state(battle, knows_challenge)),
+                effects,
+            );
+        }
+    }
+
+    fn final_battle_state(battle: &FinalBattle, knows_challenge: bool) -> GameState {
+        let challenge = battle.challenge.as_ref().filter(|_| knows_challenge);
+        GameState::FinalBattle {
+            remaining_clues: battle.remaining_clues.clone(),
+            gms: battle.gms.clone(),
+            successes: battle.successes,
+            target_successes: battle.target_successes,
+            challenge: challenge.map(|(challenge, _)| challenge.clone()),
+            chosen_clue: challenge.map(|(_, chosen_clue)| *chosen_clue),
+        }
+    }
+
+    fn face_ancient_evil(
+        &mut self,
+        mut battle: FinalBattle,
+        event: Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> Phase {
+        let unresolved_clues = battle.remaining_clues.len();
+
+        match event {
+            Event::Connect { player_id } => {
+                if self.connect_player(player_id) {
+                    if let Some((current_challenge, _)) = &battle.challenge {
+                        if current_challenge.player_id == player_id {
+                            if let Some(challenge_result) = &battle.challenge_result {
+                                effects.push(Effect::Reply(self.pending_challenge_result(
+                                    player_id,
+                                    challenge_result,
+                                    battle.artifact_used,
+                                )));
+                            } else if let Some(odds) = self.challenge_odds(current_challenge) {
+                                effects.push(Effect::Reply(odds));
+                            }
+                        }
+                    }
+                } else {
+                    Self::reject_ongoing(effects);
+                }
+            }
+            Event::Disconnect { .. } | Event::Tick { .. } => return Phase::FinalBattle(battle),
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::OfferChallengeFinal {
+                    challenge,
+                    clue_idx,
+                } => {
+                    if battle.gms.contains(&player_id)
+                        && !battle.gms.contains(&challenge.player_id)
+                        && clue_idx < battle.remaining_clues.len()
+                    {
+                        if let Some(player) = self.players.get(&challenge.player_id) {
+                            if is_alive(player) {
+                                if let Some(odds) = self.challenge_odds(&challenge) {
+                                    Self::send_to(challenge.player_id, odds, effects);
+                                }
+                                battle.challenge = Some((challenge, clue_idx));
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::ChallengeAccepted => {
+                    if let Some(player) = self.players.get_mut(&player_id) {
+                        if let Some((challenge, clue_idx)) = &battle.challenge {
+                            if challenge.player_id == player_id {
+                                let (result, resolved) = Self::roll_challenge(
+                                    player,
+                                    challenge,
+                                    &mut battle.challenge_result,
+                                    dice,
+                                );
+                                self.history.roll(
+                                    challenge.clone(),
+                                    Some(battle.remaining_clues[*clue_idx].clone()),
+                                    result.rolls.clone(),
+                                );
+                                if resolved {
+                                    self.history.resolve(Outcome::Success);
+                                    battle.successes += 1;
+                                    battle.remaining_clues.remove(*clue_idx);
+                                    battle.challenge = None;
+                                } else if result.success && !result.can_use_artifact {
+                                    // Nothing the player can do about this result
+                                    self.history.resolve(Outcome::Success);
+                                    battle.successes += 1;
+                                    battle.remaining_clues.remove(*clue_idx);
+                                    battle.challenge = None;
+                                    player.mental_condition = player.mental_condition.take_hit();
+                                }
+                                Self::send_to(
+                                    player_id,
+                                    ServerToClientMessage::ChallengeResult(result),
+                                    effects,
+                                );
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::ChallengeRejected => {
+                    if (battle.gms.contains(&player_id)
+                        || battle
+                            .challenge
+                            .as_ref()
+                            .map(|(challenge, _)| challenge.player_id)
+                            == Some(player_id))
+                        && battle.challenge_result.is_none()
+                    {
+                        battle.challenge = None;
+                        Self::send_to(player_id, ServerToClientMessage::AbortedChallenge, effects);
+                        for gm in &battle.gms {
+                            if *gm != player_id {
+                                Self::send_to(
+                                    *gm,
+                                    ServerToClientMessage::AbortedChallenge,
+                                    effects,
+                                );
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::UseArtifact => {
+                    if let Some((challenge, clue_idx)) = &battle.challenge {
+                        if challenge.player_id == player_id {
+                            if let Some(challenge_result) = battle.challenge_result.take() {
+                                if let Some(player) = self.players.get_mut(&player_id) {
+                                    let result = Self::use_artifact(player, challenge_result, dice);
+                                    self.history.reroll(result.rolls.clone());
+                                    if result.success {
+                                        self.history.resolve(Outcome::Success);
+                                        battle.successes += 1;
+                                        battle.remaining_clues.remove(*clue_idx);
+                                        battle.challenge_result = None;
+                                        battle.challenge = None;
+                                        if result.possession {
+                                            player.mental_condition =
+                                                player.mental_condition.take_hit();
+                                        }
+                                    } else {
+                                        battle.artifact_used =
+                                            Some(player.stats.as_ref().unwrap().artifact_boon);
+                                        battle.challenge_result = Some(result.rolls.clone());
+                                    }
+                                    Self::send_to(
+                                        player_id,
+                                        ServerToClientMessage::ChallengeResult(result),
+                                        effects,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::TakeWound => {
+                    if let Some((challenge, clue_idx)) = &battle.challenge {
+                        if challenge.player_id == player_id {
+                            if let Some(player) = self.players.get_mut(&player_id) {
+                                self.history.resolve(Outcome::Wounded);
+                                battle.successes += 1;
+                                battle.remaining_clues.remove(*clue_idx);
+
+                                player.condition = player.condition.take_hit();
+                                battle.challenge_result = None;
+                                battle.challenge = None;
+                            }
+                        }
+                    }
+                }
+                ClientToServerMessage::AcceptFate => {
+                    if let Some(challenge_result) = battle.challenge_result.take() {
+                        if let Some((_, clue_idx)) = battle.challenge.take() {
+                            if check_success(&challenge_result, battle.artifact_used.take()) {
+                                self.history.resolve(Outcome::Success);
+                                battle.successes += 1;
+                            } else {
+                                self.history.resolve(Outcome::Failure);
+                            }
+                            battle.remaining_clues.remove(clue_idx);
+
+                            if possessed_dice(&challenge_result) {
+                                if let Some(player) = self.players.get_mut(&player_id) {
+                                    player.mental_condition = player.mental_condition.take_hit();
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            },
+        }
+        if battle.successes < battle.target_successes
+            && battle.remaining_clues.len() < (battle.target_successes - battle.successes)
+        {
+            // unwinnable situation
+            return self.end(false, effects);
+        }
+
+        let alive_players = self
+            .players
+            .iter()
+            .filter(|(player_id, player)| !battle.gms.contains(*player_id) && is_alive(player));
+        if alive_players.count() == 0 {
+            return self.end(false, effects);
+        }
+        if battle.successes >= battle.target_successes {
+            return self.end(true, effects);
+        }
+
+        if battle.remaining_clues.len() != unresolved_clues {
+            effects.push(Effect::Checkpoint);
+        }
+        self.push_final_battle_state(&battle, effects);
+        Phase::FinalBattle(battle)
+    }
+
+    /// Everything about the game there is to tell, `None` until it is over.
+    pub fn recap(&self) -> Option<Recap> {
+        match self.phase {
+            Phase::Finished { victory } => Some(self.make_recap(victory)),
+            _ => None,
+        }
+    }
+
+    fn make_recap(&self, victory: bool) -> Recap {
+        Recap {
+            victory,
+            clues: self.clues.clone(),
+            players: self.players.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    fn end(&mut self, victory: bool, effects: &mut Vec<Effect>) -> Phase {
+        // before the new state, the clients hang up as soon as they see that the game is over
+        let recap = self.make_recap(victory);
+        for player_id in self.player_ids() {
+            Self::send_to(
+                player_id,
+                ServerToClientMessage::Recap(recap.clone()),
+                effects,
+            );
+        }
+        self.push_state_all(
+            if victory {
+                GameState::Victory
+            } else {
+                GameState::Failure
+            },
+            effects,
+        );
+        effects.push(Effect::Finished { victory });
+        Phase::Finished { victory }
+    }
+
+    fn finished(&mut self, victory: bool, event: Event, effects: &mut Vec<Effect>) -> Phase {
+        if let Event::Connect { player_id } = event {
+            if self.connect_player(player_id) {
+                effects.push(Effect::Reply(ServerToClientMessage::Recap(
+                    self.make_recap(victory),
+                )));
+                effects.push(Effect::Reply(self.state_message(if victory {
+                    GameState::Victory
+                } else {
+                    GameState::Failure
+                })));
+            } else {
+                Self::reject_ongoing(effects);
+            }
+        }
+        Phase::Finished { victory }
+    }
+}
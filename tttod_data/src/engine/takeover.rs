@@ -0,0 +1,212 @@
+use super::{Effect, Event, GameEngine, Phase};
+use crate::{ClientToServerMessage, Dice, ServerToClientMessage, TakeoverRequest};
+use uuid::Uuid;
+
+impl Phase {
+    /// Hands everything the phase remembers about a player over to somebody else.
+    fn replace_player(&mut self, old_id: Uuid, new_id: Uuid) {
+        let replace = |player_id: &mut Uuid| {
+            if *player_id == old_id {
+                *player_id = new_id;
+            }
+        };
+        match self {
+            Phase::DefineEvil { player_questions } => {
+                if let Some(questions) = player_questions.remove(&old_id) {
+                    player_questions.insert(new_id, questions);
+                }
+            }
+            Phase::Temple(room) => {
+                room.gms.iter_mut().for_each(replace);
+                if let Some(challenge) = &mut room.challenge {
+                    replace(&mut challenge.player_id);
+                }
+            }
+            Phase::FinalBattle(battle) => {
+                if battle.gms.remove(&old_id) {
+                    battle.gms.insert(new_id);
+                }
+                if let Some((challenge, _)) = &mut battle.challenge {
+                    replace(&mut challenge.player_id);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl GameEngine {
+    pub(super) fn is_online(&self, player_id: &Uuid) -> bool {
+        self.connections.get(player_id).cloned().unwrap_or(0) > 0
+    }
+    fn offline_players(&self) -> Vec<Uuid> {
+        self.player_ids()
+            .into_iter()
+            .filter(|player_id| !self.is_online(player_id))
+            .collect()
+    }
+    /// Connected clients that aren't part of the game, waiting to take over a player.
+    fn candidates(&self) -> Vec<Uuid> {
+        self.connections
+            .iter()
+            .filter(|(player_id, &connections)| {
+                connections > 0 && !self.players.contains_key(player_id)
+            })
+            .map(|(&player_id, _)| player_id)
+            .collect()
+    }
+    fn takeovers_message(&self) -> ServerToClientMessage {
+        ServerToClientMessage::Takeovers {
+            offline_players: self.offline_players(),
+            requests: self.takeovers.clone(),
+        }
+    }
+    fn push_takeovers(&self, effects: &mut Vec<Effect>) {
+        let message = self.takeovers_message();
+        for player_id in self.players.keys().cloned().chain(self.candidates()) {
+            Self::send_to(player_id, message.clone(), effects);
+        }
+    }
+
+    /// Called after a player connected or disconnected.
+    pub(super) fn update_takeovers(&mut self, effects: &mut Vec<Effect>) {
+        let connections = &self.connections;
+        self.takeovers
+            .retain(|request| connections.get(&request.player_id).cloned().unwrap_or(0) == 0);
+        if !self.candidates().is_empty() {
+            self.push_takeovers(effects);
+        }
+    }
+
+    /// Deals with everybody who isn't part of the game once it has started.
+    ///
+    /// Returns `false` if the event still has to be handled by the current phase.
+    pub(super) fn handle_takeover(
+        &mut self,
+        event: &Event,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) -> bool {
+        if let Phase::PlayerSelection | Phase::Finished { .. } = self.phase {
+            return false;
+        }
+        match event {
+            Event::Connect { player_id } => {
+                // without anybody to replace, the phase turns strangers away
+                if self.players.contains_key(player_id) || self.offline_players().is_empty() {
+                    return false;
+                }
+                *self.connections.entry(*player_id).or_default() += 1;
+                effects.push(Effect::Reply(self.public_state()));
+                effects.push(Effect::Reply(self.takeovers_message()));
+            }
+            Event::Disconnect { player_id, .. } => {
+                if self.players.contains_key(player_id) {
+                    return false;
+                }
+                if !self.is_online(player_id) {
+                    self.connections.remove(player_id);
+                    self.takeovers
+                        .retain(|request| request.replacement_id != *player_id);
+                    self.push_takeovers(effects);
+                }
+            }
+            Event::Message { player_id, message } => match message {
+                ClientToServerMessage::RequestTakeover {
+                    player_id: old_player_id,
+                } => {
+                    if self.is_online(player_id)
+                        && !self.players.contains_key(player_id)
+                        && self.players.contains_key(old_player_id)
+                        && !self.is_online(old_player_id)
+                        && self
+                            .takeovers
+                            .iter()
+                            .all(|request| request.player_id != *old_player_id)
+                    {
+                        self.takeovers.push(TakeoverRequest {
+                            player_id: *old_player_id,
+                            replacement_id: *player_id,
+                            approvals: Default::default(),
+                        });
+                        self.push_takeovers(effects);
+                    }
+                }
+                ClientToServerMessage::ApproveTakeover {
+                    player_id: old_player_id,
+                } => {
+                    if self.players.contains_key(player_id) {
+                        if let Some(request) = self
+                            .takeovers
+                            .iter_mut()
+                            .find(|request| request.player_id == *old_player_id)
+                        {
+                            request.approvals.insert(*player_id);
+                        }
+                        self.complete_takeovers(dice, effects);
+                        self.push_takeovers(effects);
+                    }
+                }
+                ClientToServerMessage::DenyTakeover {
+                    player_id: old_player_id,
+                } => {
+                    if self.players.contains_key(player_id) {
+                        self.takeovers
+                            .retain(|request| request.player_id != *old_player_id);
+                        self.push_takeovers(effects);
+                    }
+                }
+                // only players get to do anything else
+                _ => return !self.players.contains_key(player_id),
+            },
+            Event::Tick { .. } => return false,
+        }
+        true
+    }
+
+    /// Carries out all requests that everybody who is still playing agreed to.
+    fn complete_takeovers(&mut self, dice: &mut impl Dice, effects: &mut Vec<Effect>) {
+        let approved: Vec<_> = self
+            .takeovers
+            .iter()
+            .filter(|request| {
+                !request.approvals.is_empty()
+                    && self
+                        .players
+                        .keys()
+                        .filter(|player_id| self.is_online(player_id) && !self.is_bot(player_id))
+                        .all(|player_id| request.approvals.contains(player_id))
+            })
+            .map(|request| (request.player_id, request.replacement_id))
+            .collect();
+        for (old_id, new_id) in approved {
+            self.takeovers
+                .retain(|request| request.player_id != old_id && request.replacement_id != new_id);
+            if let Some(player) = self.players.remove(&old_id) {
+                self.players.insert(new_id, player);
+            }
+            self.connections.remove(&old_id);
+            self.phase.replace_player(old_id, new_id);
+            self.history.replace_player(old_id, new_id);
+            effects.push(Effect::DropClients { player_id: old_id });
+            effects.push(Effect::Checkpoint);
+            if !matches!(self.phase, Phase::Temple(_) | Phase::FinalBattle(_)) {
+                // the players map changed, the rooms push their state on connect anyway
+                self.push_state_all(self.view(false), effects);
+            }
+            // greet the replacement like a player who came back, which also hands over the clue to a GM
+            if let Some(connections) = self.connections.get_mut(&new_id) {
+                *connections = connections.saturating_sub(1);
+            }
+            for effect in self.apply(Event::Connect { player_id: new_id }, dice) {
+                effects.push(match effect {
+                    Effect::Reply(message) => Effect::Send {
+                        player_id: new_id,
+                        message,
+                    },
+                    effect => effect,
+                });
+            }
+        }
+    }
+}
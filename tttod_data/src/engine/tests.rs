@@ -0,0 +1,416 @@
+use super::*;
+use crate::Attribute;
+use std::collections::VecDeque;
+
+/// Dice that come up with exactly the results the test asks for.
+struct ScriptedDice(VecDeque<u8>);
+
+impl ScriptedDice {
+    fn new(rolls: &[u8]) -> Self {
+        Self(rolls.iter().cloned().collect())
+    }
+}
+
+impl Dice for ScriptedDice {
+    fn roll_d6(&mut self, count: usize) -> Vec<u8> {
+        (0..count)
+            .map(|_| self.0.pop_front().expect("ran out of scripted rolls"))
+            .collect()
+    }
+    fn pick(&mut self, _upper: usize) -> usize {
+        0
+    }
+}
+
+/// A game in the given room of the temple, one player per artifact boon, GMed in the order of their ids.
+fn temple(room_idx: usize, boons: &[ArtifactBoon]) -> (GameEngine, Vec<Uuid>) {
+    let mut engine = GameEngine::default();
+    let mut player_ids: Vec<Uuid> = boons.iter().map(|_| Uuid::new_v4()).collect();
+    player_ids.sort();
+    for (idx, (&player_id, &artifact_boon)) in player_ids.iter().zip(boons).enumerate() {
+        let mut attributes = HashMap::new();
+        attributes.insert(Attribute::Heroic, 2);
+        attributes.insert(Attribute::Booksmart, 2);
+        attributes.insert(Attribute::Streetwise, 1);
+        engine.players.insert(
+            player_id,
+            Player {
+                name: format!("Player {}", idx),
+                stats: Some(PlayerStats {
+                    name: format!("Character {}", idx),
+                    attributes,
+                    artifact_name: "Amulet".to_owned(),
+                    artifact_origin: "Grandma".to_owned(),
+                    artifact_boon,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+    }
+    engine.clues = (0..=boons.len())
+        .map(|idx| (format!("Question {}", idx), format!("Answer {}", idx)))
+        .collect();
+    engine.history.enter_room(player_ids[room_idx]);
+    engine.phase = Phase::Temple(Room {
+        gms: player_ids.clone(),
+        room_idx,
+        successes: 0,
+        failures: 0,
+        challenge: None,
+        challenge_result: None,
+        artifact_used: None,
+    });
+    (engine, player_ids)
+}
+
+fn send(
+    engine: &mut GameEngine,
+    dice: &mut ScriptedDice,
+    player_id: Uuid,
+    message: ClientToServerMessage,
+) -> Vec<Effect> {
+    engine.apply(Event::Message { player_id, message }, dice)
+}
+
+/// Has the GM offer a challenge on two dice to the player and accepts it, returns the result.
+fn roll(
+    engine: &mut GameEngine,
+    dice: &mut ScriptedDice,
+    gm: Uuid,
+    player_id: Uuid,
+) -> ChallengeResult {
+    let challenge = Challenge {
+        player_id,
+        attribute: Attribute::Heroic,
+        speciality_applies: false,
+        reputation_applies: false,
+    };
+    send(
+        engine,
+        dice,
+        gm,
+        ClientToServerMessage::OfferChallenge { challenge },
+    );
+    let effects = send(
+        engine,
+        dice,
+        player_id,
+        ClientToServerMessage::ChallengeAccepted,
+    );
+    challenge_result(effects)
+}
+
+fn challenge_result(effects: Vec<Effect>) -> ChallengeResult {
+    effects
+        .into_iter()
+        .find_map(|effect| match effect {
+            Effect::Send {
+                message: ServerToClientMessage::ChallengeResult(result),
+                ..
+            } => Some(result),
+            _ => None,
+        })
+        .expect("no challenge result")
+}
+
+fn room(engine: &GameEngine) -> &Room {
+    match &engine.phase {
+        Phase::Temple(room) => room,
+        phase => panic!("not in the temple but in {:?}", phase),
+    }
+}
+
+#[test]
+fn possession_takes_its_toll() {
+    let boon = ArtifactBoon::SuccessOnFive;
+    let (mut engine, player_ids) = temple(0, &[boon, boon, boon]);
+    let (gm, player_id) = (player_ids[0], player_ids[1]);
+    let mut dice = ScriptedDice::new(&[1, 1, 2, 2]);
+
+    let result = roll(&mut engine, &mut dice, gm, player_id);
+    assert!(result.possession && !result.success && !result.can_use_artifact);
+    send(
+        &mut engine,
+        &mut dice,
+        player_id,
+        ClientToServerMessage::AcceptFate,
+    );
+    assert_eq!(
+        engine.players[&player_id].mental_condition,
+        MentalCondition::Resisted
+    );
+    assert!(engine.can_speak_in_character(&player_id));
+
+    roll(&mut engine, &mut dice, gm, player_id);
+    send(
+        &mut engine,
+        &mut dice,
+        player_id,
+        ClientToServerMessage::AcceptFate,
+    );
+    assert_eq!(
+        engine.players[&player_id].mental_condition,
+        MentalCondition::Possessed
+    );
+    assert!(!engine.can_speak_in_character(&player_id));
+    assert_eq!(room(&engine).failures, 2);
+
+    // the ancient evil has got this one, there's no point in challenging them anymore
+    let effects = send(
+        &mut engine,
+        &mut dice,
+        gm,
+        ClientToServerMessage::OfferChallenge {
+            challenge: Challenge {
+                player_id,
+                attribute: Attribute::Booksmart,
+                speciality_applies: false,
+                reputation_applies: false,
+            },
+        },
+    );
+    assert!(room(&engine).challenge.is_none());
+    assert!(!effects.iter().any(|effect| matches!(
+        effect,
+        Effect::Send {
+            message: ServerToClientMessage::ChallengeOdds(_),
+            ..
+        }
+    )));
+}
+
+#[test]
+fn artifacts_turn_failures_around() {
+    let (mut engine, player_ids) = temple(
+        0,
+        &[
+            ArtifactBoon::Reroll,
+            ArtifactBoon::Reroll,
+            ArtifactBoon::RollWithPlusTwo,
+            ArtifactBoon::SuccessOnDoubles,
+        ],
+    );
+    let gm = player_ids[0];
+    // keep the room open for one more challenge
+    engine.rules.successes_needed = 4;
+    let mut dice = ScriptedDice::new(&[1, 1, 6, 3, 3, 4, 6, 2, 3, 3, 4, 4]);
+
+    // a possessed roll is worth rerolling
+    let result = roll(&mut engine, &mut dice, gm, player_ids[1]);
+    assert!(result.possession && result.can_use_artifact);
+    let result = challenge_result(send(
+        &mut engine,
+        &mut dice,
+        player_ids[1],
+        ClientToServerMessage::UseArtifact,
+    ));
+    assert_eq!(result.rolls, vec![6, 3]);
+    assert!(result.success && !result.possession);
+    assert_eq!(
+        engine.players[&player_ids[1]].mental_condition,
+        MentalCondition::Hale
+    );
+
+    let result = roll(&mut engine, &mut dice, gm, player_ids[2]);
+    assert!(!result.success && result.can_use_artifact);
+    let result = challenge_result(send(
+        &mut engine,
+        &mut dice,
+        player_ids[2],
+        ClientToServerMessage::UseArtifact,
+    ));
+    assert_eq!(result.rolls, vec![3, 4, 6, 2]);
+    assert!(result.success);
+
+    let result = roll(&mut engine, &mut dice, gm, player_ids[3]);
+    assert!(!result.success && result.can_use_artifact);
+    let result = challenge_result(send(
+        &mut engine,
+        &mut dice,
+        player_ids[3],
+        ClientToServerMessage::UseArtifact,
+    ));
+    assert!(result.success);
+    assert_eq!(room(&engine).successes, 3);
+
+    // every artifact only works once
+    let result = roll(&mut engine, &mut dice, gm, player_ids[3]);
+    assert!(!result.success && !result.can_use_artifact);
+    assert!(engine.players[&player_ids[3]].artifact_used);
+
+    let rooms = &engine.history.rooms;
+    assert_eq!(rooms[0].challenges[0].rolls, vec![vec![1, 1], vec![6, 3]]);
+    assert_eq!(rooms[0].challenges[0].outcome, Some(Outcome::Success));
+    assert_eq!(rooms[0].challenges[3].outcome, None);
+}
+
+#[test]
+fn gm_rejects_clues_that_do_not_fit() {
+    let boon = ArtifactBoon::Reroll;
+    let (mut engine, player_ids) = temple(0, &[boon, boon, boon]);
+    let mut dice = ScriptedDice::new(&[]);
+
+    // there's no lore yet the first clue could contradict
+    let effects = send(
+        &mut engine,
+        &mut dice,
+        player_ids[0],
+        ClientToServerMessage::RejectClue,
+    );
+    assert!(effects.iter().any(|effect| matches!(
+        effect,
+        Effect::Send {
+            message: ServerToClientMessage::ClueRejectionRejected,
+            ..
+        }
+    )));
+
+    let (mut engine, player_ids) = temple(1, &[boon, boon, boon]);
+    let gm = player_ids[1];
+    let effects = send(
+        &mut engine,
+        &mut dice,
+        gm,
+        ClientToServerMessage::RejectClue,
+    );
+    assert!(effects.iter().any(|effect| match effect {
+        Effect::Send {
+            player_id,
+            message: ServerToClientMessage::PushClue { clue },
+        } => *player_id == gm && clue == "Answer 2",
+        _ => false,
+    }));
+    assert_eq!(engine.clues.len(), 3);
+
+    // every room still needs a clue of its own
+    let effects = send(
+        &mut engine,
+        &mut dice,
+        gm,
+        ClientToServerMessage::RejectClue,
+    );
+    assert!(effects.iter().any(|effect| matches!(
+        effect,
+        Effect::Send {
+            message: ServerToClientMessage::ClueRejectionRejected,
+            ..
+        }
+    )));
+    assert_eq!(engine.clues.len(), 3);
+}
+
+#[test]
+fn final_battle_needs_half_the_players() {
+    let boon = ArtifactBoon::Reroll;
+    let boons = [boon, boon, boon, boon, boon];
+    for (players, target) in [(3, 2), (4, 2), (5, 3)] {
+        let (mut engine, player_ids) = temple(players - 1, &boons[..players]);
+        let gm = player_ids[players - 1];
+        if let Phase::Temple(room) = &mut engine.phase {
+            room.successes = engine.rules.successes_needed;
+        }
+        let mut dice = ScriptedDice::new(&[]);
+        let effects = send(
+            &mut engine,
+            &mut dice,
+            gm,
+            ClientToServerMessage::ReadyForGame,
+        );
+        let game_state = effects
+            .into_iter()
+            .find_map(|effect| match effect {
+                Effect::Send {
+                    message: ServerToClientMessage::PushState { game_state, .. },
+                    ..
+                } => Some(game_state),
+                _ => None,
+            })
+            .unwrap();
+        match game_state {
+            GameState::FinalBattle {
+                target_successes,
+                gms,
+                remaining_clues,
+                ..
+            } => {
+                assert_eq!(target_successes, target);
+                // nobody died, so the dice pick who plays the ancient evil
+                assert_eq!(gms.into_iter().collect::<Vec<_>>(), vec![player_ids[0]]);
+                assert_eq!(remaining_clues.len(), players);
+            }
+            game_state => panic!("expected the final battle, got {:?}", game_state),
+        }
+    }
+
+    let rules = RuleSettings {
+        final_battle_successes: Some(4),
+        ..Default::default()
+    };
+    assert_eq!(rules.final_battle_target(3), 4);
+}
+
+#[test]
+fn replacements_take_over_the_history() {
+    let boon = ArtifactBoon::Reroll;
+    let (mut engine, player_ids) = temple(0, &[boon, boon, boon]);
+    let mut dice = ScriptedDice::new(&[6, 3]);
+    roll(&mut engine, &mut dice, player_ids[0], player_ids[1]);
+
+    engine.apply(
+        Event::Connect {
+            player_id: player_ids[2],
+        },
+        &mut dice,
+    );
+    let replacements: Vec<Uuid> = (0..2).map(|_| Uuid::new_v4()).collect();
+    // everybody who is online has to agree
+    let mut approvers = vec![player_ids[2]];
+    for (&old_id, &new_id) in player_ids.iter().zip(&replacements) {
+        engine.apply(Event::Connect { player_id: new_id }, &mut dice);
+        send(
+            &mut engine,
+            &mut dice,
+            new_id,
+            ClientToServerMessage::RequestTakeover { player_id: old_id },
+        );
+        for &approver in &approvers {
+            send(
+                &mut engine,
+                &mut dice,
+                approver,
+                ClientToServerMessage::ApproveTakeover { player_id: old_id },
+            );
+        }
+        assert!(engine.players.contains_key(&new_id));
+        approvers.push(new_id);
+    }
+
+    let room = &engine.history.rooms[0];
+    assert_eq!(room.gm, replacements[0]);
+    assert_eq!(room.challenges[0].challenge.player_id, replacements[1]);
+}
+
+#[test]
+fn lobby_keeps_a_place_for_everybody_seated() {
+    let mut engine = GameEngine::default();
+    let player_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    for &player_id in &player_ids {
+        engine.players.insert(player_id, Player::default());
+    }
+    let mut dice = ScriptedDice::new(&[]);
+    for (max_players, accepted) in [(2, false), (3, true)] {
+        let rules = RuleSettings {
+            min_players: 2,
+            max_players,
+            ..Default::default()
+        };
+        send(
+            &mut engine,
+            &mut dice,
+            player_ids[0],
+            ClientToServerMessage::SetRules { rules },
+        );
+        assert_eq!(engine.rules == rules, accepted);
+    }
+}
@@ -0,0 +1,248 @@
+use super::{is_alive, Effect, Event, GameEngine, Phase};
+use crate::{
+    character::ATTRIBUTES, dice::choose, Challenge, ClientToServerMessage, Countdown, Dice,
+    PlayerStats, TimeoutAction,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// The stretches of a game the countdowns belong to, they start over whenever this changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum TimedPhase {
+    DefineEvil,
+    CreateCharacter,
+    IntroduceCharacters,
+    Room(usize),
+}
+
+impl GameEngine {
+    /// Whether the server has to keep sending [`Event::Tick`]s.
+    pub fn is_counting_down(&self) -> bool {
+        self.countdowns
+            .values()
+            .any(|countdown| countdown.seconds_left > 0)
+    }
+
+    fn timed_phase(&self) -> Option<(TimedPhase, usize)> {
+        let timers = &self.rules.timers;
+        let (timed_phase, seconds) = match &self.phase {
+            Phase::DefineEvil { .. } => (TimedPhase::DefineEvil, timers.define_evil),
+            Phase::CharacterCreation => (TimedPhase::CreateCharacter, timers.create_character),
+            Phase::CharacterIntroduction => {
+                (TimedPhase::IntroduceCharacters, timers.introduce_characters)
+            }
+            Phase::Temple(room) => (TimedPhase::Room(room.room_idx), timers.room),
+            _ => return None,
+        };
+        seconds.map(|seconds| (timed_phase, seconds))
+    }
+
+    /// Starts the clock for everybody the game is waiting for, and stops it for everybody else.
+    pub(super) fn update_countdowns(&mut self) {
+        let timed_phase = self.timed_phase();
+        if timed_phase.map(|(timed_phase, _)| timed_phase) != self.timed_phase {
+            self.countdowns.clear();
+            self.timed_phase = timed_phase.map(|(timed_phase, _)| timed_phase);
+        }
+        let seconds = match timed_phase {
+            Some((_, seconds)) => seconds,
+            None => return,
+        };
+        // the rooms mark everybody but the GM or the challenged player as ready, too
+        let waiting_for: HashSet<Uuid> = self
+            .players
+            .iter()
+            .filter(|(_, player)| !player.ready)
+            .map(|(&player_id, _)| player_id)
+            .collect();
+        self.countdowns
+            .retain(|player_id, _| waiting_for.contains(player_id));
+        for player_id in waiting_for {
+            let paused = !self.is_online(&player_id);
+            self.countdowns
+                .entry(player_id)
+                .or_insert_with(|| Countdown {
+                    seconds_left: seconds,
+                    paused,
+                    skip_votes: None,
+                });
+        }
+    }
+
+    /// Lets time pass for everybody who is online, and deals with the ones who ran out of it.
+    pub(super) fn tick(&mut self, seconds: u64, dice: &mut impl Dice, effects: &mut Vec<Effect>) {
+        let mut changed = false;
+        let mut expired = Vec::new();
+        for player_id in self.player_ids() {
+            let online = self.is_online(&player_id);
+            let countdown = match self.countdowns.get_mut(&player_id) {
+                Some(countdown) => countdown,
+                None => continue,
+            };
+            if countdown.paused == online {
+                countdown.paused = !online;
+                changed = true;
+            }
+            if countdown.paused || countdown.seconds_left == 0 {
+                continue;
+            }
+            countdown.seconds_left = countdown.seconds_left.saturating_sub(seconds as usize);
+            if countdown.seconds_left == 0 {
+                if self.rules.timers.action == TimeoutAction::SkipVote {
+                    countdown.skip_votes = Some(HashSet::new());
+                }
+                expired.push(player_id);
+                changed = true;
+            }
+        }
+        if changed {
+            self.push_current_state(effects);
+        }
+        if self.rules.timers.action == TimeoutAction::AutoReady {
+            for player_id in expired {
+                self.carry_on_for(player_id, dice, effects);
+            }
+        }
+    }
+
+    /// Counts a vote for skipping a player whose time is up, a majority of the other players who are online wins.
+    pub(super) fn vote_skip(
+        &mut self,
+        voter: Uuid,
+        player_id: Uuid,
+        dice: &mut impl Dice,
+        effects: &mut Vec<Effect>,
+    ) {
+        if voter == player_id || !self.players.contains_key(&voter) {
+            return;
+        }
+        let voters: HashSet<Uuid> = self
+            .players
+            .iter()
+            .filter(|(&id, player)| id != player_id && !player.bot && self.is_online(&id))
+            .map(|(&id, _)| id)
+            .collect();
+        let votes = match self
+            .countdowns
+            .get_mut(&player_id)
+            .and_then(|countdown| countdown.skip_votes.as_mut())
+        {
+            Some(votes) => votes,
+            None => return,
+        };
+        votes.insert(voter);
+        if votes.intersection(&voters).count() * 2 > voters.len() {
+            self.carry_on_for(player_id, dice, effects);
+        } else {
+            self.push_current_state(effects);
+        }
+    }
+
+    /// Does what the player would have had to do to move the game along, with what they entered so far.
+    fn carry_on_for(&mut self, player_id: Uuid, dice: &mut impl Dice, effects: &mut Vec<Effect>) {
+        self.countdowns.remove(&player_id);
+        let messages = match &self.phase {
+            Phase::DefineEvil { player_questions } => {
+                let answers = player_questions
+                    .get(&player_id)
+                    .map(|questions| {
+                        questions
+                            .iter()
+                            .map(|(question, answer)| {
+                                answer
+                                    .clone()
+                                    .filter(|answer| !answer.is_empty())
+                                    .unwrap_or_else(|| self.deck.roll_inspiration(question, dice))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                vec![
+                    ClientToServerMessage::Answers { answers },
+                    ClientToServerMessage::ReadyForGame,
+                ]
+            }
+            Phase::CharacterCreation => {
+                let stats = self
+                    .players
+                    .get(&player_id)
+                    .and_then(|player| player.stats.clone())
+                    .unwrap_or_default()
+                    .complete(dice);
+                vec![
+                    ClientToServerMessage::SetCharacter { stats },
+                    ClientToServerMessage::ReadyForGame,
+                ]
+            }
+            Phase::CharacterIntroduction => vec![ClientToServerMessage::ReadyForGame],
+            Phase::Temple(room) => {
+                if room.challenge.as_ref().map(|challenge| challenge.player_id) == Some(player_id) {
+                    if room.challenge_result.is_some() {
+                        vec![ClientToServerMessage::AcceptFate]
+                    } else {
+                        vec![ClientToServerMessage::ChallengeAccepted]
+                    }
+                } else if room.gm() != player_id {
+                    Vec::new()
+                } else if room.successes >= self.rules.successes_needed {
+                    vec![ClientToServerMessage::ReadyForGame]
+                } else {
+                    let candidates: Vec<Uuid> = self
+                        .player_ids()
+                        .into_iter()
+                        .filter(|id| *id != player_id && is_alive(&self.players[id]))
+                        .collect();
+                    if candidates.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![ClientToServerMessage::OfferChallenge {
+                            challenge: Challenge {
+                                player_id: *choose(&candidates, dice),
+                                attribute: *choose(ATTRIBUTES, dice),
+                                speciality_applies: false,
+                                reputation_applies: false,
+                            },
+                        }]
+                    }
+                }
+            }
+            _ => Vec::new(),
+        };
+        log::info!(
+            "Time is up for {}, carrying on with {:?}",
+            player_id,
+            messages
+        );
+        for message in messages {
+            effects.extend(self.apply(Event::Message { player_id, message }, dice));
+        }
+    }
+}
+
+impl PlayerStats {
+    /// Fills in everything the player left blank, and replaces attributes that don't add up.
+    pub(crate) fn complete(self, dice: &mut impl Dice) -> Self {
+        let random = Self::random(dice);
+        let keep = |value: String, random: String| if value.is_empty() { random } else { value };
+        let attributes_valid = Self {
+            name: random.name.clone(),
+            artifact_name: random.artifact_name.clone(),
+            artifact_origin: random.artifact_origin.clone(),
+            ..self.clone()
+        }
+        .is_valid();
+        Self {
+            name: keep(self.name, random.name),
+            artifact_name: keep(self.artifact_name, random.artifact_name),
+            artifact_origin: keep(self.artifact_origin, random.artifact_origin),
+            attributes: if attributes_valid {
+                self.attributes
+            } else {
+                random.attributes
+            },
+            ..self
+        }
+    }
+}
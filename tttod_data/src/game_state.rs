@@ -1,4 +1,4 @@
-use crate::Challenge;
+use crate::{Challenge, Deck, DeckInfo};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
@@ -8,6 +8,14 @@ use uuid::Uuid;
 pub enum GameState {
     PlayerSelection {
         player_kick_votes: HashMap<Uuid, HashSet<Uuid>>,
+        /// Hidden from the list of open games
+        unlisted: bool,
+        /// Needed by everybody else to join, only shown to the players
+        password: Option<String>,
+        /// The questions the players will answer
+        deck: DeckInfo,
+        /// Number of questions the players added themselves
+        custom_questions: usize,
     },
     DefineEvil,
     CharacterCreation,
@@ -36,9 +44,51 @@ impl Default for GameState {
     fn default() -> Self {
         Self::PlayerSelection {
             player_kick_votes: HashMap::new(),
+            unlisted: false,
+            password: None,
+            deck: Deck::default().info(),
+            custom_questions: 0,
         }
     }
 }
 
-pub const SUCCESSES_NEEDED: usize = 3;
-pub const FAILURES_NEEDED: usize = 3;
+impl GameState {
+    /// The name of the variant, without any of its data.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PlayerSelection { .. } => "PlayerSelection",
+            Self::DefineEvil => "DefineEvil",
+            Self::CharacterCreation => "CharacterCreation",
+            Self::CharacterIntroduction => "CharacterIntroduction",
+            Self::Room { .. } => "Room",
+            Self::FinalBattle { .. } => "FinalBattle",
+            Self::Victory => "Victory",
+            Self::Failure => "Failure",
+        }
+    }
+}
+
+/// What the list of open games shows about a game.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct GameInfo {
+    pub name: String,
+    /// See [`GameState::name`]
+    pub phase: String,
+    pub players: usize,
+    pub max_players: usize,
+    /// Whether somebody new could still join as a player
+    pub joinable: bool,
+    /// Joining or watching needs a password
+    pub private: bool,
+}
+
+/// The time a player has left to finish their part of the current phase.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+pub struct Countdown {
+    /// As of the message this came with, clients count down on their own from there
+    pub seconds_left: usize,
+    /// The player is offline, so the clock isn't running
+    pub paused: bool,
+    /// The players who want to carry on without them, once time has run out and the rules call for a vote
+    pub skip_votes: Option<HashSet<Uuid>>,
+}
@@ -1,11 +1,28 @@
+#![allow(clippy::single_match, clippy::naive_bytecount)]
 mod player;
 pub use player::{
     ArtifactBoon, Attribute, Condition, MentalCondition, Player, PlayerStats, Reputation,
     Speciality,
 };
+mod character;
 mod message;
-pub use message::{ChallengeResult, ClientToServerMessage, ServerToClientMessage};
+pub use message::{
+    ChallengeResult, ChatChannel, ChatMessage, ClientToServerMessage, Recipient,
+    ServerToClientMessage, SessionToken, TakeoverRequest, Whisper, PROTOCOL_VERSION, REDACTED,
+};
 mod game_state;
-pub use game_state::{GameState, FAILURES_NEEDED, SUCCESSES_NEEDED};
+pub use game_state::{Countdown, GameInfo, GameState};
 mod challenge;
-pub use challenge::Challenge;
+pub use challenge::{Challenge, ChallengeOdds, Chances};
+mod deck;
+pub use deck::{Deck, DeckInfo};
+mod engine;
+pub use engine::{Effect, Event, GameEngine};
+mod dice;
+pub use dice::{Dice, SeededDice};
+mod rules;
+pub use rules::{PhaseTimers, RuleSettings, TimeoutAction};
+mod recap;
+pub use recap::{ChallengeRecord, FinalBattleRecord, History, Outcome, Recap, RoomRecord};
+mod bot;
+pub use bot::Bot;
@@ -1,11 +1,22 @@
-use crate::{Challenge, GameState, Player, PlayerStats};
+use crate::{
+    Challenge, ChallengeOdds, Countdown, Deck, GameState, Player, PlayerStats, Recap, RuleSettings,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// Version of the messages below, has to change whenever a client built for the previous one couldn't talk to the server anymore.
+pub const PROTOCOL_VERSION: u32 = 1;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "cmd", rename_all = "snake_case")]
 pub enum ClientToServerMessage {
+    /// The first message of every connection, the server doesn't add the client to the game before it.
+    Hello {
+        protocol_version: u32,
+        /// Only for the logs of the server
+        client_build: String,
+    },
     SetPlayerName {
         name: String,
     },
@@ -16,12 +27,58 @@ pub enum ClientToServerMessage {
     RevertVoteKickPlayer {
         player_id: Uuid,
     },
+    SetUnlisted {
+        unlisted: bool,
+    },
+    /// A question about the ancient evil that someone else will have to answer.
+    AddCustomQuestion {
+        question: String,
+    },
+    /// Takes back one of the questions the player added, by index.
+    RemoveCustomQuestion {
+        idx: usize,
+    },
+    /// Asks the server to fill an empty seat in the lobby with a bot.
+    AddBot,
+    /// Sent by the server on behalf of `AddBot` with the id it picked for the bot, clients can't send it themselves.
+    SeatBot {
+        player_id: Uuid,
+    },
+    RemoveBot {
+        player_id: Uuid,
+    },
+    /// Picks one of the decks the server offers by name.
+    SelectDeck {
+        name: String,
+    },
+    /// Sent by the server on behalf of `SelectDeck`, clients can't send it themselves.
+    SetDeck {
+        deck: Deck,
+    },
+    /// Only accepted in the lobby, and only if [`RuleSettings::is_valid`].
+    SetRules {
+        rules: RuleSettings,
+    },
+    /// `None` or an empty password makes the game public again.
+    SetPassword {
+        password: Option<String>,
+    },
     Answers {
         answers: Vec<String>,
     },
+    /// Votes to carry on for a player whose time has run out.
+    VoteSkip {
+        player_id: Uuid,
+    },
+    /// Asks for a prompt to help with answering one of the player's questions.
+    RollInspiration {
+        question_idx: usize,
+    },
     SetCharacter {
         stats: PlayerStats,
     },
+    /// Asks the server to make up a character, which replaces whatever the player entered so far.
+    RollCharacter,
     RejectClue,
     OfferChallenge {
         challenge: Challenge,
@@ -35,32 +92,112 @@ pub enum ClientToServerMessage {
     UseArtifact,
     TakeWound,
     AcceptFate,
+    /// Sent by someone who isn't part of the game to take over the character of a player who left.
+    RequestTakeover {
+        player_id: Uuid,
+    },
+    ApproveTakeover {
+        player_id: Uuid,
+    },
+    DenyTakeover {
+        player_id: Uuid,
+    },
+    /// Says something to everybody in the game, handled by the server without involving the rules.
+    Chat {
+        channel: ChatChannel,
+        text: String,
+    },
+    /// Says something only the recipient gets to see, handled by the server like `Chat`.
+    Whisper {
+        to: Recipient,
+        text: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(tag = "cmd", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ServerToClientMessage {
+    /// The server speaks the protocol of the client, the game is about to start sending.
+    Welcome,
+    /// The client was built for another [`PROTOCOL_VERSION`] than the server, it has to be reloaded.
+    IncompatibleClient {
+        protocol_version: u32,
+    },
     GameIsFull,
     GameIsOngoing,
+    /// The game is protected by a password, and the client didn't provide the right one.
+    WrongPassword,
+    /// The client didn't present a valid session token for its player id, it has to join again.
+    InvalidSession,
     PushState {
         players: HashMap<Uuid, Player>,
         game_state: GameState,
+        rules: RuleSettings,
+        /// For the players the game is waiting for, if the phase has a deadline
+        #[serde(default)]
+        countdowns: HashMap<Uuid, Countdown>,
+    },
+    /// The questions this player added in the lobby, nobody else gets to see them.
+    CustomQuestions {
+        questions: Vec<String>,
     },
     Questions {
         questions: Vec<(String, Option<String>)>,
     },
+    /// A prompt for the question with the given index, only sent to the player who asked.
+    Inspiration {
+        question_idx: usize,
+        prompt: String,
+    },
     PushClue {
         clue: String,
     },
     ClueRejectionRejected,
     AbortedChallenge,
+    /// The chances of the challenge the player has been offered, only sent to them.
+    ChallengeOdds(ChallengeOdds),
     ChallengeResult(ChallengeResult),
+    ChatMessage(ChatMessage),
+    /// Sent to both the recipient and the sender.
+    Whisper(Whisper),
+    /// Everything that happened in the game, sent once it is over.
+    Recap(Recap),
+    /// Who could be replaced and who wants to replace them.
+    Takeovers {
+        offline_players: Vec<Uuid>,
+        requests: Vec<TakeoverRequest>,
+    },
 }
 
+/// Stands in for passwords and whispers in logs and journals.
+pub const REDACTED: &str = "…";
+
 impl ClientToServerMessage {
     pub fn into_json(self) -> Result<String, serde_json::error::Error> {
         serde_json::to_string(&self)
     }
+
+    /// A copy that can be logged or journaled, without the password of the game or whispered text.
+    pub fn redacted(&self) -> Self {
+        match self {
+            ClientToServerMessage::SetPassword { password } => ClientToServerMessage::SetPassword {
+                // an empty password still makes the game public again
+                password: password.as_ref().map(|password| {
+                    if password.is_empty() {
+                        String::new()
+                    } else {
+                        REDACTED.to_owned()
+                    }
+                }),
+            },
+            ClientToServerMessage::Whisper { to, .. } => ClientToServerMessage::Whisper {
+                to: *to,
+                text: REDACTED.to_owned(),
+            },
+            message => message.clone(),
+        }
+    }
 }
 
 impl ServerToClientMessage {
@@ -76,3 +213,55 @@ pub struct ChallengeResult {
     pub possession: bool,
     pub can_use_artifact: bool,
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatChannel {
+    /// Spoken by the character, only for the ones who are still in the game
+    InCharacter,
+    /// Between the players
+    OutOfCharacter,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub player_id: Uuid,
+    pub channel: ChatChannel,
+    pub text: String,
+}
+
+/// Who a whisper is meant for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Recipient {
+    Player(Uuid),
+    /// The GM of the current room, or every one of them during the final battle
+    Gm,
+}
+
+/// A whisper to a single player, one is sent to every GM when whispering to them.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Whisper {
+    pub from: Uuid,
+    pub to: Uuid,
+    /// Addressed to `to` because they were the GM at the time
+    pub to_gm: bool,
+    pub text: String,
+}
+
+/// Someone new wants to continue playing the character of a player who left the game.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TakeoverRequest {
+    /// The player who left
+    pub player_id: Uuid,
+    pub replacement_id: Uuid,
+    /// Everybody who is still playing has to approve
+    pub approvals: HashSet<Uuid>,
+}
+
+/// The answer of `POST /api/join`, the token has to be presented along with the player id when connecting.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SessionToken {
+    pub player_id: Uuid,
+    pub token: String,
+}
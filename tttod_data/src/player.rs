@@ -9,6 +9,15 @@ pub struct Player {
     pub condition: Condition,
     pub mental_condition: MentalCondition,
     pub artifact_used: bool,
+    /// Played by the server
+    #[serde(default)]
+    pub bot: bool,
+    /// Whether any client of the player is connected
+    #[serde(default)]
+    pub online: bool,
+    /// When the last client of the player went away, in seconds since the Unix epoch
+    #[serde(default)]
+    pub last_seen: Option<u64>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -146,6 +155,34 @@ pub struct PlayerStats {
     pub artifact_boon: ArtifactBoon,
 }
 
+impl PlayerStats {
+    /// Whether the character is complete enough to enter the temple.
+    pub fn is_valid(&self) -> bool {
+        let heroic = self
+            .attributes
+            .get(&Attribute::Heroic)
+            .cloned()
+            .unwrap_or(0);
+        let booksmart = self
+            .attributes
+            .get(&Attribute::Booksmart)
+            .cloned()
+            .unwrap_or(0);
+        let streetwise = self
+            .attributes
+            .get(&Attribute::Streetwise)
+            .cloned()
+            .unwrap_or(0);
+        heroic > 0
+            && booksmart > 0
+            && streetwise > 0
+            && heroic + booksmart + streetwise == 5
+            && !self.name.is_empty()
+            && !self.artifact_name.is_empty()
+            && !self.artifact_origin.is_empty()
+    }
+}
+
 impl Default for PlayerStats {
     fn default() -> Self {
         Self {
@@ -0,0 +1,427 @@
+use crate::{engine::possessed_dice, Attribute, Challenge, Player};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt::Write};
+use uuid::Uuid;
+
+/// How an accepted challenge ended.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Success,
+    /// The roll failed, but the character took a wound to succeed anyway
+    Wounded,
+    Failure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeRecord {
+    pub challenge: Challenge,
+    /// The secret of the ancient evil the challenge was about, only in the final battle
+    pub clue: Option<String>,
+    /// The first roll, followed by the one the artifact turned it into
+    pub rolls: Vec<Vec<u8>>,
+    /// `None` while the player is still deciding what to do about the roll
+    pub outcome: Option<Outcome>,
+}
+
+impl ChallengeRecord {
+    /// Whether the final roll brought the character closer to being possessed.
+    pub fn possession(&self) -> bool {
+        self.rolls.last().is_some_and(|rolls| possessed_dice(rolls))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomRecord {
+    pub gm: Uuid,
+    pub challenges: Vec<ChallengeRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalBattleRecord {
+    pub gms: Vec<Uuid>,
+    pub challenges: Vec<ChallengeRecord>,
+}
+
+/// Who GMed where and how every challenge went, kept by the engine for the recap.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    pub rooms: Vec<RoomRecord>,
+    pub final_battle: Option<FinalBattleRecord>,
+}
+
+impl History {
+    pub(crate) fn enter_room(&mut self, gm: Uuid) {
+        self.rooms.push(RoomRecord {
+            gm,
+            challenges: Vec::new(),
+        });
+    }
+
+    pub(crate) fn face_ancient_evil(&mut self, gms: Vec<Uuid>) {
+        self.final_battle = Some(FinalBattleRecord {
+            gms,
+            challenges: Vec::new(),
+        });
+    }
+
+    fn challenges(&mut self) -> Option<&mut Vec<ChallengeRecord>> {
+        match &mut self.final_battle {
+            Some(battle) => Some(&mut battle.challenges),
+            None => self.rooms.last_mut().map(|room| &mut room.challenges),
+        }
+    }
+
+    /// A challenge has been accepted and the dice came up with `rolls`.
+    pub(crate) fn roll(&mut self, challenge: Challenge, clue: Option<String>, rolls: Vec<u8>) {
+        if let Some(challenges) = self.challenges() {
+            challenges.push(ChallengeRecord {
+                challenge,
+                clue,
+                rolls: vec![rolls],
+                outcome: None,
+            });
+        }
+    }
+
+    /// The artifact changed the roll of the current challenge.
+    pub(crate) fn reroll(&mut self, rolls: Vec<u8>) {
+        if let Some(record) = self
+            .challenges()
+            .and_then(|challenges| challenges.last_mut())
+        {
+            record.rolls.push(rolls);
+        }
+    }
+
+    /// Credits everything a player did to the one who took over for them.
+    pub(crate) fn replace_player(&mut self, old_id: Uuid, new_id: Uuid) {
+        let replace = |player_id: &mut Uuid| {
+            if *player_id == old_id {
+                *player_id = new_id;
+            }
+        };
+        for room in &mut self.rooms {
+            replace(&mut room.gm);
+            for record in &mut room.challenges {
+                replace(&mut record.challenge.player_id);
+            }
+        }
+        if let Some(battle) = &mut self.final_battle {
+            battle.gms.iter_mut().for_each(replace);
+            battle.gms.sort();
+            for record in &mut battle.challenges {
+                replace(&mut record.challenge.player_id);
+            }
+        }
+    }
+
+    pub(crate) fn resolve(&mut self, outcome: Outcome) {
+        if let Some(record) = self
+            .challenges()
+            .and_then(|challenges| challenges.last_mut())
+        {
+            record.outcome = Some(outcome);
+        }
+    }
+}
+
+/// Everything worth remembering about a game that is over.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recap {
+    pub victory: bool,
+    /// The questions about the ancient evil along with their answers
+    pub clues: Vec<(String, String)>,
+    pub players: HashMap<Uuid, Player>,
+    pub history: History,
+}
+
+/// The parts a recap is made of, so that Markdown and HTML come out the same.
+enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Item(String),
+}
+
+impl Recap {
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        let mut in_list = false;
+        for block in self.blocks() {
+            if in_list && !matches!(block, Block::Item(_)) {
+                markdown.push('\n');
+            }
+            in_list = matches!(block, Block::Item(_));
+            match block {
+                Block::Heading(level, text) => {
+                    writeln!(
+                        markdown,
+                        "{} {}\n",
+                        "#".repeat(level),
+                        escape_markdown(&text)
+                    )
+                    .ok();
+                }
+                Block::Paragraph(text) => {
+                    writeln!(markdown, "{}\n", escape_markdown(&text)).ok();
+                }
+                Block::Item(text) => {
+                    writeln!(markdown, "- {}", escape_markdown(&text)).ok();
+                }
+            }
+        }
+        markdown.truncate(markdown.trim_end().len());
+        markdown.push('\n');
+        markdown
+    }
+
+    /// A complete page that can be opened in a browser as it is.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>To the Temple of Doom!</title>\n</head>\n<body>\n",
+        );
+        let mut in_list = false;
+        for block in self.blocks() {
+            let is_item = matches!(block, Block::Item(_));
+            if in_list && !is_item {
+                html.push_str("</ul>\n");
+            } else if !in_list && is_item {
+                html.push_str("<ul>\n");
+            }
+            in_list = is_item;
+            match block {
+                Block::Heading(level, text) => {
+                    writeln!(html, "<h{0}>{1}</h{0}>", level, escape(&text)).ok();
+                }
+                Block::Paragraph(text) => {
+                    writeln!(html, "<p>{}</p>", escape(&text)).ok();
+                }
+                Block::Item(text) => {
+                    writeln!(html, "<li>{}</li>", escape(&text)).ok();
+                }
+            }
+        }
+        if in_list {
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    /// The name of the character, or of the player if they never finished one.
+    fn name(&self, player_id: &Uuid) -> String {
+        self.players
+            .get(player_id)
+            .map(|player| match &player.stats {
+                Some(stats) => stats.name.clone(),
+                None => player.name.clone(),
+            })
+            .unwrap_or_else(|| "Somebody who left".to_owned())
+    }
+
+    fn names(&self, player_ids: &[Uuid]) -> String {
+        player_ids
+            .iter()
+            .map(|player_id| self.name(player_id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn describe(&self, record: &ChallengeRecord) -> String {
+        let challenge = &record.challenge;
+        let mut text = format!(
+            "{} ({:?}",
+            self.name(&challenge.player_id),
+            challenge.attribute
+        );
+        if challenge.speciality_applies {
+            text.push_str(", speciality");
+        }
+        if challenge.reputation_applies {
+            text.push_str(", reputation");
+        }
+        text.push(')');
+        if let Some(clue) = &record.clue {
+            write!(text, " against “{}”", clue).ok();
+        }
+        let mut rolls = record.rolls.iter().map(|rolls| {
+            rolls
+                .iter()
+                .map(|die| die.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        });
+        if let Some(first) = rolls.next() {
+            write!(text, " rolled {}", first).ok();
+        }
+        for rolls in rolls {
+            write!(text, ", with the artifact {}", rolls).ok();
+        }
+        text.push_str(match record.outcome {
+            Some(Outcome::Success) => ": success",
+            Some(Outcome::Wounded) => ": success at the cost of a wound",
+            Some(Outcome::Failure) => ": failure",
+            None => ": undecided",
+        });
+        if record.possession() {
+            text.push_str(", a possession roll");
+        }
+        text
+    }
+
+    fn blocks(&self) -> Vec<Block> {
+        let mut blocks = vec![
+            Block::Heading(1, "To the Temple of Doom!".to_owned()),
+            Block::Paragraph(
+                if self.victory {
+                    "The ancient evil has been banished."
+                } else {
+                    "The world was consumed by malevolent wrath."
+                }
+                .to_owned(),
+            ),
+            Block::Heading(2, "The Ancient Evil".to_owned()),
+        ];
+        for (question, answer) in &self.clues {
+            blocks.push(Block::Item(format!("{} — {}", question, answer)));
+        }
+
+        blocks.push(Block::Heading(2, "The Characters".to_owned()));
+        let mut players: Vec<_> = self.players.values().collect();
+        players.sort_by(|a, b| a.name.cmp(&b.name));
+        for player in players {
+            let stats = match &player.stats {
+                Some(stats) => stats,
+                None => continue,
+            };
+            blocks.push(Block::Heading(
+                3,
+                format!("{}, played by {}", stats.name, player.name),
+            ));
+            blocks.push(Block::Item(format!("Speciality: {}", stats.speciality)));
+            blocks.push(Block::Item(format!("Reputation: {}", stats.reputation)));
+            let attributes = [
+                Attribute::Heroic,
+                Attribute::Booksmart,
+                Attribute::Streetwise,
+            ]
+            .iter()
+            .map(|attribute| {
+                format!(
+                    "{:?} {}",
+                    attribute,
+                    stats.attributes.get(attribute).cloned().unwrap_or(0)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+            blocks.push(Block::Item(format!("Attributes: {}", attributes)));
+            blocks.push(Block::Item(format!(
+                "Artifact: {}, {} ({})",
+                stats.artifact_name, stats.artifact_origin, stats.artifact_boon
+            )));
+            blocks.push(Block::Item(format!(
+                "Condition: {}, mind: {}",
+                player.condition, player.mental_condition
+            )));
+        }
+
+        for (room_idx, room) in self.history.rooms.iter().enumerate() {
+            blocks.push(Block::Heading(
+                2,
+                format!("Room {}, GMed by {}", room_idx + 1, self.name(&room.gm)),
+            ));
+            self.challenge_blocks(&room.challenges, &mut blocks);
+        }
+        if let Some(battle) = &self.history.final_battle {
+            blocks.push(Block::Heading(
+                2,
+                format!("The Final Battle, GMed by {}", self.names(&battle.gms)),
+            ));
+            self.challenge_blocks(&battle.challenges, &mut blocks);
+        }
+        blocks
+    }
+
+    fn challenge_blocks(&self, challenges: &[ChallengeRecord], blocks: &mut Vec<Block>) {
+        if challenges.is_empty() {
+            blocks.push(Block::Paragraph("No challenges.".to_owned()));
+        }
+        for record in challenges {
+            blocks.push(Block::Item(self.describe(record)));
+        }
+    }
+}
+
+/// Keeps names and answers from turning into Markdown of their own, all on one line.
+fn escape_markdown(text: &str) -> String {
+    let text = text.trim();
+    let mut escaped = String::with_capacity(text.len());
+    // a number followed by a dot or a parenthesis starts an ordered list
+    let list_number = text
+        .find(|c: char| !c.is_ascii_digit())
+        .filter(|&idx| idx > 0 && text[idx..].starts_with(['.', ')']));
+    for (idx, c) in text.char_indices() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '&' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '-' | '+' | '=' if idx == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ if Some(idx) == list_number => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_stay_plain_text() {
+        assert_eq!(
+            escape_markdown("# *Ancient* [evil](x) <b>"),
+            "\\# \\*Ancient\\* \\[evil\\](x) \\<b\\>"
+        );
+        assert_eq!(
+            escape_markdown("- a list\n- of things"),
+            "\\- a list - of things"
+        );
+        assert_eq!(escape_markdown("1. first"), "1\\. first");
+        assert_eq!(escape_markdown("2021 was a year."), "2021 was a year.");
+
+        let recap = Recap {
+            victory: true,
+            clues: vec![("What is it?".to_owned(), "_Cthulhu_".to_owned())],
+            players: HashMap::new(),
+            history: History::default(),
+        };
+        assert!(recap
+            .to_markdown()
+            .contains("- What is it? — \\_Cthulhu\\_\n"));
+    }
+}
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+
+/// Most players a game can be set up for
+const MAX_PLAYERS: usize = 10;
+const MAX_QUESTIONS_PER_PLAYER: usize = 5;
+/// Most successes or failures a room can take
+const MAX_CHALLENGES: usize = 10;
+/// Longest deadline of a phase timer, in seconds
+const MAX_SECONDS: usize = 3600;
+
+/// What happens once a player lets a phase timer run out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutAction {
+    /// Only remind everybody who the game is waiting for
+    #[default]
+    Nudge,
+    /// Carry on for the player with whatever they entered so far
+    AutoReady,
+    /// Let the others vote on carrying on for the player
+    SkipVote,
+}
+
+/// Seconds a player has to finish their part of a phase, `None` for no deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhaseTimers {
+    pub action: TimeoutAction,
+    pub define_evil: Option<usize>,
+    pub create_character: Option<usize>,
+    pub introduce_characters: Option<usize>,
+    /// For the GM to offer a challenge or leave the room, and for the challenged player to deal with it
+    pub room: Option<usize>,
+}
+
+impl PhaseTimers {
+    fn is_valid(&self) -> bool {
+        [
+            self.define_evil,
+            self.create_character,
+            self.introduce_characters,
+            self.room,
+        ]
+        .iter()
+        .flatten()
+        .all(|seconds| (1..=MAX_SECONDS).contains(seconds))
+    }
+}
+
+/// The numbers the rules of the game depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSettings {
+    pub min_players: usize,
+    pub max_players: usize,
+    /// Questions about the ancient evil every player has to answer
+    pub questions_per_player: usize,
+    /// Successes it takes to leave a room
+    pub successes_needed: usize,
+    /// Failures in a single room that unleash the ancient evil
+    pub failures_needed: usize,
+    /// Successes it takes to win the final battle, `None` for half the players, rounded up
+    pub final_battle_successes: Option<usize>,
+    pub timers: PhaseTimers,
+}
+
+impl Default for RuleSettings {
+    fn default() -> Self {
+        Self {
+            min_players: 3,
+            max_players: 5,
+            questions_per_player: 2,
+            successes_needed: 3,
+            failures_needed: 3,
+            final_battle_successes: None,
+            timers: PhaseTimers::default(),
+        }
+    }
+}
+
+impl RuleSettings {
+    /// Whether a game can be played with these settings.
+    pub fn is_valid(&self) -> bool {
+        self.min_players > 0
+            && self.min_players <= self.max_players
+            && self.max_players <= MAX_PLAYERS
+            && (1..=MAX_QUESTIONS_PER_PLAYER).contains(&self.questions_per_player)
+            && (1..=MAX_CHALLENGES).contains(&self.successes_needed)
+            && (1..=MAX_CHALLENGES).contains(&self.failures_needed)
+            // the final battle has a single clue per player to succeed on
+            && self
+                .final_battle_successes
+                .is_none_or(|successes| (1..=self.min_players).contains(&successes))
+            && self.timers.is_valid()
+    }
+
+    pub fn final_battle_target(&self, players: usize) -> usize {
+        self.final_battle_successes
+            .unwrap_or_else(|| players.div_ceil(2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_have_to_be_playable() {
+        let rules = RuleSettings::default();
+        assert!(rules.is_valid());
+        let out_of_range = [
+            RuleSettings {
+                min_players: 0,
+                ..rules
+            },
+            RuleSettings {
+                max_players: MAX_PLAYERS + 1,
+                ..rules
+            },
+            RuleSettings {
+                questions_per_player: MAX_QUESTIONS_PER_PLAYER + 1,
+                ..rules
+            },
+            RuleSettings {
+                successes_needed: MAX_CHALLENGES + 1,
+                ..rules
+            },
+            RuleSettings {
+                failures_needed: 0,
+                ..rules
+            },
+            RuleSettings {
+                final_battle_successes: Some(rules.min_players + 1),
+                ..rules
+            },
+            RuleSettings {
+                timers: PhaseTimers {
+                    room: Some(MAX_SECONDS + 1),
+                    ..Default::default()
+                },
+                ..rules
+            },
+        ];
+        for rules in &out_of_range {
+            assert!(!rules.is_valid(), "{:?} should be rejected", rules);
+        }
+        assert!(RuleSettings {
+            max_players: MAX_PLAYERS,
+            final_battle_successes: Some(rules.min_players),
+            ..rules
+        }
+        .is_valid());
+    }
+}
@@ -22,29 +22,51 @@ mod offer_final_challenge;
 pub use offer_final_challenge::OfferFinalChallenge;
 mod challenge_result;
 pub use challenge_result::ChallengeResultDialog;
+mod challenge_odds;
+pub use challenge_odds::ChallengeOddsView;
+mod chat_panel;
+pub use chat_panel::{ChatEntry, ChatPanel};
 mod face_evil;
 pub use face_evil::{EvilState, FaceEvil};
 mod failure;
 pub use failure::Failure;
 mod success;
 pub use success::Success;
+mod recap_download;
+pub use recap_download::RecapDownload;
+mod spectate;
+pub use spectate::Spectate;
+mod take_over;
+pub use take_over::TakeOver;
+mod takeover_requests;
+pub use takeover_requests::TakeoverRequests;
+mod enter_password;
+pub use enter_password::EnterPassword;
+mod countdowns;
+pub use countdowns::Countdowns;
 
-use super::root::AppRoute;
+use super::{root::AppRoute, Icon};
+use crate::IconName;
 use futures::{
     sink::SinkExt,
     stream::{SplitSink, StreamExt},
 };
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 use tttod_data::{
-    Challenge, ChallengeResult, ClientToServerMessage, GameState, Player, PlayerStats,
-    ServerToClientMessage,
+    Challenge, ChallengeOdds, ChallengeResult, ChatChannel, ClientToServerMessage, Countdown,
+    GameState, Player, PlayerStats, Recap, Recipient, RuleSettings, ServerToClientMessage,
+    SessionToken, TakeoverRequest, PROTOCOL_VERSION,
 };
 use uuid::Uuid;
 use wasm_bindgen::{closure::Closure, JsCast};
 use wasm_bindgen_futures::spawn_local;
 use ws_stream_wasm::{WsMessage, WsMeta, WsStream};
 use ybc::{HeaderSize, TileCtx};
-use yew::prelude::*;
+use yew::{
+    format::{Nothing, Text},
+    prelude::*,
+    services::fetch::{FetchService, FetchTask, Request, Response},
+};
 use yew_router::{
     agent::RouteRequest::ChangeRoute,
     prelude::{Route, RouteAgentDispatcher},
@@ -55,26 +77,66 @@ pub struct Game {
     props: Props,
     state: GameState,
     player_id: Uuid,
+    /// Proves to the server that we own `player_id`, `None` until we joined
+    token: Option<String>,
+    join_task: Option<FetchTask>,
     websocket: Option<(WsMeta, Rc<RefCell<SplitSink<WsStream, WsMessage>>>)>,
     players: HashMap<Uuid, Player>,
+    rules: RuleSettings,
     questions: Vec<(String, String)>,
+    /// The latest prompt the server rolled for each of our questions
+    inspiration: HashMap<usize, String>,
+    /// The questions we added in the lobby
+    custom_questions: Vec<String>,
     challenge_result: Option<ChallengeResult>,
+    /// The chances of the last challenge we've been offered
+    challenge_odds: Option<ChallengeOdds>,
+    /// Everything that has been said since we connected, the server repeats older messages on connect
+    chat: Vec<ChatEntry>,
     clue: Option<String>,
+    offline_players: Vec<Uuid>,
+    takeover_requests: Vec<TakeoverRequest>,
+    /// Who the game is waiting for, if the phase has a timer
+    countdowns: HashMap<Uuid, Countdown>,
+    /// For private games, remembered so that reconnecting works
+    password: Option<String>,
+    password_rejected: bool,
+    /// The server speaks another protocol, this page has to be reloaded
+    incompatible: bool,
     game_over: bool,
+    /// Sent by the server right before the game is over
+    recap: Option<Recap>,
     router: RouteAgentDispatcher,
 }
 
 #[derive(Debug, Clone, Properties)]
 pub struct Props {
     pub game_name: String,
+    /// Only watch the game instead of taking part
+    #[prop_or_default]
+    pub spectator: bool,
 }
 
 pub enum Msg {
     SetPlayerName(String),
     VoteKick(Uuid),
+    SetUnlisted(bool),
+    SetPassword(Option<String>),
+    SetRules(RuleSettings),
+    SelectDeck(String),
+    AddCustomQuestion(String),
+    RemoveCustomQuestion(usize),
+    AddBot,
+    RemoveBot(Uuid),
+    SendChat(ChatChannel, String),
+    SendWhisper(Recipient, String),
+    EnterPassword(String),
     PlayerReady,
     SetAnswer(usize, String),
+    RollInspiration(usize),
     SetCharacter(PlayerStats),
+    RollCharacter,
+    Joined(Option<SessionToken>),
     SetWebsocket(WsMeta, SplitSink<WsStream, WsMessage>),
     WebsocketClosed,
     ConnectWebsocket,
@@ -87,6 +149,10 @@ pub enum Msg {
     UseArtifact,
     TakeWound,
     AcceptFate,
+    RequestTakeover(Uuid),
+    ApproveTakeover(Uuid),
+    DenyTakeover(Uuid),
+    VoteSkip(Uuid),
     EndGame,
 }
 
@@ -94,37 +160,51 @@ fn local_storage() -> web_sys::Storage {
     web_sys::window().unwrap().local_storage().unwrap().unwrap()
 }
 
+fn password_key(game_name: &str) -> String {
+    format!("password/{}", game_name)
+}
+
 impl Component for Game {
     type Message = Msg;
     type Properties = Props;
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         let storage = local_storage();
-        let player_id = if let Some(player_id) = storage
+        let player_id = storage
             .get_item("player_id")
             .unwrap()
-            .and_then(|player_id| Uuid::parse_str(&player_id).ok())
-        {
-            player_id
-        } else {
-            let player_id = Uuid::new_v4();
-            storage
-                .set_item("player_id", &format!("{}", player_id))
-                .unwrap();
-            player_id
+            .and_then(|player_id| Uuid::parse_str(&player_id).ok());
+        let token = storage.get_item("session_token").unwrap();
+        let (player_id, token) = match (player_id, token) {
+            (Some(player_id), Some(token)) => (player_id, Some(token)),
+            // the server hands out a new player id along with the token
+            _ => (Uuid::nil(), None),
         };
-        let instance = Self {
+        let password = storage.get_item(&password_key(&props.game_name)).unwrap();
+        let mut instance = Self {
             link,
             props,
-            state: GameState::PlayerSelection {
-                player_kick_votes: HashMap::new(),
-            },
+            state: GameState::default(),
             player_id,
+            token,
+            join_task: None,
             websocket: None,
             players: HashMap::new(),
+            rules: RuleSettings::default(),
             questions: Vec::new(),
+            inspiration: HashMap::new(),
+            custom_questions: Vec::new(),
             challenge_result: None,
+            challenge_odds: None,
+            chat: Vec::new(),
             clue: None,
+            offline_players: Vec::new(),
+            takeover_requests: Vec::new(),
+            countdowns: HashMap::new(),
+            password,
+            password_rejected: false,
+            incompatible: false,
             game_over: false,
+            recap: None,
             router: RouteAgentDispatcher::new(),
         };
         instance.connect_websocket();
@@ -145,6 +225,59 @@ impl Component for Game {
                 self.send_message(ClientToServerMessage::VoteKickPlayer { player_id });
                 false
             }
+            Msg::SetUnlisted(unlisted) => {
+                self.send_message(ClientToServerMessage::SetUnlisted { unlisted });
+                false
+            }
+            Msg::AddCustomQuestion(question) => {
+                self.send_message(ClientToServerMessage::AddCustomQuestion { question });
+                false
+            }
+            Msg::RemoveCustomQuestion(idx) => {
+                self.send_message(ClientToServerMessage::RemoveCustomQuestion { idx });
+                false
+            }
+            Msg::AddBot => {
+                self.send_message(ClientToServerMessage::AddBot);
+                false
+            }
+            Msg::RemoveBot(player_id) => {
+                self.send_message(ClientToServerMessage::RemoveBot { player_id });
+                false
+            }
+            Msg::SendChat(channel, text) => {
+                self.send_message(ClientToServerMessage::Chat { channel, text });
+                false
+            }
+            Msg::SendWhisper(to, text) => {
+                self.send_message(ClientToServerMessage::Whisper { to, text });
+                false
+            }
+            Msg::SelectDeck(name) => {
+                self.send_message(ClientToServerMessage::SelectDeck { name });
+                false
+            }
+            Msg::SetRules(rules) => {
+                self.send_message(ClientToServerMessage::SetRules { rules });
+                false
+            }
+            Msg::SetPassword(password) => {
+                self.send_message(ClientToServerMessage::SetPassword { password });
+                false
+            }
+            Msg::EnterPassword(password) => {
+                local_storage()
+                    .set_item(&password_key(&self.props.game_name), &password)
+                    .ok();
+                self.password = Some(password);
+                self.password_rejected = false;
+                self.connect_websocket();
+                true
+            }
+            Msg::RollInspiration(question_idx) => {
+                self.send_message(ClientToServerMessage::RollInspiration { question_idx });
+                false
+            }
             Msg::SetAnswer(idx, text) => {
                 let updated = if let Some((_, answer)) = self.questions.get_mut(idx) {
                     *answer = text;
@@ -163,6 +296,10 @@ impl Component for Game {
                 self.send_message(ClientToServerMessage::SetCharacter { stats });
                 false
             }
+            Msg::RollCharacter => {
+                self.send_message(ClientToServerMessage::RollCharacter);
+                false
+            }
             Msg::OfferChallenge(challenge) => {
                 self.send_message(ClientToServerMessage::OfferChallenge { challenge });
                 false
@@ -200,18 +337,78 @@ impl Component for Game {
                 }
                 false
             }
+            Msg::RequestTakeover(player_id) => {
+                self.send_message(ClientToServerMessage::RequestTakeover { player_id });
+                false
+            }
+            Msg::ApproveTakeover(player_id) => {
+                self.send_message(ClientToServerMessage::ApproveTakeover { player_id });
+                false
+            }
+            Msg::DenyTakeover(player_id) => {
+                self.send_message(ClientToServerMessage::DenyTakeover { player_id });
+                false
+            }
+            Msg::VoteSkip(player_id) => {
+                self.send_message(ClientToServerMessage::VoteSkip { player_id });
+                false
+            }
+            Msg::Joined(session) => {
+                self.join_task = None;
+                if let Some(session) = session {
+                    let storage = local_storage();
+                    storage
+                        .set_item("player_id", &format!("{}", session.player_id))
+                        .unwrap();
+                    storage.set_item("session_token", &session.token).unwrap();
+                    self.player_id = session.player_id;
+                    self.token = Some(session.token);
+                    self.connect_websocket();
+                } else {
+                    self.link.send_message(Msg::WebsocketClosed);
+                }
+                false
+            }
             Msg::SetWebsocket(meta, sink) => {
                 self.websocket = Some((meta, Rc::new(RefCell::new(sink))));
+                self.send_message(ClientToServerMessage::Hello {
+                    protocol_version: PROTOCOL_VERSION,
+                    client_build: env!("CARGO_PKG_VERSION").to_owned(),
+                });
+                // the server sends the history again
+                self.chat.clear();
                 true
             }
             Msg::ReceivedMessage(message) => {
                 log::debug!("received message {:?}", message);
                 match message {
+                    ServerToClientMessage::Welcome => false,
+                    ServerToClientMessage::IncompatibleClient { protocol_version } => {
+                        log::warn!(
+                            "Server speaks protocol {}, this client {}",
+                            protocol_version,
+                            PROTOCOL_VERSION
+                        );
+                        self.incompatible = true;
+                        true
+                    }
                     ServerToClientMessage::GameIsFull => false,
                     ServerToClientMessage::GameIsOngoing => false,
+                    ServerToClientMessage::InvalidSession => {
+                        // join again once the connection is closed
+                        local_storage().remove_item("session_token").ok();
+                        self.token = None;
+                        false
+                    }
+                    ServerToClientMessage::WrongPassword => {
+                        self.password_rejected = true;
+                        true
+                    }
                     ServerToClientMessage::PushState {
                         players,
                         game_state,
+                        rules,
+                        countdowns,
                     } => {
                         if let (
                             GameState::Room {
@@ -248,6 +445,19 @@ impl Component for Game {
 
                         self.state = game_state;
                         self.players = players;
+                        self.rules = rules;
+                        self.countdowns = countdowns;
+                        true
+                    }
+                    ServerToClientMessage::CustomQuestions { questions } => {
+                        self.custom_questions = questions;
+                        true
+                    }
+                    ServerToClientMessage::Inspiration {
+                        question_idx,
+                        prompt,
+                    } => {
+                        self.inspiration.insert(question_idx, prompt);
                         true
                     }
                     ServerToClientMessage::Questions { questions } => {
@@ -265,10 +475,34 @@ impl Component for Game {
                         self.challenge_result = None;
                         true
                     }
+                    ServerToClientMessage::ChallengeOdds(odds) => {
+                        self.challenge_odds = Some(odds);
+                        true
+                    }
+                    ServerToClientMessage::ChatMessage(message) => {
+                        self.chat.push(ChatEntry::Message(message));
+                        true
+                    }
+                    ServerToClientMessage::Whisper(whisper) => {
+                        self.chat.push(ChatEntry::Whisper(whisper));
+                        true
+                    }
                     ServerToClientMessage::ChallengeResult(results) => {
                         self.challenge_result = Some(results);
                         true
                     }
+                    ServerToClientMessage::Recap(recap) => {
+                        self.recap = Some(recap);
+                        true
+                    }
+                    ServerToClientMessage::Takeovers {
+                        offline_players,
+                        requests,
+                    } => {
+                        self.offline_players = offline_players;
+                        self.takeover_requests = requests;
+                        true
+                    }
                     _ => false,
                 }
             }
@@ -277,7 +511,8 @@ impl Component for Game {
                 false
             }
             Msg::WebsocketClosed => {
-                if !self.game_over {
+                // after a wrong password, only reconnect once the user entered another one
+                if !self.game_over && !self.password_rejected && !self.incompatible {
                     let link = self.link.clone();
                     let closure = Closure::once_into_js(move || {
                         link.send_message(Msg::ConnectWebsocket);
@@ -322,29 +557,88 @@ impl Component for Game {
         let set_name_callback = self.link.callback(Msg::SetPlayerName);
         let set_ready_callback = self.link.callback(|_| Msg::PlayerReady);
         let vote_kick_callback = self.link.callback(Msg::VoteKick);
+        let set_unlisted_callback = self.link.callback(Msg::SetUnlisted);
+        let set_password_callback = self.link.callback(Msg::SetPassword);
+        let set_rules_callback = self.link.callback(Msg::SetRules);
+        let select_deck_callback = self.link.callback(Msg::SelectDeck);
+        let add_custom_question_callback = self.link.callback(Msg::AddCustomQuestion);
+        let remove_custom_question_callback = self.link.callback(Msg::RemoveCustomQuestion);
+        let add_bot_callback = self.link.callback(|_| Msg::AddBot);
+        let remove_bot_callback = self.link.callback(Msg::RemoveBot);
         let set_answer_callback = self.link.callback(|(idx, text)| Msg::SetAnswer(idx, text));
+        let roll_inspiration_callback = self.link.callback(Msg::RollInspiration);
         let set_character_callback = self.link.callback(Msg::SetCharacter);
+        let roll_character_callback = self.link.callback(|_| Msg::RollCharacter);
         let reject_secret_callback = self.link.callback(|_| Msg::RejectSecret);
         let accept_challenge_callback = self.link.callback(|_| Msg::AcceptChallenge);
         let reject_challenge_callback = self.link.callback(|_| Msg::RejectChallenge);
         let use_artifact_callback = self.link.callback(|_| Msg::UseArtifact);
         let take_wound_callback = self.link.callback(|_| Msg::TakeWound);
         let accept_fate_callback = self.link.callback(|_| Msg::AcceptFate);
+        let (send_chat_callback, whisper_callback) = if self.props.spectator {
+            (None, None)
+        } else {
+            (
+                Some(
+                    self.link
+                        .callback(|(channel, text)| Msg::SendChat(channel, text)),
+                ),
+                Some(self.link.callback(|(to, text)| Msg::SendWhisper(to, text))),
+            )
+        };
+        let gms = match &self.state {
+            GameState::Room { gm, .. } => vec![*gm],
+            GameState::FinalBattle { gms, .. } => gms.iter().cloned().collect(),
+            _ => Vec::new(),
+        };
+        let chat = html! {
+            <ChatPanel player_id=self.player_id players=self.players.clone() messages=self.chat.clone() gms=gms send=send_chat_callback whisper=whisper_callback/>
+        };
 
         html! {
             <ybc::Tile vertical=false ctx=TileCtx::Ancestor>
             {
-                if self.websocket.is_some() || self.game_over {
+                if self.incompatible {
+                    let reload = Callback::from(|_| {
+                        web_sys::window().unwrap().location().reload().ok();
+                    });
+                    html! {
+                        <ybc::Tile classes="box" ctx=TileCtx::Child>
+                            <ybc::Title size=HeaderSize::Is4>{"The Temple Has Moved On"}</ybc::Title>
+                            <p class="block">{"The server was updated since this page was loaded. Reload the page to get the current version and carry on where you left off."}</p>
+                            <ybc::Button onclick=reload><Icon classes="icon" name=IconName::Redo/><span>{"Reload"}</span></ybc::Button>
+                        </ybc::Tile>
+                    }
+                } else if self.password_rejected {
+                    let enter_password_callback = self.link.callback(Msg::EnterPassword);
+                    html! {
+                        <EnterPassword retry=self.password.is_some() enter=enter_password_callback/>
+                    }
+                } else if self.websocket.is_some() || self.game_over {
                     log::debug!("state = {:?}", self.state);
                     match &self.state {
-                        GameState::PlayerSelection { player_kick_votes } => {
+                        _ if self.props.spectator && !self.game_over => {
+                            html! {
+                                <Spectate players=self.players.clone() state=self.state.clone() rules=self.rules chat=chat/>
+                            }
+                        }
+                        // somebody who joined after the game started
+                        _ if !self.props.spectator
+                            && !self.players.contains_key(&self.player_id)
+                            && !matches!(self.state, GameState::PlayerSelection { .. } | GameState::Victory | GameState::Failure) => {
+                            let request_takeover_callback = self.link.callback(Msg::RequestTakeover);
                             html! {
-                                <Lobby set_name=set_name_callback set_ready=set_ready_callback vote_kick=vote_kick_callback player_id=self.player_id players=self.players.clone() player_kick_votes=player_kick_votes.clone()/>
+                                <TakeOver player_id=self.player_id players=self.players.clone() offline_players=self.offline_players.clone() requests=self.takeover_requests.clone() request_takeover=request_takeover_callback/>
+                            }
+                        }
+                        GameState::PlayerSelection { player_kick_votes, unlisted, password, deck, custom_questions } => {
+                            html! {
+                                <Lobby set_name=set_name_callback set_ready=set_ready_callback vote_kick=vote_kick_callback set_unlisted=set_unlisted_callback set_password=set_password_callback player_id=self.player_id players=self.players.clone() player_kick_votes=player_kick_votes.clone() unlisted=*unlisted password=password.clone() rules=self.rules set_rules=set_rules_callback deck=deck.clone() select_deck=select_deck_callback custom_question_count=*custom_questions own_questions=self.custom_questions.clone() add_custom_question=add_custom_question_callback remove_custom_question=remove_custom_question_callback add_bot=add_bot_callback remove_bot=remove_bot_callback chat=chat/>
                             }
                         }
                         GameState::DefineEvil => {
                             html! {
-                                <DefineEvil player_id=self.player_id players=self.players.clone() questions=self.questions.clone() set_answer=set_answer_callback set_ready=set_ready_callback/>
+                                <DefineEvil player_id=self.player_id players=self.players.clone() questions=self.questions.clone() inspiration=self.inspiration.clone() roll_inspiration=roll_inspiration_callback set_answer=set_answer_callback set_ready=set_ready_callback chat=chat/>
                             }
                         }
                         GameState::CharacterCreation => {
@@ -354,12 +648,12 @@ impl Component for Game {
                                 PlayerStats::default()
                             };
                             html! {
-                                <CreateCharacter stats=stats player_id=self.player_id players=self.players.clone() set_character=set_character_callback set_ready=set_ready_callback/>
+                                <CreateCharacter stats=stats player_id=self.player_id players=self.players.clone() set_character=set_character_callback roll_character=roll_character_callback set_ready=set_ready_callback chat=chat/>
                             }
                         }
                         GameState::CharacterIntroduction => {
                             html! {
-                                <IntroduceCharacters player_id=self.player_id players=self.players.clone() set_ready=set_ready_callback/>
+                                <IntroduceCharacters player_id=self.player_id players=self.players.clone() set_ready=set_ready_callback chat=chat/>
                             }
                         }
                         GameState::Room { room_idx, gm, successes, failures, known_clues, challenge } => {
@@ -368,6 +662,7 @@ impl Component for Game {
                             let room_state = RoomState {
                                 challenge: challenge.clone(),
                                 challenge_result: self.challenge_result.clone(),
+                                challenge_odds: self.challenge_odds.clone(),
                                 clue: self.clue.clone(),
                             };
                             html! {
@@ -378,6 +673,7 @@ impl Component for Game {
                                     gm=gm
                                     successes=successes
                                     failures=failures
+                                    rules=self.rules
                                     state=room_state
                                     known_clues=known_clues.clone()
                                     reject_secret=reject_secret_callback
@@ -388,6 +684,7 @@ impl Component for Game {
                                     take_wound=take_wound_callback
                                     accept_fate=accept_fate_callback
                                     send_ready=player_ready_callback
+                                    chat=chat
                                 />
                             }
                         }
@@ -403,6 +700,7 @@ impl Component for Game {
                             let evil_state = EvilState {
                                 challenge: challenge.as_ref().map(|challenge| (challenge.clone(), chosen_clue.unwrap_or(0))),
                                 challenge_result: self.challenge_result.clone(),
+                                challenge_odds: self.challenge_odds.clone(),
                             };
                             html! {
                                 <FaceEvil
@@ -419,19 +717,20 @@ impl Component for Game {
                                     use_artifact=use_artifact_callback
                                     take_wound=take_wound_callback
                                     accept_fate=accept_fate_callback
+                                    chat=chat
                                 />
                             }
                         }
                         GameState::Victory => {
                             let end_game_callback = self.link.callback(|_| Msg::EndGame);
                             html! {
-                                <Success set_ready=end_game_callback/>
+                                <Success set_ready=end_game_callback recap=self.recap.clone()/>
                             }
                         }
                         GameState::Failure => {
                             let end_game_callback = self.link.callback(|_| Msg::EndGame);
                             html! {
-                                <Failure set_ready=end_game_callback/>
+                                <Failure set_ready=end_game_callback recap=self.recap.clone()/>
                             }
                         }
                     }
@@ -441,16 +740,83 @@ impl Component for Game {
                     }
                 }
             }
+            {
+                if self.players.contains_key(&self.player_id) {
+                    let approve_callback = self.link.callback(Msg::ApproveTakeover);
+                    let deny_callback = self.link.callback(Msg::DenyTakeover);
+                    html! {
+                        <TakeoverRequests player_id=self.player_id players=self.players.clone() requests=self.takeover_requests.clone() approve=approve_callback deny=deny_callback/>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if self.game_over {
+                    html! {}
+                } else {
+                    let vote_skip_callback = self.link.callback(Msg::VoteSkip);
+                    html! {
+                        <Countdowns player_id=self.player_id players=self.players.clone() countdowns=self.countdowns.clone() vote_skip=vote_skip_callback/>
+                    }
+                }
+            }
             </ybc::Tile>
         }
     }
 }
 
 impl Game {
-    fn connect_websocket(&self) {
-        let game_name = self.props.game_name.clone();
+    fn join(&mut self) {
+        if self.join_task.is_some() {
+            return;
+        }
+        // a constant request can't be malformed
+        let request = Request::post("/api/join").body(Nothing).unwrap();
+        self.join_task = FetchService::fetch(
+            request,
+            self.link.callback(|response: Response<Text>| {
+                Msg::Joined(
+                    response
+                        .into_body()
+                        .ok()
+                        .and_then(|body| serde_json::from_str(&body).ok()),
+                )
+            }),
+        )
+        .map_err(|err| log::error!("Failed joining: {}", err))
+        .ok();
+        if self.join_task.is_none() {
+            self.link.send_message(Msg::WebsocketClosed);
+        }
+    }
+
+    fn connect_websocket(&mut self) {
+        let token = match &self.token {
+            Some(token) => token.clone(),
+            None if !self.props.spectator => return self.join(),
+            None => String::new(),
+        };
         let link = self.link.clone();
-        let player_id = self.player_id;
+        let mut path = if self.props.spectator {
+            format!("/api/{}/spectate/ws", self.props.game_name)
+        } else {
+            format!("/api/{}/{}/ws", self.props.game_name, self.player_id)
+        };
+        let mut query = Vec::new();
+        if !self.props.spectator {
+            query.push(format!("token={}", token));
+        }
+        if let Some(password) = &self.password {
+            query.push(format!(
+                "password={}",
+                String::from(js_sys::encode_uri_component(password))
+            ));
+        }
+        if !query.is_empty() {
+            path.push('?');
+            path.push_str(&query.join("&"));
+        }
         spawn_local(async move {
             let base = {
                 let host = web_sys::window().unwrap().location().host().unwrap();
@@ -460,12 +826,7 @@ impl Game {
                     format!("ws://{}", host)
                 }
             };
-            if let Ok((meta, stream)) = WsMeta::connect(
-                &format!("{}/api/{}/{}/ws", base, game_name, player_id),
-                None,
-            )
-            .await
-            {
+            if let Ok((meta, stream)) = WsMeta::connect(&format!("{}{}", base, path), None).await {
                 let (sink, mut stream) = stream.split();
                 link.send_message(Msg::SetWebsocket(meta, sink));
                 while let Some(message) = stream.next().await {
@@ -1,6 +1,7 @@
+use super::ChallengeOddsView;
 use crate::{components::Icon, IconName};
 use std::str::FromStr;
-use tttod_data::{Attribute, Challenge, Player};
+use tttod_data::{Attribute, Challenge, ChallengeOdds, Player};
 use uuid::Uuid;
 use ybc::{TileCtx, TileSize};
 use yew::prelude::*;
@@ -29,6 +30,17 @@ pub enum Msg {
     UpdateReputation(bool),
 }
 
+impl ChallengeDialog {
+    fn challenge(&self) -> Challenge {
+        Challenge {
+            player_id: self.props.player_id,
+            attribute: self.attribute,
+            speciality_applies: self.speciality_applies,
+            reputation_applies: self.reputation_applies,
+        }
+    }
+}
+
 impl Component for ChallengeDialog {
     type Message = Msg;
     type Properties = Props;
@@ -46,12 +58,7 @@ impl Component for ChallengeDialog {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::OfferChallenge => {
-                self.props.offer_challenge.emit(Challenge {
-                    player_id: self.props.player_id,
-                    attribute: self.attribute,
-                    speciality_applies: self.speciality_applies,
-                    reputation_applies: self.reputation_applies,
-                });
+                self.props.offer_challenge.emit(self.challenge());
                 self.modal_bridge.send(ybc::ModalCloseMsg(format!(
                     "offer-challenge-{}",
                     self.props.player_id
@@ -99,6 +106,7 @@ impl Component for ChallengeDialog {
         let stats = self.props.player.stats.as_ref().unwrap();
         let player_id = self.props.player_id;
         let selected_attribute = self.attribute.as_str();
+        let odds = ChallengeOdds::new(&self.props.player, &self.challenge());
 
         html! {
             <ybc::ModalCard id={format!("offer-challenge-{}", player_id)} trigger={
@@ -173,6 +181,15 @@ impl Component for ChallengeDialog {
                                 </ybc::Card>
                             </ybc::Tile>
                         </ybc::Tile>
+                        {
+                            if let Some(odds) = odds {
+                                html! {
+                                    <ChallengeOddsView odds=odds artifact_boon=stats.artifact_boon/>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                     </>
                 }
             } footer={
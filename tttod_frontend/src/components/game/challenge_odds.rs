@@ -0,0 +1,77 @@
+use tttod_data::{ArtifactBoon, ChallengeOdds};
+use yew::prelude::*;
+
+/// The chances of a challenge, so that nobody has to work them out in their head.
+pub struct ChallengeOddsView {
+    props: Props,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub odds: ChallengeOdds,
+    pub artifact_boon: ArtifactBoon,
+}
+
+fn percent(probability: f64) -> String {
+    format!("{:.0}%", probability * 100.0)
+}
+
+fn change(probability: f64) -> String {
+    format!("{:+.0}%", probability * 100.0)
+}
+
+impl Component for ChallengeOddsView {
+    type Message = ();
+    type Properties = Props;
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let odds = &self.props.odds;
+        html! {
+            <ybc::Table narrow=true fullwidth=true>
+                <thead>
+                    <tr>
+                        <th>{format!("{}d6", odds.dice)}</th>
+                        <th>{"Success"}</th>
+                        <th>{"Possession"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    <tr>
+                        <td>{"Chance"}</td>
+                        <td>{percent(odds.roll.success)}</td>
+                        <td>{percent(odds.roll.possession)}</td>
+                    </tr>
+                    {
+                        if let Some(with_artifact) = &odds.with_artifact {
+                            html! {
+                                <tr>
+                                    <td>{format!("Using the artifact ({})", self.props.artifact_boon)}</td>
+                                    <td>{change(with_artifact.success - odds.roll.success)}</td>
+                                    <td>{change(with_artifact.possession - odds.roll.possession)}</td>
+                                </tr>
+                            }
+                        } else {
+                            html! {
+                                <tr>
+                                    <td colspan=3>{"The artifact has already been used."}</td>
+                                </tr>
+                            }
+                        }
+                    }
+                </tbody>
+            </ybc::Table>
+        }
+    }
+}
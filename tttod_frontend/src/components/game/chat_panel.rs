@@ -0,0 +1,285 @@
+use crate::{components::Icon, IconName};
+use std::collections::HashMap;
+use tttod_data::{
+    ChatChannel, ChatMessage, Condition, MentalCondition, Player, Recipient, Whisper,
+};
+use uuid::Uuid;
+use yew::prelude::*;
+
+/// Something said in the chat, to everybody or not.
+#[derive(Debug, Clone)]
+pub enum ChatEntry {
+    Message(ChatMessage),
+    Whisper(Whisper),
+}
+
+/// A button that opens the chat, so that players without voice can take part as well.
+pub struct ChatPanel {
+    link: ComponentLink<Self>,
+    props: Props,
+    open: bool,
+    /// Number of messages that have been shown already
+    seen: usize,
+    draft: String,
+    /// `in_character`, `out_of_character`, `gm` or the id of the player to whisper to
+    audience: String,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub player_id: Uuid,
+    pub players: HashMap<Uuid, Player>,
+    pub messages: Vec<ChatEntry>,
+    /// Whoever can be whispered to as the GM at the moment
+    pub gms: Vec<Uuid>,
+    /// `None` for spectators, who can only read along
+    pub send: Option<Callback<(ChatChannel, String)>>,
+    pub whisper: Option<Callback<(Recipient, String)>>,
+}
+
+pub enum Msg {
+    Toggle,
+    UpdateDraft(String),
+    UpdateAudience(String),
+    Send,
+}
+
+impl ChatPanel {
+    /// Dead and possessed characters can only talk out of character.
+    fn can_speak_in_character(&self) -> bool {
+        self.props
+            .players
+            .get(&self.props.player_id)
+            .map_or(false, |player| {
+                player.condition != Condition::Dead
+                    && player.mental_condition != MentalCondition::Possessed
+            })
+    }
+
+    fn can_whisper_to_gm(&self) -> bool {
+        !self.props.gms.is_empty() && !self.props.gms.contains(&self.props.player_id)
+    }
+
+    /// The selected audience, unless it isn't available (anymore).
+    fn audience(&self) -> &str {
+        let available = match self.audience.as_str() {
+            "in_character" => self.can_speak_in_character(),
+            "out_of_character" => true,
+            "gm" => self.can_whisper_to_gm(),
+            player_id => Uuid::parse_str(player_id).map_or(false, |player_id| {
+                player_id != self.props.player_id && self.props.players.contains_key(&player_id)
+            }),
+        };
+        if available {
+            &self.audience
+        } else {
+            "out_of_character"
+        }
+    }
+
+    fn player_name(&self, player_id: &Uuid) -> &str {
+        self.props
+            .players
+            .get(player_id)
+            .map_or("Somebody who left", |player| &player.name)
+    }
+
+    fn view_entry(&self, entry: &ChatEntry) -> Html {
+        match entry {
+            ChatEntry::Message(message) => self.view_message(message),
+            ChatEntry::Whisper(whisper) => {
+                let gm = if whisper.to_gm { " (GM)" } else { "" };
+                if whisper.from == self.props.player_id {
+                    html! {
+                        <p class="has-text-info">
+                            {"You whisper to "}<span class="has-text-weight-bold">{self.player_name(&whisper.to)}</span>{gm}{": "}{&whisper.text}
+                        </p>
+                    }
+                } else {
+                    html! {
+                        <p class="has-text-info">
+                            <span class="has-text-weight-bold">{self.player_name(&whisper.from)}</span>{" whispers to you"}{gm}{": "}{&whisper.text}
+                        </p>
+                    }
+                }
+            }
+        }
+    }
+
+    fn view_message(&self, message: &ChatMessage) -> Html {
+        let player = self.props.players.get(&message.player_id);
+        let player_name = self.player_name(&message.player_id);
+        match message.channel {
+            ChatChannel::InCharacter => {
+                let name = player.and_then(|player| player.stats.as_ref()).map_or_else(
+                    || player_name.to_owned(),
+                    |stats| format!("Dr. {}", stats.name),
+                );
+                html! {
+                    <p class="chat-in-character">
+                        <span class="has-text-weight-bold">{name}</span>{": "}{&message.text}
+                    </p>
+                }
+            }
+            ChatChannel::OutOfCharacter => html! {
+                <p>
+                    <span class="has-text-weight-bold">{player_name}</span>{" (OOC): "}{&message.text}
+                </p>
+            },
+        }
+    }
+}
+
+impl Component for ChatPanel {
+    type Message = Msg;
+    type Properties = Props;
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            props,
+            open: false,
+            seen: 0,
+            draft: String::new(),
+            audience: "in_character".to_owned(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Toggle => {
+                self.open = !self.open;
+                self.seen = self.props.messages.len();
+                true
+            }
+            Msg::UpdateDraft(draft) => {
+                self.draft = draft;
+                true
+            }
+            Msg::UpdateAudience(audience) => {
+                self.audience = audience;
+                true
+            }
+            Msg::Send => {
+                let text = self.draft.trim().to_owned();
+                if text.is_empty() {
+                    return false;
+                }
+                match (self.audience(), &self.props.send, &self.props.whisper) {
+                    ("in_character", Some(send), _) => {
+                        send.emit((ChatChannel::InCharacter, text));
+                    }
+                    ("out_of_character", Some(send), _) => {
+                        send.emit((ChatChannel::OutOfCharacter, text));
+                    }
+                    ("gm", _, Some(whisper)) => whisper.emit((Recipient::Gm, text)),
+                    (player_id, _, Some(whisper)) => {
+                        if let Ok(player_id) = Uuid::parse_str(player_id) {
+                            whisper.emit((Recipient::Player(player_id), text));
+                        }
+                    }
+                    _ => return false,
+                }
+                self.draft.clear();
+                true
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        if self.open {
+            self.seen = self.props.messages.len();
+        }
+        true
+    }
+
+    fn view(&self) -> Html {
+        let toggle_callback = self.link.callback(|_| Msg::Toggle);
+        let update_draft_callback = self.link.callback(Msg::UpdateDraft);
+        let update_audience_callback = self.link.callback(Msg::UpdateAudience);
+        let send_callback = self.link.callback(|event: FocusEvent| {
+            event.prevent_default();
+            Msg::Send
+        });
+        let unread = self.props.messages.len().saturating_sub(self.seen);
+        let in_character = self.can_speak_in_character();
+
+        html! {
+            <div class="chat">
+                <ybc::Button classes="is-small" onclick=toggle_callback>
+                    <Icon classes="icon" name=IconName::Comments/>
+                    <span>{"Chat"}</span>
+                    {
+                        if unread > 0 {
+                            html! {
+                                <span class="tag is-danger is-rounded ml-2">{unread}</span>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </ybc::Button>
+                {
+                    if self.open {
+                        html! {
+                            <div class="chat-panel box">
+                                <div class="chat-messages">
+                                    {
+                                        // the list is reversed, so that it sticks to the latest message
+                                        for self.props.messages.iter().rev().map(|entry| self.view_entry(entry))
+                                    }
+                                </div>
+                                {
+                                    if self.props.send.is_some() {
+                                        let audience = self.audience();
+                                        let mut others: Vec<_> = self.props.players.iter().filter(|(player_id, _)| **player_id != self.props.player_id).collect();
+                                        others.sort_by(|(id_a, _), (id_b, _)| id_a.cmp(id_b));
+                                        html! {
+                                            <form onsubmit=send_callback>
+                                                <ybc::Field addons=true>
+                                                    <ybc::Control>
+                                                        <ybc::Select name="chat-audience" value=audience update=update_audience_callback>
+                                                            <option value="in_character" disabled=!in_character selected=audience == "in_character">{"In character"}</option>
+                                                            <option value="out_of_character" selected=audience == "out_of_character">{"Out of character"}</option>
+                                                            {
+                                                                if self.can_whisper_to_gm() {
+                                                                    html! {
+                                                                        <option value="gm" selected=audience == "gm">{"Whisper to the GM"}</option>
+                                                                    }
+                                                                } else {
+                                                                    html! {}
+                                                                }
+                                                            }
+                                                            {
+                                                                for others.into_iter().map(|(player_id, player)| {
+                                                                    let value = player_id.to_string();
+                                                                    html! {
+                                                                        <option selected=audience == value value=value>{format!("Whisper to {}", player.name)}</option>
+                                                                    }
+                                                                })
+                                                            }
+                                                        </ybc::Select>
+                                                    </ybc::Control>
+                                                    <ybc::Control expanded=true>
+                                                        <ybc::Input name="chat" update=update_draft_callback value=self.draft.clone() placeholder="Say something" rounded=false/>
+                                                    </ybc::Control>
+                                                    <ybc::Control>
+                                                        <ybc::Button disabled=self.draft.trim().is_empty()><Icon classes="icon" name=IconName::PaperPlane/></ybc::Button>
+                                                    </ybc::Control>
+                                                </ybc::Field>
+                                            </form>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{components::Icon, IconName};
+use std::{collections::HashMap, time::Duration};
+use tttod_data::{Countdown, Player};
+use uuid::Uuid;
+use yew::{
+    prelude::*,
+    services::interval::{IntervalService, IntervalTask},
+};
+
+/// Shows who the game is waiting for and how long they have left.
+///
+/// The server only sends the countdowns when something about them changes, so the clock runs here in between.
+pub struct Countdowns {
+    props: Props,
+    countdowns: HashMap<Uuid, Countdown>,
+    _ticks: IntervalTask,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub player_id: Uuid,
+    pub players: HashMap<Uuid, Player>,
+    pub countdowns: HashMap<Uuid, Countdown>,
+    pub vote_skip: Callback<Uuid>,
+}
+
+pub enum Msg {
+    Tick,
+}
+
+fn format_time(seconds: usize) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Component for Countdowns {
+    type Message = Msg;
+    type Properties = Props;
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let ticks = IntervalService::spawn(Duration::from_secs(1), link.callback(|_| Msg::Tick));
+        Self {
+            countdowns: props.countdowns.clone(),
+            props,
+            _ticks: ticks,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::Tick => {
+                let mut changed = false;
+                for countdown in self.countdowns.values_mut() {
+                    if !countdown.paused && countdown.seconds_left > 0 {
+                        countdown.seconds_left -= 1;
+                        changed = true;
+                    }
+                }
+                changed
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.countdowns = props.countdowns.clone();
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        if self.countdowns.is_empty() {
+            return html! {};
+        }
+        let mut waiting_for: Vec<_> = self
+            .countdowns
+            .iter()
+            .filter_map(|(player_id, countdown)| {
+                self.props
+                    .players
+                    .get(player_id)
+                    .map(|player| (*player_id, player, countdown))
+            })
+            .collect();
+        waiting_for.sort_by(|(_, a, _), (_, b, _)| a.name.cmp(&b.name));
+        let nudge = self
+            .countdowns
+            .get(&self.props.player_id)
+            .map_or(false, |countdown| countdown.seconds_left == 0);
+        html! {
+            <div class="box countdowns">
+                {
+                    if nudge {
+                        html! {
+                            <p class="block has-text-weight-bold has-text-danger">{"Time's up, the others are waiting for you!"}</p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                <ul>
+                {
+                    for waiting_for.into_iter().map(|(player_id, player, countdown)| {
+                        let status = if countdown.paused {
+                            "paused while offline".to_owned()
+                        } else if countdown.seconds_left == 0 {
+                            "time's up".to_owned()
+                        } else {
+                            format!("{} left", format_time(countdown.seconds_left))
+                        };
+                        let vote = match &countdown.skip_votes {
+                            Some(votes) if player_id != self.props.player_id && self.props.players.contains_key(&self.props.player_id) => {
+                                if votes.contains(&self.props.player_id) {
+                                    html! {
+                                        <span class="tag">{format!("{} voted to skip", votes.len())}</span>
+                                    }
+                                } else {
+                                    html! {
+                                        <ybc::Button classes="is-small" onclick=self.props.vote_skip.reform(move |_| player_id)>
+                                            <Icon classes="icon" name=IconName::Forward/><span>{"Vote to Skip"}</span>
+                                        </ybc::Button>
+                                    }
+                                }
+                            }
+                            _ => html! {},
+                        };
+                        html! {
+                            <li>
+                                <Icon classes="icon" name=IconName::Hourglass/>
+                                <span>{format!("Waiting for {}: {} ", player.name, status)}</span>
+                                {vote}
+                            </li>
+                        }
+                    })
+                }
+                </ul>
+            </div>
+        }
+    }
+}
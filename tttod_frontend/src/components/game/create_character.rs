@@ -18,7 +18,10 @@ pub struct Props {
     pub player_id: Uuid,
     pub players: HashMap<Uuid, Player>,
     pub set_character: Callback<PlayerStats>,
+    /// Asks the server to make up a character
+    pub roll_character: Callback<()>,
     pub set_ready: Callback<()>,
+    pub chat: Html,
 }
 
 pub enum Msg {
@@ -173,6 +176,7 @@ impl Component for CreateCharacter {
 
     fn view(&self) -> Html {
         let ready_callback = self.link.callback(|_| Msg::Ready);
+        let roll_character_callback = self.props.roll_character.reform(|_| ());
         let update_name_callback = self.link.callback(Msg::UpdateName);
         let update_speciality_callback = self.link.callback(Msg::UpdateSpeciality);
         let update_other_speciality_callback = self.link.callback(Msg::UpdateOtherSpeciality);
@@ -225,10 +229,12 @@ impl Component for CreateCharacter {
                     <ybc::Tile classes="button-with-player-list" ctx=TileCtx::Child size=TileSize::Three>
                         <ybc::Button loading=self.loading disabled=invalid_stats onclick=ready_callback><Icon classes="icon" name=IconName::Mountain/><span>{"Let's Go!"}</span></ybc::Button>
                         <PlayerList player_id=self.props.player_id players=&self.props.players/>
+                        {self.props.chat.clone()}
                     </ybc::Tile>
                 </ybc::Tile>
                 <ybc::Tile vertical=false ctx=TileCtx::Child>
                     <div class="block">
+                        <ybc::Button classes="block" disabled=self.loading onclick=roll_character_callback><Icon classes="icon" name=IconName::Dice/><span>{"Roll Me a Character"}</span></ybc::Button>
                         <div class="field is-horizontal">
                             <div class="field-label is-normal">
                                 <label class="label">{"Name:"}</label>
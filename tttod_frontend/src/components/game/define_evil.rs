@@ -17,8 +17,12 @@ pub struct Props {
     pub player_id: Uuid,
     pub players: HashMap<Uuid, Player>,
     pub questions: Vec<(String, String)>,
+    /// Prompts the server rolled, by question index
+    pub inspiration: HashMap<usize, String>,
+    pub roll_inspiration: Callback<usize>,
     pub set_answer: Callback<(usize, String)>,
     pub set_ready: Callback<()>,
+    pub chat: Html,
 }
 
 pub enum Msg {
@@ -70,6 +74,7 @@ impl Component for DefineEvil {
                     <ybc::Tile classes="button-with-player-list" ctx=TileCtx::Child size=TileSize::Four>
                         <ybc::Button loading=self.loading disabled=self.props.questions.iter().any(|(q, a)| a.is_empty()) onclick=ready_callback><Icon classes="icon" name=IconName::ChalkboardTeacher/><span>{"Look Into the Mirror"}</span></ybc::Button>
                         <PlayerList player_id=self.props.player_id players=&self.props.players/>
+                        {self.props.chat.clone()}
                     </ybc::Tile>
                 </ybc::Tile>
                 <ybc::Tile vertical=false ctx=TileCtx::Child>
@@ -79,9 +84,20 @@ impl Component for DefineEvil {
                     {
                         for self.props.questions.iter().enumerate().map(|(idx, (question, answer))| {
                             let update_callback = self.link.callback(move |text| Msg::SetAnswer(idx, text));
+                            let roll_callback = self.props.roll_inspiration.reform(move |_| idx);
                             html! {
                                 <ybc::Box>
                                     <ybc::Title size=HeaderSize::Is4>{question}</ybc::Title>
+                                    <div class="inspiration block">
+                                        <ybc::Button classes="is-small" disabled=self.loading onclick=roll_callback><Icon classes="icon" name=IconName::Dice/><span>{"Inspire Me"}</span></ybc::Button>
+                                        {
+                                            if let Some(prompt) = self.props.inspiration.get(&idx) {
+                                                html! { <em>{prompt}</em> }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                    </div>
                                     <ybc::TextArea name={format!("q{}", idx+1)} rows=5 disabled=self.loading value=answer.clone() placeholder="Answer" update=update_callback size=Size::Medium/>
                                 </ybc::Box>
                             }
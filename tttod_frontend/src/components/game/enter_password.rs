@@ -0,0 +1,82 @@
+use crate::{components::Icon, IconName};
+use ybc::{HeaderSize, InputType, TileCtx};
+use yew::prelude::*;
+
+/// Shown when the server turned us away from a private game.
+pub struct EnterPassword {
+    link: ComponentLink<Self>,
+    props: Props,
+    password: String,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    /// We already tried a password that didn't work
+    pub retry: bool,
+    pub enter: Callback<String>,
+}
+
+pub enum Msg {
+    UpdatePassword(String),
+    Enter,
+}
+
+impl Component for EnterPassword {
+    type Message = Msg;
+    type Properties = Props;
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            link,
+            props,
+            password: String::new(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            Msg::UpdatePassword(password) => {
+                self.password = password;
+                true
+            }
+            Msg::Enter => {
+                self.props.enter.emit(self.password.clone());
+                false
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let update_password_callback = self.link.callback(Msg::UpdatePassword);
+        let enter_callback = self.link.callback(|_| Msg::Enter);
+        html! {
+            <ybc::Tile vertical=true ctx=TileCtx::Parent>
+                <ybc::Tile ctx=TileCtx::Child>
+                    <ybc::Title size=HeaderSize::Is1>{"This Expedition Is Private"}</ybc::Title>
+                    <p class="block">
+                    {
+                        if self.props.retry {
+                            "That password was wrong. Ask the players of this expedition for the right one."
+                        } else {
+                            "The players of this expedition have set a password to keep strangers out."
+                        }
+                    }
+                    </p>
+                    <ybc::Field classes="control has-icons-left">
+                        <ybc::Input name="password" r#type=InputType::Password update=update_password_callback value=self.password.clone() placeholder="Password" rounded=false/>
+                        <span class="icon is-small is-left">
+                            <Icon name=IconName::Lock/>
+                        </span>
+                    </ybc::Field>
+                    <ybc::Field>
+                        <ybc::Button disabled=self.password.is_empty() onclick=enter_callback><Icon classes="icon" name=IconName::Unlock/><span>{"Join the Expedition"}</span></ybc::Button>
+                    </ybc::Field>
+                </ybc::Tile>
+            </ybc::Tile>
+        }
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     IconName,
 };
 use std::collections::{HashMap, HashSet};
-use tttod_data::{Challenge, ChallengeResult, Condition, MentalCondition, Player};
+use tttod_data::{Challenge, ChallengeOdds, ChallengeResult, Condition, MentalCondition, Player};
 use uuid::Uuid;
 use ybc::{HeaderSize, TileCtx, TileSize};
 use yew::prelude::*;
@@ -32,6 +32,7 @@ pub struct Props {
     pub use_artifact: yew::Callback<()>,
     pub take_wound: yew::Callback<()>,
     pub accept_fate: yew::Callback<()>,
+    pub chat: Html,
 }
 
 pub enum Msg {
@@ -95,6 +96,7 @@ impl Component for FaceEvil {
                     </ybc::Tile>
                     <ybc::Tile classes="button-with-player-list" ctx=TileCtx::Child size=TileSize::Three>
                         <PlayerList player_id=self.props.player_id players=&self.props.players/>
+                        {self.props.chat.clone()}
                     </ybc::Tile>
                 </ybc::Tile>
                 <ybc::Tile vertical=false ctx=TileCtx::Parent>
@@ -294,7 +296,7 @@ impl Component for FaceEvil {
                                         {
                                             if self.props.evil_state.challenge_result.is_none() {
                                                 html! {
-                                                    <OfferFinalChallenge clue=clue challenge=challenge player=player.clone() accept_challenge=accept_challenge_callback reject_challenge=reject_challenge_callback/>
+                                                    <OfferFinalChallenge clue=clue challenge=challenge odds=self.props.evil_state.challenge_odds.clone() player=player.clone() accept_challenge=accept_challenge_callback reject_challenge=reject_challenge_callback/>
                                                 }
                                             } else {
                                                 html! {}
@@ -328,4 +330,5 @@ impl Component for FaceEvil {
 pub struct EvilState {
     pub challenge: Option<(Challenge, usize)>,
     pub challenge_result: Option<ChallengeResult>,
+    pub challenge_odds: Option<ChallengeOdds>,
 }
@@ -1,3 +1,5 @@
+use super::RecapDownload;
+use tttod_data::Recap;
 use ybc::{HeaderSize, TileCtx};
 use yew::prelude::*;
 
@@ -8,6 +10,7 @@ pub struct Failure {
 #[derive(Debug, Clone, Properties)]
 pub struct Props {
     pub set_ready: Callback<()>,
+    pub recap: Option<Recap>,
 }
 
 impl Component for Failure {
@@ -35,6 +38,13 @@ impl Component for Failure {
                         {"The world is consumed by malevolent wrath. The GM(s) explain(s) how this happens."}
                     </p>
                     <div class="failure-image"></div>
+                    {
+                        if let Some(recap) = &self.props.recap {
+                            html! { <RecapDownload recap=recap.clone()/> }
+                        } else {
+                            html! {}
+                        }
+                    }
                     <ybc::Button onclick=self.props.set_ready.reform(|_| ())>{"Despair"}</ybc::Button>
                 </ybc::Tile>
             </ybc::Tile>
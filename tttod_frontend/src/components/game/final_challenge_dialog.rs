@@ -1,6 +1,7 @@
+use super::ChallengeOddsView;
 use crate::{components::Icon, IconName};
 use std::str::FromStr;
-use tttod_data::{Attribute, Challenge, Player};
+use tttod_data::{Attribute, Challenge, ChallengeOdds, Player};
 use uuid::Uuid;
 use ybc::{TileCtx, TileSize};
 use yew::prelude::*;
@@ -32,6 +33,17 @@ pub enum Msg {
     UpdateChosenClue(String),
 }
 
+impl FinalChallengeDialog {
+    fn challenge(&self) -> Challenge {
+        Challenge {
+            player_id: self.props.player_id,
+            attribute: self.attribute,
+            speciality_applies: self.speciality_applies,
+            reputation_applies: self.reputation_applies,
+        }
+    }
+}
+
 impl Component for FinalChallengeDialog {
     type Message = Msg;
     type Properties = Props;
@@ -50,15 +62,9 @@ impl Component for FinalChallengeDialog {
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Msg::OfferChallenge => {
-                self.props.offer_challenge.emit((
-                    Challenge {
-                        player_id: self.props.player_id,
-                        attribute: self.attribute,
-                        speciality_applies: self.speciality_applies,
-                        reputation_applies: self.reputation_applies,
-                    },
-                    self.chosen_clue,
-                ));
+                self.props
+                    .offer_challenge
+                    .emit((self.challenge(), self.chosen_clue));
                 self.modal_bridge.send(ybc::ModalCloseMsg(format!(
                     "offer-final-challenge-{}",
                     self.props.player_id
@@ -113,6 +119,7 @@ impl Component for FinalChallengeDialog {
         let stats = self.props.player.stats.as_ref().unwrap();
         let player_id = self.props.player_id;
         let selected_attribute = self.attribute.as_str();
+        let odds = ChallengeOdds::new(&self.props.player, &self.challenge());
 
         html! {
             <ybc::ModalCard id={format!("offer-final-challenge-{}", player_id)} trigger={
@@ -199,6 +206,15 @@ impl Component for FinalChallengeDialog {
                                 </ybc::Card>
                             </ybc::Tile>
                         </ybc::Tile>
+                        {
+                            if let Some(odds) = odds {
+                                html! {
+                                    <ChallengeOddsView odds=odds artifact_boon=stats.artifact_boon/>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                     </>
                 }
             } footer={
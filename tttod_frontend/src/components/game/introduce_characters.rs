@@ -17,6 +17,7 @@ pub struct Props {
     pub player_id: Uuid,
     pub players: HashMap<Uuid, Player>,
     pub set_ready: Callback<()>,
+    pub chat: Html,
 }
 
 pub enum Msg {
@@ -61,6 +62,7 @@ impl Component for IntroduceCharacters {
                     <ybc::Tile classes="button-with-player-list" ctx=TileCtx::Child size=TileSize::Three>
                         <ybc::Button loading=self.loading onclick=ready_callback><Icon classes="icon" name=IconName::Gopuram/><span>{"Enter the Temple"}</span></ybc::Button>
                         <PlayerList player_id=self.props.player_id players=&self.props.players/>
+                        {self.props.chat.clone()}
                     </ybc::Tile>
                 </ybc::Tile>
                 <ybc::Tile vertical=false ctx=TileCtx::Child>
@@ -3,12 +3,16 @@ use crate::{
     IconName,
 };
 use std::collections::{HashMap, HashSet};
-use tttod_data::Player;
+use tttod_data::{DeckInfo, Player, RuleSettings, TimeoutAction};
 use uuid::Uuid;
 use wasm_bindgen::{closure::Closure, JsCast};
 use web_sys::HtmlElement;
 use ybc::{HeaderSize, TileCtx, TileSize};
-use yew::prelude::*;
+use yew::{
+    format::{Nothing, Text},
+    prelude::*,
+    services::fetch::{FetchService, FetchTask, Request, Response},
+};
 
 pub struct Lobby {
     link: ComponentLink<Self>,
@@ -17,6 +21,11 @@ pub struct Lobby {
     player_name: String,
     keyup_closure: Closure<dyn FnMut(web_sys::KeyboardEvent)>,
     loading: bool,
+    password: String,
+    custom_question: String,
+    /// What the server offers, fetched once
+    decks: Vec<DeckInfo>,
+    _fetch_task: Option<FetchTask>,
 }
 
 #[derive(Debug, Clone, Properties)]
@@ -24,15 +33,39 @@ pub struct Props {
     pub set_name: Callback<String>,
     pub set_ready: Callback<()>,
     pub vote_kick: Callback<Uuid>,
+    pub set_unlisted: Callback<bool>,
+    pub set_password: Callback<Option<String>>,
+    pub set_rules: Callback<RuleSettings>,
+    pub select_deck: Callback<String>,
+    pub add_custom_question: Callback<String>,
+    pub remove_custom_question: Callback<usize>,
+    pub add_bot: Callback<()>,
+    pub remove_bot: Callback<Uuid>,
     pub player_id: Uuid,
     pub players: HashMap<Uuid, Player>,
     pub player_kick_votes: HashMap<Uuid, HashSet<Uuid>>,
+    pub unlisted: bool,
+    pub password: Option<String>,
+    pub rules: RuleSettings,
+    pub deck: DeckInfo,
+    /// All questions the players added, including ours
+    pub custom_question_count: usize,
+    pub own_questions: Vec<String>,
+    pub chat: Html,
 }
 
 pub enum Msg {
     UpdateName(String),
     VoteKick(Uuid),
     EnterGame,
+    UpdatePassword(String),
+    SetPassword,
+    ClearPassword,
+    ChangeRule(fn(&mut RuleSettings, Option<usize>), String),
+    ChangeTimeoutAction(String),
+    Decks(Vec<DeckInfo>),
+    UpdateCustomQuestion(String),
+    AddCustomQuestion,
 }
 
 impl Component for Lobby {
@@ -46,6 +79,21 @@ impl Component for Lobby {
                 event.stop_propagation();
             }
         }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
+        // a constant request can't be malformed
+        let request = Request::get("/api/decks").body(Nothing).unwrap();
+        let fetch_task = FetchService::fetch(
+            request,
+            link.callback(|response: Response<Text>| {
+                let decks = response
+                    .into_body()
+                    .ok()
+                    .and_then(|body| serde_json::from_str(&body).ok())
+                    .unwrap_or_default();
+                Msg::Decks(decks)
+            }),
+        )
+        .map_err(|err| log::error!("Failed fetching the list of decks: {}", err))
+        .ok();
         Self {
             link,
             props,
@@ -53,6 +101,10 @@ impl Component for Lobby {
             player_name: "".to_owned(),
             keyup_closure,
             loading: false,
+            password: String::new(),
+            custom_question: String::new(),
+            decks: Vec::new(),
+            _fetch_task: fetch_task,
         }
     }
 
@@ -80,6 +132,53 @@ impl Component for Lobby {
                 self.props.vote_kick.emit(id);
                 false
             }
+            Msg::UpdatePassword(password) => {
+                self.password = password;
+                true
+            }
+            Msg::SetPassword => {
+                self.props.set_password.emit(Some(self.password.clone()));
+                self.password.clear();
+                true
+            }
+            Msg::ClearPassword => {
+                self.props.set_password.emit(None);
+                false
+            }
+            Msg::UpdateCustomQuestion(question) => {
+                self.custom_question = question;
+                true
+            }
+            Msg::AddCustomQuestion => {
+                self.props
+                    .add_custom_question
+                    .emit(std::mem::take(&mut self.custom_question));
+                true
+            }
+            Msg::Decks(decks) => {
+                self.decks = decks;
+                true
+            }
+            Msg::ChangeRule(set, value) => {
+                let mut rules = self.props.rules;
+                set(&mut rules, value.trim().parse().ok());
+                if rules.is_valid() && rules != self.props.rules {
+                    self.props.set_rules.emit(rules);
+                }
+                false
+            }
+            Msg::ChangeTimeoutAction(action) => {
+                let mut rules = self.props.rules;
+                rules.timers.action = match action.as_str() {
+                    "auto_ready" => TimeoutAction::AutoReady,
+                    "skip_vote" => TimeoutAction::SkipVote,
+                    _ => TimeoutAction::Nudge,
+                };
+                if rules != self.props.rules {
+                    self.props.set_rules.emit(rules);
+                }
+                false
+            }
         }
     }
 
@@ -106,6 +205,10 @@ impl Component for Lobby {
     fn view(&self) -> Html {
         let game_callback = self.link.callback(|_| Msg::EnterGame);
         let update_name_callback = self.link.callback(Msg::UpdateName);
+        let update_password_callback = self.link.callback(Msg::UpdatePassword);
+        let set_password_callback = self.link.callback(|_| Msg::SetPassword);
+        let clear_password_callback = self.link.callback(|_| Msg::ClearPassword);
+        let add_bot_callback = self.props.add_bot.reform(|_| ());
         let mut players: Vec<_> = self
             .props
             .players
@@ -130,10 +233,44 @@ impl Component for Lobby {
                             <ybc::Field>
                                 <ybc::Button loading=self.loading disabled=self.player_name.is_empty() onclick=game_callback><Icon classes="icon" name=IconName::BookDead/><span>{"Face the Evil"}</span></ybc::Button>
                             </ybc::Field>
+                            <ybc::Field>
+                                <ybc::Checkbox name="unlisted" checked=self.props.unlisted update=self.props.set_unlisted.clone()>
+                                    {" Hide this expedition from the list of open games"}
+                                </ybc::Checkbox>
+                            </ybc::Field>
+                            {
+                                if let Some(password) = &self.props.password {
+                                    html! {
+                                        <ybc::Field>
+                                            <p class="block">{"Others need the password "}<span class="has-text-weight-bold">{password}</span>{" to join."}</p>
+                                            <ybc::Button classes="is-light" onclick=clear_password_callback><Icon classes="icon" name=IconName::Unlock/><span>{"Remove Password"}</span></ybc::Button>
+                                        </ybc::Field>
+                                    }
+                                } else {
+                                    html! {
+                                        <ybc::Field addons=true>
+                                            <ybc::Control classes="has-icons-left">
+                                                <ybc::Input name="password" update=update_password_callback value=self.password.clone() placeholder="Password" rounded=false/>
+                                                <span class="icon is-small is-left">
+                                                    <Icon name=IconName::Lock/>
+                                                </span>
+                                            </ybc::Control>
+                                            <ybc::Control>
+                                                <ybc::Button classes="is-light" disabled=self.password.is_empty() onclick=set_password_callback><span>{"Make Private"}</span></ybc::Button>
+                                            </ybc::Control>
+                                        </ybc::Field>
+                                    }
+                                }
+                            }
                         </ybc::Section>
                     </ybc::Tile>
+                    { self.view_rules() }
+                    { self.view_custom_questions() }
                     <ybc::Tile classes="box" ctx=TileCtx::Child>
-                        <ybc::Title size=HeaderSize::Is4>{"Players"}</ybc::Title>
+                        <div class="button-with-player-list">
+                            <ybc::Title size=HeaderSize::Is4>{"Players"}</ybc::Title>
+                            {self.props.chat.clone()}
+                        </div>
                         <ybc::Table striped=true narrow=true fullwidth=true>
                             <thead>
                                 <tr><th></th><th class="name">{"Name"}</th><th></th></tr>
@@ -143,7 +280,12 @@ impl Component for Lobby {
                                 for players.iter().map(move |(player_id, player)| {
                                     let player_id = *player_id;
                                     let onclick_callback = self.link.callback(move |_| Msg::VoteKick(player_id));
-                                    if player.name.is_empty() {
+                                    if player.bot {
+                                        let remove_callback = self.props.remove_bot.reform(move |_| player_id);
+                                        html! {
+                                            <tr><td><Icon name=IconName::Robot/></td><td class="name">{&player.name}</td><td><ybc::Button classes="is-danger is-rounded is-light" onclick=remove_callback><Icon name=IconName::UserMinus/></ybc::Button></td></tr>
+                                        }
+                                    } else if player.name.is_empty() {
                                         html! {
                                             <tr><td><Icon name=IconName::Hourglass/></td><td class="name"><em>{"unknown"}</em></td><td>
                                             {
@@ -199,9 +341,161 @@ impl Component for Lobby {
                             }
                             </tbody>
                         </ybc::Table>
+                        {
+                            if players.len() < self.props.rules.max_players {
+                                html! {
+                                    <ybc::Button classes="is-light" onclick=add_bot_callback><Icon classes="icon" name=IconName::Robot/><span>{"Add a Bot"}</span></ybc::Button>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
                     </ybc::Tile>
                 </ybc::Tile>
             </>
         }
     }
 }
+
+impl Lobby {
+    fn view_rules(&self) -> Html {
+        let rules = &self.props.rules;
+        html! {
+            <ybc::Tile classes="box" ctx=TileCtx::Child>
+                <ybc::Title size=HeaderSize::Is4>{"Rules"}</ybc::Title>
+                { self.view_deck() }
+                <ybc::Table narrow=true fullwidth=true>
+                    <tbody>
+                        { self.view_rule("Minimum players", "", Some(rules.min_players), |rules, value| if let Some(value) = value { rules.min_players = value }) }
+                        { self.view_rule("Maximum players", "", Some(rules.max_players), |rules, value| if let Some(value) = value { rules.max_players = value }) }
+                        { self.view_rule("Questions per player", "", Some(rules.questions_per_player), |rules, value| if let Some(value) = value { rules.questions_per_player = value }) }
+                        { self.view_rule("Successes to leave a room", "", Some(rules.successes_needed), |rules, value| if let Some(value) = value { rules.successes_needed = value }) }
+                        { self.view_rule("Failures that unleash the evil", "", Some(rules.failures_needed), |rules, value| if let Some(value) = value { rules.failures_needed = value }) }
+                        { self.view_rule("Successes in the final battle", "half the players", rules.final_battle_successes, |rules, value| rules.final_battle_successes = value) }
+                    </tbody>
+                </ybc::Table>
+                { self.view_timers() }
+            </ybc::Tile>
+        }
+    }
+
+    fn view_custom_questions(&self) -> Html {
+        let update_callback = self.link.callback(Msg::UpdateCustomQuestion);
+        let add_callback = self.link.callback(|_| Msg::AddCustomQuestion);
+        let others = self.props.custom_question_count - self.props.own_questions.len();
+        html! {
+            <ybc::Tile classes="box" ctx=TileCtx::Child>
+                <ybc::Title size=HeaderSize::Is4>{"Your Questions"}</ybc::Title>
+                <p class="block">{"Ask something about the ancient evil, another player will have to answer it."}</p>
+                <ul class="block">
+                {
+                    for self.props.own_questions.iter().enumerate().map(|(idx, question)| {
+                        let remove_callback = self.props.remove_custom_question.reform(move |_| idx);
+                        html! {
+                            <li class="is-flex is-align-items-center">
+                                <span class="is-flex-grow-1">{question}</span>
+                                <ybc::Button classes="is-danger is-rounded is-light is-small" onclick=remove_callback><Icon name=IconName::Trash/></ybc::Button>
+                            </li>
+                        }
+                    })
+                }
+                </ul>
+                <ybc::Field addons=true>
+                    <ybc::Control expanded=true>
+                        <ybc::Input name="custom_question" update=update_callback value=self.custom_question.clone() placeholder="What…?" rounded=false/>
+                    </ybc::Control>
+                    <ybc::Control>
+                        <ybc::Button classes="is-light" disabled=self.custom_question.trim().is_empty() onclick=add_callback><Icon classes="icon" name=IconName::Plus/><span>{"Add"}</span></ybc::Button>
+                    </ybc::Control>
+                </ybc::Field>
+                {
+                    if others > 0 {
+                        html! {
+                            <p class="help">{format!("The others have added {} questions of their own.", others)}</p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </ybc::Tile>
+        }
+    }
+
+    fn view_deck(&self) -> Html {
+        let deck = &self.props.deck;
+        let needed = self.props.players.len() * self.props.rules.questions_per_player;
+        html! {
+            <ybc::Field label="Questions about the ancient evil".to_owned()>
+                <ybc::Control>
+                    <ybc::Select name="deck" value=deck.name.clone() update=self.props.select_deck.clone()>
+                    {
+                        for self.decks.iter().map(|deck| html! {
+                            <option value=deck.name.clone()>{format!("{} ({} questions)", deck.name, deck.questions)}</option>
+                        })
+                    }
+                    </ybc::Select>
+                </ybc::Control>
+                {
+                    if deck.questions + self.props.custom_question_count < needed {
+                        html! {
+                            <p class="help is-danger">{format!("{} only has {} questions and you wrote {}, but {} players need {}.", deck.name, deck.questions, self.props.custom_question_count, self.props.players.len(), needed)}</p>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+            </ybc::Field>
+        }
+    }
+
+    /// Seconds per phase, empty for no limit.
+    fn view_timers(&self) -> Html {
+        let timers = &self.props.rules.timers;
+        let update_action = self.link.callback(Msg::ChangeTimeoutAction);
+        let action = match timers.action {
+            TimeoutAction::Nudge => "nudge",
+            TimeoutAction::AutoReady => "auto_ready",
+            TimeoutAction::SkipVote => "skip_vote",
+        };
+        html! {
+            <>
+                <ybc::Title size=HeaderSize::Is5>{"Timers"}</ybc::Title>
+                <ybc::Table narrow=true fullwidth=true>
+                    <tbody>
+                        { self.view_rule("Seconds for the questions", "no limit", timers.define_evil, |rules, value| rules.timers.define_evil = value) }
+                        { self.view_rule("Seconds for the character", "no limit", timers.create_character, |rules, value| rules.timers.create_character = value) }
+                        { self.view_rule("Seconds for the introduction", "no limit", timers.introduce_characters, |rules, value| rules.timers.introduce_characters = value) }
+                        { self.view_rule("Seconds per turn in a room", "no limit", timers.room, |rules, value| rules.timers.room = value) }
+                    </tbody>
+                </ybc::Table>
+                <ybc::Field label="Once time is up".to_owned()>
+                    <ybc::Control>
+                        <ybc::Select name="timeout-action" value=action.to_owned() update=update_action>
+                            <option value="nudge">{"Remind everybody"}</option>
+                            <option value="auto_ready">{"Carry on for the player"}</option>
+                            <option value="skip_vote">{"Let the others vote to skip"}</option>
+                        </ybc::Select>
+                    </ybc::Control>
+                </ybc::Field>
+            </>
+        }
+    }
+
+    fn view_rule(
+        &self,
+        label: &str,
+        placeholder: &str,
+        value: Option<usize>,
+        set: fn(&mut RuleSettings, Option<usize>),
+    ) -> Html {
+        let oninput = self
+            .link
+            .callback(move |data: InputData| Msg::ChangeRule(set, data.value));
+        html! {
+            <tr>
+                <td><label class="label">{label}</label></td>
+                <td><input class="input is-small" type="number" min="1" value=value.map(|value| value.to_string()).unwrap_or_default() placeholder=placeholder oninput=oninput/></td>
+            </tr>
+        }
+    }
+}
@@ -1,8 +1,9 @@
+use super::ChallengeOddsView;
 use crate::{
     components::{Icon, ModalDialog},
     IconName,
 };
-use tttod_data::{Attribute, Challenge, Player};
+use tttod_data::{Attribute, Challenge, ChallengeOdds, Player};
 use yew::prelude::*;
 
 pub struct OfferChallenge {
@@ -13,6 +14,7 @@ pub struct OfferChallenge {
 pub struct Props {
     pub player: Player,
     pub challenge: Option<Challenge>,
+    pub odds: Option<ChallengeOdds>,
     pub accept_challenge: Callback<()>,
     pub reject_challenge: Callback<()>,
 }
@@ -106,6 +108,15 @@ impl Component for OfferChallenge {
                                     })
                                 }
                             </ybc::Box>
+                            {
+                                if let Some(odds) = &self.props.odds {
+                                    html! {
+                                        <ChallengeOddsView odds=odds.clone() artifact_boon=self.props.player.stats.as_ref().unwrap().artifact_boon/>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
                         </>
                     }
                 } else {
@@ -1,8 +1,9 @@
+use super::ChallengeOddsView;
 use crate::{
     components::{Icon, ModalDialog},
     IconName,
 };
-use tttod_data::{Attribute, Challenge, Player};
+use tttod_data::{Attribute, Challenge, ChallengeOdds, Player};
 use yew::prelude::*;
 
 pub struct OfferFinalChallenge {
@@ -13,6 +14,7 @@ pub struct OfferFinalChallenge {
 pub struct Props {
     pub player: Player,
     pub challenge: Option<Challenge>,
+    pub odds: Option<ChallengeOdds>,
     pub clue: String,
     pub accept_challenge: Callback<()>,
     pub reject_challenge: Callback<()>,
@@ -111,6 +113,15 @@ impl Component for OfferFinalChallenge {
                                     })
                                 }
                             </ybc::Box>
+                            {
+                                if let Some(odds) = &self.props.odds {
+                                    html! {
+                                        <ChallengeOddsView odds=odds.clone() artifact_boon=self.props.player.stats.as_ref().unwrap().artifact_boon/>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
                         </>
                     }
                 } else {
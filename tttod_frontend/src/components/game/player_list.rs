@@ -14,6 +14,25 @@ pub struct Props {
     pub players: HashMap<Uuid, Player>,
 }
 
+/// The name of the player, and how long ago they left if they aren't connected.
+fn describe(player: &Player) -> String {
+    if player.online {
+        return player.name.clone();
+    }
+    match player.last_seen {
+        Some(last_seen) => {
+            let now = (js_sys::Date::now() / 1000.0) as u64;
+            let minutes = now.saturating_sub(last_seen) / 60;
+            if minutes == 0 {
+                format!("{} (offline, just left)", player.name)
+            } else {
+                format!("{} (offline for {} min)", player.name, minutes)
+            }
+        }
+        None => format!("{} (offline)", player.name),
+    }
+}
+
 impl Component for PlayerList {
     type Message = ();
     type Properties = Props;
@@ -42,15 +61,16 @@ impl Component for PlayerList {
             <div class="player-list">
             {
                 for players.into_iter().map(|(_, player)| {
+                    let (icon, class) = if !player.online {
+                        (IconName::UserSlash, "user-offline")
+                    } else if player.ready {
+                        (IconName::UserGraduate, "user-ready")
+                    } else {
+                        (IconName::UserClock, "user-not-ready")
+                    };
                     html! {
-                        <span title={&player.name}>
-                            <Icon name={if player.ready { IconName::UserGraduate } else { IconName::UserClock }} classes={
-                                if player.ready {
-                                    "user-ready"
-                                } else {
-                                    "user-not-ready"
-                                }
-                            }/>
+                        <span title={describe(player)}>
+                            <Icon name={icon} classes={class}/>
                         </span>
                     }
                 })
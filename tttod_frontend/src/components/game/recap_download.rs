@@ -0,0 +1,48 @@
+use tttod_data::Recap;
+use yew::prelude::*;
+
+/// Links for saving the recap of the game, the browser makes a file out of them without asking the server.
+pub struct RecapDownload {
+    props: Props,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub recap: Recap,
+}
+
+fn data_url(mime_type: &str, content: &str) -> String {
+    format!(
+        "data:{};charset=utf-8,{}",
+        mime_type,
+        String::from(js_sys::encode_uri_component(content))
+    )
+}
+
+impl Component for RecapDownload {
+    type Message = ();
+    type Properties = Props;
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let markdown = data_url("text/markdown", &self.props.recap.to_markdown());
+        let html = data_url("text/html", &self.props.recap.to_html());
+        html! {
+            <div class="buttons">
+                <a class="button" href=markdown download="temple-of-doom.md">{"Download Recap (Markdown)"}</a>
+                <a class="button" href=html download="temple-of-doom.html">{"Download Recap (HTML)"}</a>
+            </div>
+        }
+    }
+}
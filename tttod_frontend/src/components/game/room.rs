@@ -5,8 +5,7 @@ use crate::{
 };
 use std::collections::HashMap;
 use tttod_data::{
-    Challenge, ChallengeResult, Condition, MentalCondition, Player, FAILURES_NEEDED,
-    SUCCESSES_NEEDED,
+    Challenge, ChallengeOdds, ChallengeResult, Condition, MentalCondition, Player, RuleSettings,
 };
 use uuid::Uuid;
 use ybc::{HeaderSize, TileCtx, TileSize};
@@ -27,6 +26,7 @@ pub struct Props {
     pub gm: Uuid,
     pub successes: usize,
     pub failures: usize,
+    pub rules: RuleSettings,
     pub state: RoomState,
     pub known_clues: Vec<String>,
     pub reject_secret: yew::Callback<()>,
@@ -37,6 +37,7 @@ pub struct Props {
     pub take_wound: yew::Callback<()>,
     pub accept_fate: yew::Callback<()>,
     pub send_ready: yew::Callback<()>,
+    pub chat: Html,
 }
 
 pub enum Msg {
@@ -89,7 +90,7 @@ impl Component for Room {
         let is_gm = self.props.gm == self.props.player_id;
         let player = self.props.players.get(&self.props.player_id);
 
-        let room_over = self.props.successes >= SUCCESSES_NEEDED;
+        let room_over = self.props.successes >= self.props.rules.successes_needed;
         html! {
             <ybc::Tile vertical=true ctx=TileCtx::Parent>
                 <ybc::Tile vertical=false ctx=TileCtx::Parent>
@@ -111,6 +112,7 @@ impl Component for Room {
                     </ybc::Tile>
                     <ybc::Tile classes="button-with-player-list" ctx=TileCtx::Child size=TileSize::Three>
                         <PlayerList player_id=self.props.player_id players=&self.props.players/>
+                        {self.props.chat.clone()}
                     </ybc::Tile>
                 </ybc::Tile>
                 <ybc::Tile vertical=true ctx=TileCtx::Parent>
@@ -153,8 +155,8 @@ impl Component for Room {
                         <ybc::Box classes="p-1">
                             <ybc::Table classes="success-table" fullwidth=true>
                                 <tbody>
-                                    <tr><td class="success-table-label"><label class="label">{"Failures:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-danger" max={ FAILURES_NEEDED as f32 } value={ self.props.failures as f32 }/></td><td class="success-table-summary">{self.props.failures}{"/"}{FAILURES_NEEDED}</td></tr>
-                                    <tr><td class="success-table-label"><label class="label">{"Successes:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-primary" max={ SUCCESSES_NEEDED as f32 } value={ self.props.successes as f32 }/></td><td class="success-table-summary">{self.props.successes}{"/"}{SUCCESSES_NEEDED}</td></tr>
+                                    <tr><td class="success-table-label"><label class="label">{"Failures:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-danger" max={ self.props.rules.failures_needed as f32 } value={ self.props.failures as f32 }/></td><td class="success-table-summary">{self.props.failures}{"/"}{self.props.rules.failures_needed}</td></tr>
+                                    <tr><td class="success-table-label"><label class="label">{"Successes:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-primary" max={ self.props.rules.successes_needed as f32 } value={ self.props.successes as f32 }/></td><td class="success-table-summary">{self.props.successes}{"/"}{self.props.rules.successes_needed}</td></tr>
                                 </tbody>
                             </ybc::Table>
                         </ybc::Box>
@@ -335,7 +337,7 @@ impl Component for Room {
                                 {
                                     if self.props.state.challenge_result.is_none() {
                                         html! {
-                                            <OfferChallenge challenge=self.props.state.challenge.clone() player=player.clone() accept_challenge=accept_challenge_callback reject_challenge=reject_challenge_callback/>
+                                            <OfferChallenge challenge=self.props.state.challenge.clone() odds=self.props.state.challenge_odds.clone() player=player.clone() accept_challenge=accept_challenge_callback reject_challenge=reject_challenge_callback/>
                                         }
                                     } else {
                                         html! {}
@@ -383,5 +385,6 @@ impl Component for Room {
 pub struct RoomState {
     pub challenge: Option<Challenge>,
     pub challenge_result: Option<ChallengeResult>,
+    pub challenge_odds: Option<ChallengeOdds>,
     pub clue: Option<String>,
 }
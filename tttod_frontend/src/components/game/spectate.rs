@@ -0,0 +1,186 @@
+use super::{CharacterViewer, PlayerList};
+use crate::{components::Icon, IconName};
+use std::collections::HashMap;
+use tttod_data::{GameState, Player, RuleSettings};
+use uuid::Uuid;
+use ybc::{HeaderSize, TileCtx, TileSize};
+use yew::prelude::*;
+
+/// Read-only view of a game for someone who isn't playing.
+pub struct Spectate {
+    props: Props,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub players: HashMap<Uuid, Player>,
+    pub state: GameState,
+    pub rules: RuleSettings,
+    pub chat: Html,
+}
+
+impl Component for Spectate {
+    type Message = ();
+    type Properties = Props;
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let mut players: Vec<_> = self.props.players.iter().collect();
+        players.sort_by(|(id_a, _), (id_b, _)| id_a.cmp(id_b));
+        let title = match &self.props.state {
+            GameState::PlayerSelection { .. } => "The Expedition Is Being Prepared".to_owned(),
+            GameState::DefineEvil => "The Players Define the Ancient Evil".to_owned(),
+            GameState::CharacterCreation => "The Players Create Their Archeologists".to_owned(),
+            GameState::CharacterIntroduction => "The Archeologists Introduce Themselves".to_owned(),
+            GameState::Room { room_idx, .. } => {
+                format!("Room {} of {}", room_idx + 1, self.props.players.len())
+            }
+            GameState::FinalBattle { .. } => "The Final Challenge".to_owned(),
+            GameState::Victory => "The Archeologists Have Escaped the Temple of Doom!".to_owned(),
+            GameState::Failure => "The Ancient Evil Has Been Unleashed".to_owned(),
+        };
+        html! {
+            <ybc::Tile vertical=true ctx=TileCtx::Parent>
+                <ybc::Tile vertical=false ctx=TileCtx::Parent>
+                    <ybc::Tile ctx=TileCtx::Child size=TileSize::Nine>
+                        <ybc::Title classes="is-flex is-align-items-center" size=HeaderSize::Is1>
+                            <span title="You are watching this game">
+                                <Icon classes="mr-3" name=IconName::Eye/>
+                            </span>
+                            {title}
+                        </ybc::Title>
+                    </ybc::Tile>
+                    <ybc::Tile classes="button-with-player-list" ctx=TileCtx::Child size=TileSize::Three>
+                        <PlayerList player_id=Uuid::nil() players=&self.props.players/>
+                        {self.props.chat.clone()}
+                    </ybc::Tile>
+                </ybc::Tile>
+                { self.view_progress() }
+                <ybc::Tile vertical=false classes="is-flex-wrap-wrap" ctx=TileCtx::Parent size=TileSize::Twelve>
+                {
+                    for players.into_iter().map(|(_, player)| {
+                        if player.stats.is_some() {
+                            html! {
+                                <ybc::Tile vertical=true ctx=TileCtx::Child size=TileSize::Six>
+                                    <CharacterViewer classes="m-2" player=player.clone() brief=true/>
+                                </ybc::Tile>
+                            }
+                        } else {
+                            html! {
+                                <ybc::Tile vertical=true ctx=TileCtx::Child size=TileSize::Six>
+                                    <ybc::Box classes="m-2">
+                                    {
+                                        if player.name.is_empty() {
+                                            html! { <em>{"unknown"}</em> }
+                                        } else {
+                                            html! { {&player.name} }
+                                        }
+                                    }
+                                    </ybc::Box>
+                                </ybc::Tile>
+                            }
+                        }
+                    })
+                }
+                </ybc::Tile>
+            </ybc::Tile>
+        }
+    }
+}
+
+impl Spectate {
+    fn view_progress(&self) -> Html {
+        match &self.props.state {
+            GameState::Room {
+                gm,
+                successes,
+                failures,
+                known_clues,
+                ..
+            } => {
+                html! {
+                    <ybc::Tile vertical=false ctx=TileCtx::Parent>
+                        <ybc::Tile classes="pt-4" vertical=true ctx=TileCtx::Child size=TileSize::Four>
+                            {
+                                if let Some(gm) = self.props.players.get(gm) {
+                                    html! {
+                                        <ybc::Box>
+                                            <span class="has-text-weight-bold">{gm.name.as_str()}</span>
+                                            {" is the GM for this room!"}
+                                        </ybc::Box>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            <ybc::Box classes="p-1">
+                                <ybc::Table classes="success-table" fullwidth=true>
+                                    <tbody>
+                                        <tr><td class="success-table-label"><label class="label">{"Failures:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-danger" max={ self.props.rules.failures_needed as f32 } value={ *failures as f32 }/></td><td class="success-table-summary">{failures}{"/"}{self.props.rules.failures_needed}</td></tr>
+                                        <tr><td class="success-table-label"><label class="label">{"Successes:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-primary" max={ self.props.rules.successes_needed as f32 } value={ *successes as f32 }/></td><td class="success-table-summary">{successes}{"/"}{self.props.rules.successes_needed}</td></tr>
+                                    </tbody>
+                                </ybc::Table>
+                            </ybc::Box>
+                        </ybc::Tile>
+                        { Self::view_clues("Known Secrets", known_clues) }
+                    </ybc::Tile>
+                }
+            }
+            GameState::FinalBattle {
+                remaining_clues,
+                successes,
+                target_successes,
+                ..
+            } => {
+                html! {
+                    <ybc::Tile vertical=false ctx=TileCtx::Parent>
+                        <ybc::Tile classes="pt-4" vertical=true ctx=TileCtx::Child size=TileSize::Four>
+                            <ybc::Box classes="p-1">
+                                <ybc::Table classes="success-table" fullwidth=true>
+                                    <tbody>
+                                        <tr><td class="success-table-label"><label class="label">{"Successes:"}</label></td><td class="success-table-progress"><ybc::Progress classes="is-primary" max={ *target_successes as f32 } value={ *successes as f32 }/></td><td class="success-table-summary">{successes}{"/"}{target_successes}</td></tr>
+                                    </tbody>
+                                </ybc::Table>
+                            </ybc::Box>
+                        </ybc::Tile>
+                        { Self::view_clues("Remaining Secrets", remaining_clues) }
+                    </ybc::Tile>
+                }
+            }
+            _ => html! {},
+        }
+    }
+
+    fn view_clues(title: &str, clues: &[String]) -> Html {
+        if clues.is_empty() {
+            return html! {};
+        }
+        html! {
+            <ybc::Tile vertical=true ctx=TileCtx::Child size=TileSize::Eight>
+                <ybc::Box classes="m-4">
+                    <ybc::Title size=HeaderSize::Is5>{title}</ybc::Title>
+                    <ol>
+                        {
+                            for clues.iter().map(|clue| {
+                                html! {
+                                    <li>{clue}</li>
+                                }
+                            })
+                        }
+                    </ol>
+                </ybc::Box>
+            </ybc::Tile>
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use super::RecapDownload;
+use tttod_data::Recap;
 use ybc::{HeaderSize, TileCtx};
 use yew::prelude::*;
 
@@ -8,6 +10,7 @@ pub struct Success {
 #[derive(Debug, Clone, Properties)]
 pub struct Props {
     pub set_ready: Callback<()>,
+    pub recap: Option<Recap>,
 }
 
 impl Component for Success {
@@ -36,6 +39,13 @@ impl Component for Success {
                         wondering how this exploit will affect their careers."}
                     </p>
                     <div class="success-image"></div>
+                    {
+                        if let Some(recap) = &self.props.recap {
+                            html! { <RecapDownload recap=recap.clone()/> }
+                        } else {
+                            html! {}
+                        }
+                    }
                     <ybc::Button onclick=self.props.set_ready.reform(|_| ())>{"Publish the Groundbreaking Paper"}</ybc::Button>
                 </ybc::Tile>
             </ybc::Tile>
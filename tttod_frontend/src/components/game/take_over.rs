@@ -0,0 +1,88 @@
+use super::CharacterViewer;
+use crate::{components::Icon, IconName};
+use std::collections::HashMap;
+use tttod_data::{Player, TakeoverRequest};
+use uuid::Uuid;
+use ybc::{HeaderSize, TileCtx, TileSize};
+use yew::prelude::*;
+
+/// Shown to someone who joins a game that has already started, to continue playing for somebody who left.
+pub struct TakeOver {
+    props: Props,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub player_id: Uuid,
+    pub players: HashMap<Uuid, Player>,
+    pub offline_players: Vec<Uuid>,
+    pub requests: Vec<TakeoverRequest>,
+    pub request_takeover: Callback<Uuid>,
+}
+
+impl Component for TakeOver {
+    type Message = ();
+    type Properties = Props;
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let own_request = self
+            .props
+            .requests
+            .iter()
+            .find(|request| request.replacement_id == self.props.player_id);
+        html! {
+            <ybc::Tile vertical=true ctx=TileCtx::Parent>
+                <ybc::Tile ctx=TileCtx::Child>
+                    <ybc::Title size=HeaderSize::Is1>{"The Expedition Has Already Started"}</ybc::Title>
+                    {
+                        if let Some(request) = own_request {
+                            let name = self.props.players.get(&request.player_id).map(|player| player.name.as_str()).unwrap_or_default();
+                            html! {
+                                <p class="block">{format!("Waiting for the other players to let you take over for {}…", name)}</p>
+                            }
+                        } else if self.props.offline_players.is_empty() {
+                            html! {
+                                <p class="block">{"Everybody is still on board. Somebody might leave later, though."}</p>
+                            }
+                        } else {
+                            html! {
+                                <p class="block">{"Some of the archeologists have lost their players. If the others agree, you can take over one of them."}</p>
+                            }
+                        }
+                    }
+                </ybc::Tile>
+                <ybc::Tile vertical=false classes="is-flex-wrap-wrap" ctx=TileCtx::Parent size=TileSize::Twelve>
+                {
+                    for self.props.offline_players.iter().filter_map(|player_id| self.props.players.get(player_id).map(|player| (*player_id, player))).map(|(player_id, player)| {
+                        let requested = self.props.requests.iter().any(|request| request.player_id == player_id);
+                        let request_takeover = self.props.request_takeover.reform(move |_| player_id);
+                        html! {
+                            <ybc::Tile vertical=true ctx=TileCtx::Child size=TileSize::Six>
+                                <CharacterViewer classes="m-2" player=player.clone() header={
+                                    html! {
+                                        <ybc::Button classes="is-primary is-light" disabled=requested || own_request.is_some() onclick=request_takeover>
+                                            <Icon classes="icon" name=IconName::UserPlus/><span>{"Take Over"}</span>
+                                        </ybc::Button>
+                                    }
+                                }/>
+                            </ybc::Tile>
+                        }
+                    })
+                }
+                </ybc::Tile>
+            </ybc::Tile>
+        }
+    }
+}
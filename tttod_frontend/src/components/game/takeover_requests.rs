@@ -0,0 +1,75 @@
+use crate::{
+    components::{Icon, ModalDialog},
+    IconName,
+};
+use std::collections::HashMap;
+use tttod_data::{Player, TakeoverRequest};
+use uuid::Uuid;
+use yew::prelude::*;
+
+/// Asks a player to approve somebody new taking over for a player who left.
+pub struct TakeoverRequests {
+    props: Props,
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct Props {
+    pub player_id: Uuid,
+    pub players: HashMap<Uuid, Player>,
+    pub requests: Vec<TakeoverRequest>,
+    pub approve: Callback<Uuid>,
+    pub deny: Callback<Uuid>,
+}
+
+impl Component for TakeoverRequests {
+    type Message = ();
+    type Properties = Props;
+    fn create(props: Self::Properties, _link: ComponentLink<Self>) -> Self {
+        Self { props }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        // one at a time, the next one shows up once this one is decided
+        let request = self
+            .props
+            .requests
+            .iter()
+            .find(|request| !request.approvals.contains(&self.props.player_id));
+        if let Some(request) = request {
+            let player_id = request.player_id;
+            let name = self
+                .props
+                .players
+                .get(&player_id)
+                .map(|player| player.name.clone())
+                .unwrap_or_default();
+            html! {
+                <ModalDialog id="takeover-request" is_active=true title=format!("Somebody Wants to Take Over for {}", name) body={
+                    html! {
+                        <p class="block">
+                            {format!("{} has left the expedition. A new player offers to continue playing their archeologist, including all of their duties as a GM.", name)}
+                        </p>
+                    }
+                } footer={
+                    html! {
+                        <>
+                            <ybc::Button classes="is-danger is-light" onclick=self.props.deny.reform(move |_| player_id)><Icon classes="icon" name=IconName::UserTimes/><span>{"Deny"}</span></ybc::Button>
+                            <ybc::Button onclick=self.props.approve.reform(move |_| player_id)><Icon classes="icon" name=IconName::UserCheck/><span>{"Welcome Aboard"}</span></ybc::Button>
+                        </>
+                    }
+                }/>
+            }
+        } else {
+            html! {}
+        }
+    }
+}
@@ -6,6 +6,8 @@ use yew_router::{router::Router, Switch};
 
 #[derive(Debug, Switch, Clone)]
 pub enum AppRoute {
+    #[to = "/game/{game_name}/watch"]
+    Watch(String),
     #[to = "/game/{game_name}"]
     Game(String),
     #[to = "/"]
@@ -45,6 +47,11 @@ impl Component for Root {
                                             <Game game_name=game_name/>
                                         }
                                     }
+                                    AppRoute::Watch(game_name) => {
+                                        html! {
+                                            <Game game_name=game_name spectator=true/>
+                                        }
+                                    }
                                     AppRoute::Index => {
                                         html! {
                                             <SelectGame/>
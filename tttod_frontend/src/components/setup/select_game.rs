@@ -2,10 +2,15 @@ use crate::{
     components::{root::AppRoute, Icon, Introduction},
     IconName,
 };
+use tttod_data::GameInfo;
 use wasm_bindgen::{closure::Closure, JsCast};
 use web_sys::HtmlElement;
-use ybc::{TileCtx, TileSize};
-use yew::prelude::*;
+use ybc::{HeaderSize, TileCtx, TileSize};
+use yew::{
+    format::{Nothing, Text},
+    prelude::*,
+    services::fetch::{FetchService, FetchTask, Request, Response},
+};
 use yew_router::{
     agent::RouteRequest::ChangeRoute,
     prelude::{Route, RouteAgentDispatcher},
@@ -17,11 +22,16 @@ pub struct SelectGame {
     game_name: String,
     router: RouteAgentDispatcher,
     keyup_closure: Closure<dyn FnMut(web_sys::KeyboardEvent)>,
+    open_games: Vec<GameInfo>,
+    _fetch_task: Option<FetchTask>,
 }
 
 pub enum Msg {
     UpdateName(String),
     EnterGame,
+    WatchGame,
+    OpenGames(Vec<GameInfo>),
+    JoinGame(String),
 }
 
 impl Component for SelectGame {
@@ -36,12 +46,29 @@ impl Component for SelectGame {
                 event.stop_propagation();
             }
         }) as Box<dyn FnMut(web_sys::KeyboardEvent)>);
+        // a constant request can't be malformed
+        let request = Request::get("/api/games").body(Nothing).unwrap();
+        let fetch_task = FetchService::fetch(
+            request,
+            link.callback(|response: Response<Text>| {
+                let games = response
+                    .into_body()
+                    .ok()
+                    .and_then(|body| serde_json::from_str(&body).ok())
+                    .unwrap_or_default();
+                Msg::OpenGames(games)
+            }),
+        )
+        .map_err(|err| log::error!("Failed fetching the list of open games: {}", err))
+        .ok();
         Self {
             link,
             input_ref: NodeRef::default(),
             game_name: "".to_owned(),
             router: RouteAgentDispatcher::new(),
             keyup_closure,
+            open_games: Vec::new(),
+            _fetch_task: fetch_task,
         }
     }
 
@@ -57,6 +84,21 @@ impl Component for SelectGame {
                 ))));
                 false
             }
+            Msg::WatchGame => {
+                self.router.send(ChangeRoute(Route::from(AppRoute::Watch(
+                    self.game_name.clone(),
+                ))));
+                false
+            }
+            Msg::OpenGames(games) => {
+                self.open_games = games.into_iter().filter(|game| game.joinable).collect();
+                true
+            }
+            Msg::JoinGame(game_name) => {
+                self.router
+                    .send(ChangeRoute(Route::from(AppRoute::Game(game_name))));
+                false
+            }
         }
     }
 
@@ -77,6 +119,7 @@ impl Component for SelectGame {
 
     fn view(&self) -> Html {
         let game_callback = self.link.callback(|_| Msg::EnterGame);
+        let watch_callback = self.link.callback(|_| Msg::WatchGame);
         let update_name_callback = self.link.callback(Msg::UpdateName);
         html! {
             <ybc::Tile vertical=false ctx=TileCtx::Ancestor>
@@ -95,10 +138,53 @@ impl Component for SelectGame {
                             <ybc::Field>
                                 <ybc::Button disabled=self.game_name.is_empty() onclick=game_callback><Icon classes="icon" name=IconName::Hiking/><span>{"Prepare the Expedition"}</span></ybc::Button>
                             </ybc::Field>
+                            <ybc::Field>
+                                <ybc::Button classes="is-light" disabled=self.game_name.is_empty() onclick=watch_callback><Icon classes="icon" name=IconName::Eye/><span>{"Watch the Expedition"}</span></ybc::Button>
+                            </ybc::Field>
                         </ybc::Section>
+                        { self.view_open_games() }
                     </ybc::Tile>
                 </ybc::Tile>
             </ybc::Tile>
         }
     }
 }
+
+impl SelectGame {
+    fn view_open_games(&self) -> Html {
+        if self.open_games.is_empty() {
+            return html! {};
+        }
+        html! {
+            <ybc::Box>
+                <ybc::Title size=HeaderSize::Is5>{"Open Expeditions"}</ybc::Title>
+                <ybc::Table striped=true narrow=true fullwidth=true>
+                    <tbody>
+                    {
+                        for self.open_games.iter().map(|game| {
+                            let game_name = game.name.clone();
+                            let onclick_callback = self.link.callback(move |_| Msg::JoinGame(game_name.clone()));
+                            html! {
+                                <tr>
+                                    <td>
+                                    {
+                                        if game.private {
+                                            html! { <span title="Needs a password"><Icon name=IconName::Lock/></span> }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                    </td>
+                                    <td class="name">{&game.name}</td>
+                                    <td>{game.players}{"/"}{game.max_players}</td>
+                                    <td><ybc::Button classes="is-small is-rounded" onclick=onclick_callback><Icon name=IconName::Hiking/></ybc::Button></td>
+                                </tr>
+                            }
+                        })
+                    }
+                    </tbody>
+                </ybc::Table>
+            </ybc::Box>
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use actix_codec::Framed;
+use awc::{
+    ws::{Codec, Frame, Message},
+    BoxedSocket, Client,
+};
+use futures::{SinkExt, StreamExt};
+use std::{
+    collections::VecDeque,
+    fmt,
+    time::{Duration, Instant},
+};
+use tttod_data::{
+    Bot, ClientToServerMessage, GameState, SeededDice, ServerToClientMessage, SessionToken,
+    PROTOCOL_VERSION,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Join(String),
+    Connect(String),
+    Protocol(String),
+    /// The server sent something that wasn't a message we know.
+    Garbled(serde_json::Error),
+    /// One of our own messages couldn't be turned into JSON.
+    Unsendable(serde_json::Error),
+    /// The server didn't let us into the game, with the message it sent instead.
+    Rejected(&'static str),
+    Closed,
+    TimedOut,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Join(err) => write!(f, "Failed joining: {}", err),
+            Self::Connect(err) => write!(f, "Failed connecting: {}", err),
+            Self::Protocol(err) => write!(f, "Websocket error: {}", err),
+            Self::Garbled(err) => write!(f, "Unreadable message: {}", err),
+            Self::Unsendable(err) => write!(f, "Failed encoding message: {}", err),
+            Self::Rejected(message) => write!(f, "Rejected with {}", message),
+            Self::Closed => write!(f, "Connection closed before the game was over"),
+            Self::TimedOut => write!(f, "Game wasn't over in time"),
+        }
+    }
+}
+
+/// One scripted player, connected to a game.
+pub struct ScriptedClient {
+    framed: Framed<BoxedSocket, Codec>,
+    bot: Bot,
+    /// Messages that arrived while waiting in the lobby, for the bot to see once it plays
+    backlog: VecDeque<ServerToClientMessage>,
+    /// How long it took to get a player id and open the websocket
+    pub connect_latency: Duration,
+}
+
+/// What a client saw of its game.
+pub struct Outcome {
+    /// Time from sending a message to the next message the server sent back
+    pub latencies: Vec<Duration>,
+    /// Whether the expedition was victorious
+    pub result: Result<bool, Error>,
+}
+
+impl ScriptedClient {
+    pub async fn connect(server: &str, game_name: &str) -> Result<Self, Error> {
+        let started = Instant::now();
+        let client = Client::new();
+        let session: SessionToken = client
+            .post(format!("{}/api/join", server))
+            .send()
+            .await
+            .map_err(|err| Error::Join(err.to_string()))?
+            .json()
+            .await
+            .map_err(|err| Error::Join(err.to_string()))?;
+        let (_, framed) = client
+            .ws(format!(
+                "{}/api/{}/{}/ws?token={}",
+                server, game_name, session.player_id, session.token
+            ))
+            .connect()
+            .await
+            .map_err(|err| Error::Connect(err.to_string()))?;
+        let mut client = Self {
+            framed,
+            bot: Bot::new(session.player_id),
+            backlog: VecDeque::new(),
+            connect_latency: started.elapsed(),
+        };
+        let hello = ClientToServerMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_build: concat!("tttod_loadtest ", env!("CARGO_PKG_VERSION")).to_owned(),
+        };
+        let json = serde_json::to_string(&hello).map_err(Error::Unsendable)?;
+        client.send(Message::Text(json)).await?;
+        Ok(client)
+    }
+
+    /// Lets the bot play until the game is over or the deadline passed.
+    pub async fn play(mut self, deadline: Instant) -> Outcome {
+        let mut latencies = Vec::new();
+        let result = self.play_until(deadline, &mut latencies).await;
+        self.framed.close().await.ok();
+        Outcome { latencies, result }
+    }
+
+    async fn play_until(
+        &mut self,
+        deadline: Instant,
+        latencies: &mut Vec<Duration>,
+    ) -> Result<bool, Error> {
+        let mut dice = SeededDice::from_entropy();
+        let mut sent: Option<Instant> = None;
+        loop {
+            let message = match self.backlog.pop_front() {
+                Some(message) => message,
+                None => self.next_message(deadline).await?,
+            };
+            if let Some(sent) = sent.take() {
+                latencies.push(sent.elapsed());
+            }
+            match &message {
+                ServerToClientMessage::GameIsFull => return Err(Error::Rejected("GameIsFull")),
+                ServerToClientMessage::GameIsOngoing => {
+                    return Err(Error::Rejected("GameIsOngoing"))
+                }
+                ServerToClientMessage::WrongPassword => {
+                    return Err(Error::Rejected("WrongPassword"))
+                }
+                ServerToClientMessage::InvalidSession => {
+                    return Err(Error::Rejected("InvalidSession"))
+                }
+                ServerToClientMessage::IncompatibleClient { .. } => {
+                    return Err(Error::Rejected("IncompatibleClient"))
+                }
+                ServerToClientMessage::PushState {
+                    game_state: GameState::Victory,
+                    ..
+                } => return Ok(true),
+                ServerToClientMessage::PushState {
+                    game_state: GameState::Failure,
+                    ..
+                } => return Ok(false),
+                _ => {}
+            }
+            for reply in self.bot.react(&message, &mut dice) {
+                let json = serde_json::to_string(&reply).map_err(Error::Unsendable)?;
+                self.send(Message::Text(json)).await?;
+                sent.get_or_insert_with(Instant::now);
+            }
+        }
+    }
+
+    /// Holds off the bot until the lobby lists `count` players, so that nobody readies up
+    /// before everybody is seated.
+    pub async fn wait_for_players(&mut self, count: usize, deadline: Instant) -> Result<(), Error> {
+        loop {
+            let message = self.next_message(deadline).await?;
+            let seated = match &message {
+                ServerToClientMessage::PushState {
+                    players,
+                    game_state: GameState::PlayerSelection { .. },
+                    ..
+                } => players.len() >= count,
+                // the game went on without us, or won't have us at all: the bot reports it
+                ServerToClientMessage::PushState { .. }
+                | ServerToClientMessage::GameIsFull
+                | ServerToClientMessage::GameIsOngoing
+                | ServerToClientMessage::WrongPassword
+                | ServerToClientMessage::InvalidSession
+                | ServerToClientMessage::IncompatibleClient { .. } => true,
+                _ => false,
+            };
+            if let ServerToClientMessage::PushState { .. } = message {
+                // only the latest state counts, the bot would answer every outdated one
+                self.backlog
+                    .retain(|queued| !matches!(queued, ServerToClientMessage::PushState { .. }));
+            }
+            self.backlog.push_back(message);
+            if seated {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn next_message(&mut self, deadline: Instant) -> Result<ServerToClientMessage, Error> {
+        loop {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .ok_or(Error::TimedOut)?;
+            let frame = actix_rt::time::timeout(remaining, self.framed.next())
+                .await
+                .map_err(|_| Error::TimedOut)?
+                .ok_or(Error::Closed)?
+                .map_err(|err| Error::Protocol(err.to_string()))?;
+            match frame {
+                Frame::Text(text) => return serde_json::from_slice(&text).map_err(Error::Garbled),
+                Frame::Ping(ping) => self.send(Message::Pong(ping)).await?,
+                Frame::Close(_) => return Err(Error::Closed),
+                _ => {}
+            }
+        }
+    }
+
+    async fn send(&mut self, message: Message) -> Result<(), Error> {
+        self.framed
+            .send(message)
+            .await
+            .map_err(|err| Error::Protocol(err.to_string()))
+    }
+}
@@ -0,0 +1,145 @@
+use futures::future::join_all;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use uuid::Uuid;
+
+mod client;
+use client::{Error, ScriptedClient};
+mod report;
+use report::{Errors, Latencies};
+
+#[derive(StructOpt, Debug)]
+#[structopt(
+    name = "tttod-loadtest",
+    about = "Plays many games against a server at once to see how it copes"
+)]
+struct Opt {
+    #[structopt(short, long, default_value = "http://127.0.0.1:8081")]
+    /// Base URL of the server
+    server: String,
+    #[structopt(short, long, default_value = "10")]
+    /// Number of games to play at the same time
+    games: usize,
+    #[structopt(short, long, default_value = "3")]
+    /// Scripted players in every game
+    players: usize,
+    #[structopt(short, long, default_value = "300")]
+    /// Seconds after which a game that isn't over yet counts as an error
+    timeout: u64,
+}
+
+/// Everything that happened in a single game.
+struct GameReport {
+    connect_latencies: Vec<Duration>,
+    latencies: Vec<Duration>,
+    errors: Vec<Error>,
+    /// `None` unless all players saw the end of the game
+    victory: Option<bool>,
+}
+
+async fn play_game(
+    server: &str,
+    game_name: String,
+    players: usize,
+    deadline: Instant,
+) -> GameReport {
+    let mut report = GameReport {
+        connect_latencies: Vec::new(),
+        latencies: Vec::new(),
+        errors: Vec::new(),
+        victory: None,
+    };
+    let mut clients = Vec::new();
+    let connecting = (0..players).map(|_| ScriptedClient::connect(server, &game_name));
+    for client in join_all(connecting).await {
+        match client {
+            Ok(client) => {
+                report.connect_latencies.push(client.connect_latency);
+                clients.push(client);
+            }
+            Err(err) => report.errors.push(err),
+        }
+    }
+    // the bots ready up as soon as they see the lobby, so they wait until it lists everybody,
+    // otherwise the game could start without the latecomers
+    let seated = clients.len();
+    let waiting = clients
+        .iter_mut()
+        .map(|client| client.wait_for_players(seated, deadline));
+    let waited = join_all(waiting).await;
+    let mut ready = Vec::new();
+    for (client, waited) in clients.into_iter().zip(waited) {
+        match waited {
+            Ok(()) => ready.push(client),
+            Err(err) => report.errors.push(err),
+        }
+    }
+    let mut results = Vec::new();
+    for outcome in join_all(ready.into_iter().map(|client| client.play(deadline))).await {
+        report.latencies.extend(outcome.latencies);
+        match outcome.result {
+            Ok(victory) => results.push(victory),
+            Err(err) => report.errors.push(err),
+        }
+    }
+    if results.len() == players {
+        report.victory = results.first().cloned();
+    }
+    report
+}
+
+#[actix_rt::main]
+async fn main() {
+    let opt = Opt::from_args();
+    let server = opt.server.trim_end_matches('/');
+    // keeps the games of different runs apart
+    let run = Uuid::new_v4().to_simple().to_string();
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(opt.timeout);
+
+    let reports = join_all((0..opt.games).map(|idx| {
+        play_game(
+            server,
+            format!("loadtest-{}-{}", &run[..8], idx),
+            opt.players,
+            deadline,
+        )
+    }))
+    .await;
+
+    let mut connect_latencies = Latencies::new("connect");
+    let mut latencies = Latencies::new("message");
+    let mut errors = Errors::default();
+    let (mut victories, mut failures, mut unfinished) = (0, 0, 0);
+    for report in reports {
+        connect_latencies.extend(report.connect_latencies);
+        latencies.extend(report.latencies);
+        for err in report.errors {
+            errors.add(err);
+        }
+        match report.victory {
+            Some(true) => victories += 1,
+            Some(false) => failures += 1,
+            None => unfinished += 1,
+        }
+    }
+
+    println!(
+        "Played {} games with {} players each against {} in {:.1?}",
+        opt.games,
+        opt.players,
+        server,
+        started.elapsed()
+    );
+    println!(
+        "{} victories, {} failures, {} unfinished",
+        victories, failures, unfinished
+    );
+    println!("{}", connect_latencies);
+    println!("{}", latencies);
+    println!("{} errors", errors.total());
+    print!("{}", errors);
+    if errors.total() > 0 {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,69 @@
+use std::{collections::BTreeMap, fmt, time::Duration};
+
+/// Latencies of one kind, summarized by percentiles.
+pub struct Latencies {
+    name: &'static str,
+    samples: Vec<Duration>,
+}
+
+impl Latencies {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn extend(&mut self, samples: impl IntoIterator<Item = Duration>) {
+        self.samples.extend(samples);
+    }
+
+    /// Nearest-rank percentile, the list has to be sorted.
+    fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+        let rank = (percent * sorted.len()).div_ceil(100);
+        sorted[rank.max(1) - 1]
+    }
+}
+
+impl fmt::Display for Latencies {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.samples.is_empty() {
+            return write!(f, "{:<10} no samples", self.name);
+        }
+        let mut sorted = self.samples.clone();
+        sorted.sort();
+        write!(f, "{:<10} n={:<7}", self.name, sorted.len())?;
+        for &percent in &[50, 90, 99] {
+            write!(
+                f,
+                " p{}={:<10?}",
+                percent,
+                Self::percentile(&sorted, percent)
+            )?;
+        }
+        write!(f, " max={:?}", sorted[sorted.len() - 1])
+    }
+}
+
+/// Counts the errors by their description.
+#[derive(Default)]
+pub struct Errors(BTreeMap<String, usize>);
+
+impl Errors {
+    pub fn add(&mut self, error: impl fmt::Display) {
+        *self.0.entry(error.to_string()).or_default() += 1;
+    }
+
+    pub fn total(&self) -> usize {
+        self.0.values().sum()
+    }
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (error, count) in &self.0 {
+            writeln!(f, "{:>7} × {}", count, error)?;
+        }
+        Ok(())
+    }
+}
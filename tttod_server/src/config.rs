@@ -1,10 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashMap,
     error::Error,
     fs::read_to_string,
     net::SocketAddr,
     path::{Path, PathBuf},
+    time::Duration,
 };
+use tttod_data::RuleSettings;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Server {
@@ -14,10 +17,83 @@ pub struct Server {
     pub index: Option<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Storage {
+    /// Directory the snapshots of running games are written to
+    pub path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Journal {
+    /// Directory the event journals of all games are appended to
+    pub path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Decks {
+    /// Directory with additional question decks, one YAML file each
+    pub path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Reaper {
+    /// Seconds after which a game nobody is connected to is removed
+    pub idle_timeout: u64,
+    /// Seconds a game is kept after it ended, so that its players can come back to the recap
+    pub recap_timeout: u64,
+}
+
+impl Default for Reaper {
+    fn default() -> Self {
+        Self {
+            idle_timeout: 3600,
+            recap_timeout: 300,
+        }
+    }
+}
+
+impl Reaper {
+    pub fn idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.idle_timeout)
+    }
+    pub fn recap_timeout(&self) -> Duration {
+        Duration::from_secs(self.recap_timeout)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Dice {
+    /// Fixed seeds for individual games by name, to make their dice rolls reproducible
+    #[serde(default)]
+    pub seeds: HashMap<String, u64>,
+    /// Allow reading and changing the seed of running games via `/api/{game_name}/seed`
+    #[serde(default)]
+    pub seed_api: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Session {
+    /// Key for signing session tokens, they stay valid for as long as this doesn't change
+    pub secret: Option<String>,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct Config {
     pub logging: log4rs::file::RawConfig,
     pub server: Server,
+    pub storage: Option<Storage>,
+    pub journal: Option<Journal>,
+    pub decks: Option<Decks>,
+    #[serde(default)]
+    pub reaper: Reaper,
+    #[serde(default)]
+    pub dice: Dice,
+    #[serde(default)]
+    pub session: Session,
+    /// Defaults for new games, the players can change them in the lobby
+    #[serde(default)]
+    pub rules: RuleSettings,
 }
 
 impl Config {
@@ -0,0 +1,43 @@
+use crate::{game::InternalMessage, Config, Error, Game};
+use actix_web::{get, put, web, HttpResponse};
+use futures::{channel::mpsc::unbounded, StreamExt};
+
+fn running_game(game_name: &str, games: &crate::Games, config: &Config) -> Result<Game, Error> {
+    if !config.dice.seed_api {
+        return Err(Error::NotFound);
+    }
+    games
+        .lock()
+        .map_err(|_| Error::MutexPoisoned)?
+        .get(game_name)
+        .cloned()
+        .ok_or(Error::NotFound)
+}
+
+#[get("/api/{game_name}/seed")]
+pub async fn get_seed(
+    web::Path(game_name): web::Path<String>,
+    games: web::Data<crate::Games>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, Error> {
+    let game = running_game(&game_name, &games, &config)?;
+    let (sender, mut receiver) = unbounded();
+    game.as_ref()
+        .unbounded_send(InternalMessage::GetSeed { sender })
+        .map_err(|_| Error::NotFound)?;
+    let seed = receiver.next().await.ok_or(Error::NotFound)?;
+    Ok(HttpResponse::Ok().body(seed.to_string()))
+}
+
+#[put("/api/{game_name}/seed/{seed}")]
+pub async fn set_seed(
+    web::Path((game_name, seed)): web::Path<(String, u64)>,
+    games: web::Data<crate::Games>,
+    config: web::Data<Config>,
+) -> Result<HttpResponse, Error> {
+    let game = running_game(&game_name, &games, &config)?;
+    game.as_ref()
+        .unbounded_send(InternalMessage::SetSeed { seed })
+        .map_err(|_| Error::NotFound)?;
+    Ok(HttpResponse::NoContent().finish())
+}
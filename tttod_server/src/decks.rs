@@ -0,0 +1,72 @@
+use crate::Error;
+use actix_web::{get, web, HttpResponse};
+use std::{fs, path::Path, sync::Arc};
+use tttod_data::{Deck, DeckInfo};
+
+/// All decks the players can choose from, the one from the original game always comes first.
+#[derive(Debug, Clone)]
+pub struct DeckLibrary(Arc<Vec<Deck>>);
+
+impl Default for DeckLibrary {
+    fn default() -> Self {
+        Self(Arc::new(vec![Deck::default()]))
+    }
+}
+
+fn load(path: &Path) -> Result<Deck, Error> {
+    let mut deck: Deck = serde_yaml::from_slice(&fs::read(path)?)?;
+    for question in deck.inspiration.keys() {
+        if !deck.questions.contains(question) {
+            log::warn!(
+                "Deck {} has inspiration for \"{}\", which isn't one of its questions",
+                path.display(),
+                question
+            );
+        }
+    }
+    if deck.name.is_empty() {
+        deck.name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+    }
+    Ok(deck)
+}
+
+impl DeckLibrary {
+    /// Reads all YAML files in the directory, in addition to the built-in deck.
+    pub fn load_all(path: &Path) -> Result<Self, Error> {
+        let mut decks = vec![Deck::default()];
+        let mut paths: Vec<_> = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        paths.sort();
+        for path in paths {
+            if !matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("yaml") | Some("yml")
+            ) {
+                continue;
+            }
+            match load(&path) {
+                Ok(deck) if decks.iter().any(|other| other.name == deck.name) => log::error!(
+                    "Ignoring deck {}, there already is one called {}",
+                    path.display(),
+                    deck.name
+                ),
+                Ok(deck) => decks.push(deck),
+                Err(err) => log::error!("Failed loading deck {}: {}", path.display(), err),
+            }
+        }
+        Ok(Self(Arc::new(decks)))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Deck> {
+        self.0.iter().find(|deck| deck.name == name)
+    }
+}
+
+#[get("/api/decks")]
+pub async fn list_decks(decks: web::Data<DeckLibrary>) -> HttpResponse {
+    HttpResponse::Ok().json(decks.0.iter().map(Deck::info).collect::<Vec<DeckInfo>>())
+}
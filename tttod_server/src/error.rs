@@ -7,9 +7,11 @@ pub enum Error {
     MutexPoisoned,
     Actix(actix_web::Error),
     GameIsFull,
-    UnableToJoin(SendError),
+    NotFound,
     SendError(SendError),
-    NoPlayers,
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
 }
 
 impl fmt::Display for Error {
@@ -18,9 +20,11 @@ impl fmt::Display for Error {
             Self::MutexPoisoned => write!(f, "Mutex poisoned"),
             Self::Actix(err) => err.fmt(f),
             Self::GameIsFull => write!(f, "Game is full"),
-            Self::UnableToJoin(err) => err.fmt(f),
+            Self::NotFound => write!(f, "Not found"),
             Self::SendError(err) => err.fmt(f),
-            Self::NoPlayers => write!(f, "No players left in game"),
+            Self::Io(err) => err.fmt(f),
+            Self::Json(err) => err.fmt(f),
+            Self::Yaml(err) => err.fmt(f),
         }
     }
 }
@@ -33,6 +37,24 @@ impl From<actix_web::Error> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(err: serde_yaml::Error) -> Self {
+        Self::Yaml(err)
+    }
+}
+
 impl<T> From<futures::channel::mpsc::TrySendError<T>> for Error {
     fn from(err: futures::channel::mpsc::TrySendError<T>) -> Self {
         Self::SendError(err.into_send_error())
@@ -43,15 +65,14 @@ impl actix_web::error::ResponseError for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::GameIsFull => StatusCode::BAD_REQUEST,
+            Self::NotFound => StatusCode::NOT_FOUND,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
     fn error_response(&self) -> HttpResponse {
         match self {
             Self::GameIsFull => HttpResponse::BadRequest().body("Game is full"),
-            Self::UnableToJoin(_) => {
-                HttpResponse::InternalServerError().body("Unable to join game")
-            }
+            Self::NotFound => HttpResponse::NotFound().finish(),
             _ => HttpResponse::InternalServerError().finish(),
         }
     }
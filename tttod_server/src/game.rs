@@ -1,21 +1,69 @@
-use crate::{Error, Question};
-use actix_web::rt::spawn;
-use enum_iterator::IntoEnumIterator;
+use crate::{
+    decks::DeckLibrary,
+    journal::{Entry, Journal, RecordingDice},
+    session::Sessions,
+    snapshot::Snapshot,
+    Config,
+};
+use actix_web::rt::{spawn, time::interval};
 use futures::{
     channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
     StreamExt,
 };
-use rand::{seq::SliceRandom, Rng};
-use std::collections::{HashMap, HashSet};
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 use tttod_data::{
-    ArtifactBoon, Attribute, Challenge, ChallengeResult, ClientToServerMessage, Condition,
-    GameState, MentalCondition, Player, ServerToClientMessage, FAILURES_NEEDED, SUCCESSES_NEEDED,
+    Bot, ChatChannel, ChatMessage, ClientToServerMessage, Effect, Event, GameEngine, GameInfo,
+    Recipient, SeededDice, ServerToClientMessage, Whisper,
 };
 use uuid::Uuid;
 
-const MIN_PLAYERS: usize = 3;
-const MAX_PLAYERS: usize = 5;
-const QUESTIONS_PER_PLAYER: usize = 2;
+/// Chat messages kept for clients that connect later, for everybody and for each pair of players whispering
+const CHAT_HISTORY: usize = 100;
+/// Longer chat messages are cut off
+const MAX_CHAT_LENGTH: usize = 500;
+
+/// `None` if there's nothing left to say after trimming.
+fn chat_text(text: &str) -> Option<String> {
+    let text: String = text.trim().chars().take(MAX_CHAT_LENGTH).collect();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// The key of the whispers between two players, regardless of who whispered to whom.
+fn pair(a: Uuid, b: Uuid) -> (Uuid, Uuid) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// What a client may have to present in the query string to get into a game.
+#[derive(Clone, Deserialize)]
+pub struct Credentials {
+    /// Proves that the client owns the player id it connects with
+    pub token: Option<String>,
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for Credentials {
+    // keep secrets out of the logs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("token", &self.token.as_ref().map(|_| "…"))
+            .field("password", &self.password.as_ref().map(|_| "…"))
+            .finish()
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum InternalMessage {
@@ -25,11 +73,36 @@ pub enum InternalMessage {
     },
     AddClient {
         player_id: Uuid,
+        credentials: Credentials,
         sender: UnboundedSender<ServerToClientMessage>,
     },
     RemoveClient {
         player_id: Uuid,
     },
+    /// A client that only watches the game, it only gets to see public information.
+    AddSpectator {
+        credentials: Credentials,
+        sender: UnboundedSender<ServerToClientMessage>,
+    },
+    /// Replaces the dice of the game with ones using the given seed.
+    SetSeed {
+        seed: u64,
+    },
+    /// Replies with the seed of the current dice.
+    GetSeed {
+        sender: UnboundedSender<u64>,
+    },
+    /// Replies with a summary for the list of open games, nothing if the game is unlisted.
+    GetInfo {
+        sender: UnboundedSender<Option<GameInfo>>,
+    },
+    /// Shuts the game down if nobody has been connected to it for `idle_timeout`, or it ended `recap_timeout` ago.
+    Reap {
+        idle_timeout: Duration,
+        recap_timeout: Duration,
+    },
+    /// A second has passed, for the countdowns of the phase timers.
+    Tick,
 }
 
 #[derive(Debug, Clone)]
@@ -41,1347 +114,555 @@ impl AsRef<UnboundedSender<InternalMessage>> for Game {
     }
 }
 
-impl Default for Game {
-    fn default() -> Self {
+impl Game {
+    pub fn new(name: String, config: &Config, decks: &DeckLibrary) -> Self {
+        Self::spawn(name, config, decks, GameEngine::new(config.rules))
+    }
+    /// Resumes a game from a snapshot written by a previous server instance.
+    pub fn restore(snapshot: Snapshot, config: &Config, decks: &DeckLibrary) -> Self {
+        Self::spawn(snapshot.name, config, decks, snapshot.engine)
+    }
+    /// Keeps the clock of the game running until the game shuts down.
+    async fn tick(sender: UnboundedSender<InternalMessage>) {
+        let mut ticks = interval(Duration::from_secs(1));
+        loop {
+            ticks.tick().await;
+            if sender.unbounded_send(InternalMessage::Tick).is_err() {
+                return;
+            }
+        }
+    }
+    fn spawn(name: String, config: &Config, decks: &DeckLibrary, engine: GameEngine) -> Self {
         let (sender, receiver) = unbounded();
-        spawn(GameManager::run_game(receiver));
+        let dice = config
+            .dice
+            .seeds
+            .get(&name)
+            .map(|&seed| SeededDice::new(seed))
+            .unwrap_or_else(SeededDice::from_entropy);
+        log::info!("Game {} uses dice seed {}", name, dice.seed());
+        let journal = config.journal.as_ref().and_then(|journal| {
+            let start = Entry::Start {
+                name: name.clone(),
+                seed: dice.seed(),
+                engine: engine.redacted(),
+            };
+            match Journal::open(&journal.path, &name).and_then(|mut journal| {
+                journal.write(&start)?;
+                Ok(journal)
+            }) {
+                Ok(journal) => Some(journal),
+                Err(err) => {
+                    log::error!("Failed opening journal of game {}: {}", name, err);
+                    None
+                }
+            }
+        });
+        let bots: Vec<Uuid> = engine
+            .players()
+            .keys()
+            .filter(|player_id| engine.is_bot(player_id))
+            .cloned()
+            .collect();
+        let mut instance = GameManager {
+            sender: sender.clone(),
+            receiver,
+            name,
+            storage: config.storage.as_ref().map(|storage| storage.path.clone()),
+            journal,
+            sessions: Sessions::new(config),
+            decks: decks.clone(),
+            engine,
+            dice,
+            clients: HashMap::new(),
+            spectators: Vec::new(),
+            chat: VecDeque::new(),
+            whispers: HashMap::new(),
+            idle_since: None,
+            finished_at: None,
+            unjournaled_seconds: 0,
+        };
+        // the bots of a restored game have to be started again
+        for bot_id in bots {
+            instance.attach_bot(bot_id);
+        }
+        spawn(instance.run_game());
+        spawn(Self::tick(sender.clone()));
         Game(sender)
     }
 }
 
+/// A `Disconnect` event for the given player, stamped with the time it happened.
+fn disconnect(player_id: Uuid) -> Event {
+    let at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0);
+    Event::Disconnect { player_id, at }
+}
+
+/// Connects the clients of a game to its [`GameEngine`].
 struct GameManager {
+    /// For the bots to send their messages
+    sender: UnboundedSender<InternalMessage>,
     receiver: UnboundedReceiver<InternalMessage>,
-    players: HashMap<Uuid, (Player, Vec<UnboundedSender<ServerToClientMessage>>)>,
-    player_kick_votes: HashMap<Uuid, HashSet<Uuid>>,
-    clues: Vec<(Question, String)>,
+    name: String,
+    storage: Option<PathBuf>,
+    journal: Option<Journal>,
+    sessions: Sessions,
+    decks: DeckLibrary,
+    engine: GameEngine,
+    dice: SeededDice,
+    clients: HashMap<Uuid, Vec<UnboundedSender<ServerToClientMessage>>>,
+    spectators: Vec<UnboundedSender<ServerToClientMessage>>,
+    /// The latest chat messages, at most [`CHAT_HISTORY`]
+    chat: VecDeque<ChatMessage>,
+    /// The latest whispers by [`pair`] of players, at most [`CHAT_HISTORY`] each
+    whispers: HashMap<(Uuid, Uuid), VecDeque<Whisper>>,
+    idle_since: Option<Instant>,
+    /// When the game ended, its players can still come back to the recap for a while
+    finished_at: Option<Instant>,
+    /// Ticks that changed nothing but the countdowns, journaled as one before the next entry
+    unjournaled_seconds: u64,
 }
 
 impl GameManager {
-    fn send_all_f(&mut self, mut f: impl FnMut(Uuid) -> Option<ServerToClientMessage>) {
-        for (&player_id, (_, senders)) in self.players.iter_mut() {
-            if let Some(msg) = f(player_id) {
-                senders.drain_filter(move |sender| sender.unbounded_send(msg.clone()).is_err());
-            }
-        }
-    }
-    fn send_all(&mut self, message: ServerToClientMessage) {
-        self.send_all_f(|_| Some(message.clone()));
-    }
-    fn send_to_client(
-        &mut self,
-        player_id: Uuid,
-        client_idx: usize,
-        message: ServerToClientMessage,
-    ) {
-        if let Some((_, senders)) = self.players.get_mut(&player_id) {
-            let fail = if let Some(client) = senders.get(client_idx) {
-                client.unbounded_send(message).is_err()
-            } else {
-                false
+    fn save(&self) {
+        if let Some(storage) = &self.storage {
+            let snapshot = Snapshot {
+                name: self.name.clone(),
+                engine: self.engine.clone(),
             };
-            if fail {
-                senders.remove(client_idx);
-            }
-        }
-    }
-    fn send_to(&mut self, player_id: Uuid, message: ServerToClientMessage) {
-        if let Some((_, senders)) = self.players.get_mut(&player_id) {
-            senders.drain_filter(|sender| sender.unbounded_send(message.clone()).is_err());
-        }
-    }
-    fn known_clues(&self, room_idx: usize) -> Vec<String> {
-        self.clues[0..room_idx]
-            .iter()
-            .map(|(_, clue)| clue.clone())
-            .collect()
-    }
-    fn push_state_all(&mut self, game_state: GameState) {
-        let players: HashMap<_, _> = self
-            .players
-            .iter()
-            .map(|(id, (player, _))| (*id, player.clone()))
-            .collect();
-        self.send_all(ServerToClientMessage::PushState {
-            players,
-            game_state,
-        });
-    }
-    fn push_state_to(&mut self, player_ids: impl IntoIterator<Item = Uuid>, game_state: GameState) {
-        let players: HashMap<_, _> = self
-            .players
-            .iter()
-            .map(|(id, (player, _))| (*id, player.clone()))
-            .collect();
-        for player_id in player_ids {
-            self.send_to(
-                player_id,
-                ServerToClientMessage::PushState {
-                    players: players.clone(),
-                    game_state: game_state.clone(),
-                },
-            );
-        }
-    }
-    fn roll_d6(count: usize) -> Vec<u8> {
-        let mut rng = rand::thread_rng();
-        let result = (0..count).map(|_| rng.gen_range(1, 7)).collect();
-        log::info!("Roll result = {:?}", result);
-        result
-    }
-    fn possessed_dice(dice: &[u8]) -> bool {
-        let ones = dice.iter().filter(|die| **die == 1).count();
-        if ones > 1 {
-            true
-        } else {
-            let twos = dice.iter().filter(|die| **die == 2).count();
-            twos > 1
-        }
-    }
-    fn check_success(dice: &[u8], artifact: Option<ArtifactBoon>) -> bool {
-        match artifact {
-            Some(ArtifactBoon::SuccessOnFive) => dice.contains(&5),
-            Some(ArtifactBoon::SuccessOnDoubles) => {
-                let mut results = dice.to_vec();
-                results.sort_unstable();
-                let (_, duplicates) = results.partition_dedup();
-                !duplicates.is_empty()
+            if let Err(err) = snapshot.save(storage) {
+                log::error!("Failed saving snapshot of game {}: {}", self.name, err);
             }
-            _ => dice.contains(&6),
         }
     }
-    /// Does not check whether the player has already used the artifact previously!
-    fn check_can_use_artifact(dice: &[u8], artifact_boon: ArtifactBoon) -> bool {
-        let success = Self::check_success(dice, None);
-        let possession = Self::possessed_dice(&dice);
-        if success && !possession {
-            false // no point in using it
-        } else if possession && artifact_boon == ArtifactBoon::Reroll {
-            true
-        } else if !success {
-            // check whether the artifact could make a difference
-            match artifact_boon {
-                ArtifactBoon::SuccessOnFive if !dice.contains(&5) => false,
-                ArtifactBoon::SuccessOnDoubles => {
-                    let mut results = dice.to_vec();
-                    results.sort_unstable();
-                    let (_, duplicates) = results.partition_dedup();
-                    !duplicates.is_empty()
-                }
-                _ => true,
+
+    fn remove_snapshot(&self) {
+        if let Some(storage) = &self.storage {
+            if let Err(err) = Snapshot::remove(storage, &self.name) {
+                log::error!("Failed removing snapshot of game {}: {}", self.name, err);
             }
-        } else {
-            false
         }
     }
 
-    pub async fn run_game(receiver: UnboundedReceiver<InternalMessage>) {
-        let mut instance = GameManager {
-            receiver,
-            players: HashMap::new(),
-            player_kick_votes: HashMap::new(),
-            clues: Vec::new(),
+    fn record(&mut self, entry: &Entry) {
+        let ticks = match std::mem::take(&mut self.unjournaled_seconds) {
+            0 => None,
+            seconds => Some(Entry::Event {
+                event: Event::Tick { seconds },
+            }),
         };
-
-        // wait for players
-        if let Err(err) = instance.wait_for_players().await {
-            log::error!("wait_for_players: {:?}", err);
-            return;
-        }
-        // let players define the evil
-        if let Err(err) = instance.define_evil().await {
-            log::error!("define_evil: {:?}", err);
-            return;
-        }
-        // let players create their character
-        if let Err(err) = instance.create_character().await {
-            log::error!("create_character: {:?}", err);
-            return;
-        }
-        // character introduction
-        if let Err(err) = instance.introduce_characters().await {
-            log::error!("introduce_characters: {:?}", err);
-            return;
-        }
-        // enter the temple
-        match instance.enter_temple().await {
-            Err(err) => {
-                log::error!("enter_temple: {:?}", err);
-                return;
-            }
-            Ok(success) => {
-                if success {
-                    // face the ancient evil
-                    match instance.face_ancient_evil().await {
-                        Err(err) => {
-                            log::error!("face_ancient_evil: {:?}", err);
-                        }
-                        Ok(success) => {
-                            if let Err(err) = instance.end(success).await {
-                                log::error!("end: {:?}", err);
-                            }
-                        }
-                    }
-                } else if let Err(err) = instance.end(false).await {
-                    log::error!("failed: {:?}", err);
-                }
+        if let Some(journal) = &mut self.journal {
+            if let Err(err) = ticks
+                .iter()
+                .chain(std::iter::once(entry))
+                .try_for_each(|entry| journal.write(entry))
+            {
+                log::error!("Failed writing journal of game {}: {}", self.name, err);
             }
         }
     }
 
-    async fn wait_for_players(&mut self) -> Result<(), Error> {
-        while self.players.len() < MIN_PLAYERS
-            || !self.players.values().all(|(player, _)| player.ready)
-        {
-            match self.receiver.next().await {
-                None => {
-                    log::error!("Game failed");
-                    return Err(Error::NoPlayers);
-                }
-                Some(InternalMessage::AddClient { player_id, sender }) => {
-                    let all_players: HashMap<_, _> = self
-                        .players
-                        .iter()
-                        .map(|(id, (player, _))| (*id, player.clone()))
-                        .collect();
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        let client_idx = senders.len();
-                        senders.push(sender);
-                        self.send_to_client(
-                            player_id,
-                            client_idx,
-                            ServerToClientMessage::PushState {
-                                players: all_players.clone(),
-                                game_state: GameState::PlayerSelection {
-                                    player_kick_votes: self.player_kick_votes.clone(),
-                                },
-                            },
-                        );
-                    } else if self.players.len() >= MAX_PLAYERS {
-                        sender.unbounded_send(ServerToClientMessage::GameIsFull)?;
+    async fn run_game(mut self) {
+        while let Some(message) = self.receiver.next().await {
+            match &message {
+                InternalMessage::Message { player_id, message } => log::info!(
+                    "Received message {:?} from {}",
+                    message.redacted(),
+                    player_id
+                ),
+                InternalMessage::Tick => {}
+                message => log::info!("Received event {:?}", message),
+            }
+            match message {
+                InternalMessage::AddClient {
+                    player_id,
+                    credentials,
+                    sender,
+                } => {
+                    let token = credentials.token.as_deref();
+                    if !token.is_some_and(|token| self.sessions.verify(&player_id, token)) {
+                        Self::turn_away(sender, ServerToClientMessage::InvalidSession);
+                    } else if !self.admits(Some(player_id), credentials.password) {
+                        Self::turn_away(sender, ServerToClientMessage::WrongPassword);
                     } else {
-                        self.players
-                            .insert(player_id, (Player::default(), vec![sender]));
-                        self.push_state_all(GameState::PlayerSelection {
-                            player_kick_votes: self.player_kick_votes.clone(),
-                        });
-                    }
-                }
-                Some(InternalMessage::RemoveClient { player_id }) => {
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        senders.drain_filter(|sender| sender.is_closed());
+                        self.handle(Event::Connect { player_id }, Some((player_id, sender)));
+                        self.replay_chat(player_id);
                     }
                 }
-                Some(InternalMessage::Message { player_id, message }) => match message {
-                    ClientToServerMessage::ReadyForGame => {
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            player.ready = true;
+                InternalMessage::RemoveClient { player_id } => self.remove_closed(player_id),
+                InternalMessage::Message { player_id, message } => match message {
+                    ClientToServerMessage::SelectDeck { name } => {
+                        if let Some(deck) = self.decks.get(&name).cloned() {
+                            self.handle(
+                                Event::Message {
+                                    player_id,
+                                    message: ClientToServerMessage::SetDeck { deck },
+                                },
+                                None,
+                            );
                         }
-                        self.push_state_all(GameState::PlayerSelection {
-                            player_kick_votes: self.player_kick_votes.clone(),
-                        });
                     }
-                    ClientToServerMessage::SetPlayerName { name } => {
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            player.name = name;
+                    // only the server picks the questions
+                    ClientToServerMessage::SetDeck { .. } => {}
+                    ClientToServerMessage::AddBot => {
+                        let bot_id = Uuid::new_v4();
+                        self.handle(
+                            Event::Message {
+                                player_id,
+                                message: ClientToServerMessage::SeatBot { player_id: bot_id },
+                            },
+                            None,
+                        );
+                        if self.engine.is_bot(&bot_id) {
+                            self.attach_bot(bot_id);
                         }
-                        self.push_state_all(GameState::PlayerSelection {
-                            player_kick_votes: self.player_kick_votes.clone(),
-                        });
                     }
-                    ClientToServerMessage::VoteKickPlayer {
-                        player_id: other_player_id,
-                    } => {
-                        if player_id != other_player_id {
-                            let votes = self.player_kick_votes.entry(other_player_id).or_default();
-                            votes.insert(player_id);
-                            let online_voters: HashSet<_> = self
-                                .players
-                                .iter()
-                                .filter_map(|(id, (_, senders))| {
-                                    if senders.is_empty() || id == &other_player_id {
-                                        None
-                                    } else {
-                                        Some(*id)
-                                    }
-                                })
-                                .collect();
-                            let votes = votes.intersection(&online_voters).count();
-                            let voting_player_count = self
-                                .players
-                                .keys()
-                                .filter(|id| online_voters.contains(*id))
-                                .count();
-                            if votes >= voting_player_count {
-                                self.players.remove(&other_player_id);
-                                for player_kick_votes in self.player_kick_votes.values_mut() {
-                                    player_kick_votes.remove(&other_player_id);
-                                }
-                            }
-                            self.push_state_all(GameState::PlayerSelection {
-                                player_kick_votes: self.player_kick_votes.clone(),
-                            });
-                        }
+                    // only the server picks the ids of bots
+                    ClientToServerMessage::SeatBot { .. } => {}
+                    ClientToServerMessage::Chat { channel, text } => {
+                        self.chat(player_id, channel, &text);
                     }
-                    ClientToServerMessage::RevertVoteKickPlayer {
-                        player_id: other_player_id,
-                    } => {
-                        if let Some(votes) = self.player_kick_votes.get_mut(&other_player_id) {
-                            votes.remove(&player_id);
-                        }
+                    ClientToServerMessage::Whisper { to, text } => {
+                        self.whisper(player_id, to, &text);
                     }
-                    _ => {}
+                    message => self.handle(Event::Message { player_id, message }, None),
                 },
-            }
-        }
-        Ok(())
-    }
-
-    async fn define_evil(&mut self) -> Result<(), Error> {
-        for (player, _) in self.players.values_mut() {
-            player.ready = false;
-        }
-        self.player_kick_votes.clear();
-        self.push_state_all(GameState::DefineEvil);
-
-        let mut rng = rand::thread_rng();
-        let mut questions: Vec<Question> = Question::into_enum_iter().collect();
-        questions.shuffle(&mut rng);
-        let questions_iter = questions.chunks_exact(QUESTIONS_PER_PLAYER);
-
-        let mut player_questions: HashMap<Uuid, Vec<(Question, Option<String>)>> = self
-            .players
-            .keys()
-            .zip(questions_iter)
-            .map(|(id, questions)| (*id, questions.iter().map(|q| (*q, None)).collect()))
-            .collect();
-
-        self.send_all_f(|player_id| {
-            player_questions.get(&player_id).map(|questions| {
-                let payload: Vec<(String, Option<String>)> = questions
-                    .iter()
-                    .map(|(question, _)| (format!("{}", question), None))
-                    .collect();
-                ServerToClientMessage::Questions { questions: payload }
-            })
-        });
-        while !self.players.values().all(|(player, _)| player.ready) {
-            match self.receiver.next().await {
-                None => {
-                    log::error!("Game failed");
-                    return Err(Error::NoPlayers);
-                }
-                Some(InternalMessage::AddClient { player_id, sender }) => {
-                    let all_players: HashMap<_, _> = self
-                        .players
-                        .iter()
-                        .map(|(id, (player, _))| (*id, player.clone()))
-                        .collect();
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        let client_idx = senders.len();
-                        senders.push(sender);
-                        self.send_to_client(
-                            player_id,
-                            client_idx,
-                            ServerToClientMessage::PushState {
-                                players: all_players.clone(),
-                                game_state: GameState::DefineEvil,
-                            },
-                        );
-
-                        if let Some(questions) = player_questions.get(&player_id) {
-                            let payload = questions
-                                .iter()
-                                .map(|(question, answer)| (format!("{}", question), answer.clone()))
-                                .collect();
-                            self.send_to_client(
-                                player_id,
-                                client_idx,
-                                ServerToClientMessage::Questions { questions: payload },
-                            );
+                InternalMessage::AddSpectator {
+                    credentials,
+                    sender,
+                } => {
+                    if !self.admits(None, credentials.password) {
+                        Self::turn_away(sender, ServerToClientMessage::WrongPassword);
+                    } else if sender.unbounded_send(self.engine.public_state()).is_ok() {
+                        for message in &self.chat {
+                            let message = ServerToClientMessage::ChatMessage(message.clone());
+                            sender.unbounded_send(message).ok();
                         }
-                    } else {
-                        sender
-                            .unbounded_send(ServerToClientMessage::GameIsOngoing)
-                            .ok();
-                        sender.close_channel();
+                        self.spectators.push(sender);
                     }
                 }
-                Some(InternalMessage::RemoveClient { player_id }) => {
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        senders.drain_filter(|sender| sender.is_closed());
-                    }
+                InternalMessage::SetSeed { seed } => {
+                    log::info!("Game {} now uses dice seed {}", self.name, seed);
+                    self.dice = SeededDice::new(seed);
+                    self.record(&Entry::Seed { seed });
                 }
-                Some(InternalMessage::Message { player_id, message }) => match message {
-                    ClientToServerMessage::Answers { answers } => {
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            if !player.ready {
-                                if let Some(questions) = player_questions.get_mut(&player_id) {
-                                    let mut answer_iter = answers.into_iter();
-                                    for question in questions.iter_mut() {
-                                        if let Some(answer) = answer_iter.next() {
-                                            if !answer.is_empty() {
-                                                question.1 = Some(answer);
-                                            } else {
-                                                question.1 = None;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    ClientToServerMessage::ReadyForGame => {
-                        let mut ready = false;
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            if !player.ready {
-                                if let Some(questions) = player_questions.get(&player_id) {
-                                    ready = questions.iter().all(|(_, answer)| {
-                                        answer.as_ref().filter(|a| !a.is_empty()).is_some()
-                                    });
-                                    if ready {
-                                        player.ready = true;
-                                    }
-                                }
-                            }
-                        }
-                        if ready {
-                            self.push_state_all(GameState::DefineEvil);
-                        }
-                    }
-                    _ => {}
-                },
-            }
-        }
-        self.clues.extend(
-            player_questions
-                .into_values()
-                .flatten()
-                .filter_map(|(question, answer)| answer.map(|answer| (question, answer))),
-        );
-        self.clues.shuffle(&mut rng);
-        Ok(())
-    }
-    async fn create_character(&mut self) -> Result<(), Error> {
-        for (player, _) in self.players.values_mut() {
-            player.ready = false;
-        }
-        self.push_state_all(GameState::CharacterCreation);
-        while !self.players.values().all(|(player, _)| player.ready) {
-            match self.receiver.next().await {
-                None => {
-                    log::error!("Game failed");
-                    return Err(Error::NoPlayers);
+                InternalMessage::GetSeed { sender } => {
+                    sender.unbounded_send(self.dice.seed()).ok();
                 }
-                Some(InternalMessage::AddClient { player_id, sender }) => {
-                    let all_players: HashMap<_, _> = self
-                        .players
-                        .iter()
-                        .map(|(id, (player, _))| (*id, player.clone()))
-                        .collect();
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        let client_idx = senders.len();
-                        senders.push(sender);
-                        self.send_to_client(
-                            player_id,
-                            client_idx,
-                            ServerToClientMessage::PushState {
-                                players: all_players.clone(),
-                                game_state: GameState::CharacterCreation,
-                            },
-                        );
+                InternalMessage::GetInfo { sender } => {
+                    let info = if self.engine.is_unlisted() {
+                        None
                     } else {
-                        sender
-                            .unbounded_send(ServerToClientMessage::GameIsOngoing)
-                            .ok();
-                        sender.close_channel();
-                    }
+                        Some(self.engine.info(&self.name))
+                    };
+                    sender.unbounded_send(info).ok();
                 }
-                Some(InternalMessage::RemoveClient { player_id }) => {
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        senders.drain_filter(|sender| sender.is_closed());
+                InternalMessage::Reap {
+                    idle_timeout,
+                    recap_timeout,
+                } => {
+                    if self.reap(idle_timeout, recap_timeout) {
+                        log::info!("Game {} shut down", self.name);
+                        return;
                     }
                 }
-                Some(InternalMessage::Message { player_id, message }) => match message {
-                    ClientToServerMessage::SetCharacter { stats } => {
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            if !player.ready {
-                                player.stats = Some(stats);
-                            }
-                        }
-                        self.push_state_all(GameState::CharacterCreation);
+                InternalMessage::Tick => {
+                    if self.engine.is_counting_down() {
+                        self.handle(Event::Tick { seconds: 1 }, None);
                     }
-                    ClientToServerMessage::ReadyForGame => {
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            if let Some(stats) = &player.stats {
-                                let heroic = stats
-                                    .attributes
-                                    .get(&Attribute::Heroic)
-                                    .cloned()
-                                    .unwrap_or(0);
-                                let booksmart = stats
-                                    .attributes
-                                    .get(&Attribute::Booksmart)
-                                    .cloned()
-                                    .unwrap_or(0);
-                                let streetwise = stats
-                                    .attributes
-                                    .get(&Attribute::Streetwise)
-                                    .cloned()
-                                    .unwrap_or(0);
-                                if heroic > 0
-                                    && booksmart > 0
-                                    && streetwise > 0
-                                    && heroic + booksmart + streetwise == 5
-                                    && !stats.name.is_empty()
-                                    && !stats.artifact_name.is_empty()
-                                    && !stats.artifact_origin.is_empty()
-                                {
-                                    player.ready = true;
-                                    self.push_state_all(GameState::CharacterCreation);
-                                }
-                            }
-                        }
-                    }
-                    _ => {}
-                },
+                }
             }
         }
-        Ok(())
+        log::error!("Game failed");
     }
-    async fn introduce_characters(&mut self) -> Result<(), Error> {
-        for (player, _) in self.players.values_mut() {
-            player.ready = false;
-        }
-        self.push_state_all(GameState::CharacterIntroduction);
 
-        while !self.players.values().all(|(player, _)| player.ready) {
-            match self.receiver.next().await {
-                None => {
-                    log::error!("Game failed");
-                    return Err(Error::NoPlayers);
-                }
-                Some(InternalMessage::AddClient { player_id, sender }) => {
-                    let all_players: HashMap<_, _> = self
-                        .players
-                        .iter()
-                        .map(|(id, (player, _))| (*id, player.clone()))
-                        .collect();
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        let client_idx = senders.len();
-                        senders.push(sender);
-                        self.send_to_client(
-                            player_id,
-                            client_idx,
-                            ServerToClientMessage::PushState {
-                                players: all_players.clone(),
-                                game_state: GameState::CharacterIntroduction,
-                            },
-                        );
-                    } else {
-                        sender
-                            .unbounded_send(ServerToClientMessage::GameIsOngoing)
-                            .ok();
-                        sender.close_channel();
-                    }
-                }
-                Some(InternalMessage::RemoveClient { player_id }) => {
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        senders.drain_filter(|sender| sender.is_closed());
-                    }
-                }
-                Some(InternalMessage::Message { player_id, message }) => match message {
-                    ClientToServerMessage::ReadyForGame => {
-                        if let Some((player, _)) = self.players.get_mut(&player_id) {
-                            player.ready = true;
-                        }
-                        self.push_state_all(GameState::CharacterIntroduction);
-                    }
-                    _ => {}
-                },
+    /// Players that are already part of a private game don't need its password to come back.
+    fn admits(&self, player_id: Option<Uuid>, password: Option<String>) -> bool {
+        match self.engine.password() {
+            None => true,
+            Some(expected) => {
+                password.as_deref() == Some(expected)
+                    || player_id
+                        .is_some_and(|player_id| self.engine.players().contains_key(&player_id))
             }
         }
-        Ok(())
     }
-    async fn enter_temple(&mut self) -> Result<bool, Error> {
-        let mut rng = rand::thread_rng();
-        let mut gms: Vec<Uuid> = self.players.keys().cloned().collect();
-        gms.shuffle(&mut rng);
 
-        for (room, &gm) in gms.iter().enumerate() {
-            let mut successes = 0;
-            let mut failures = 0;
-            let mut clue = self.clues[room].1.clone();
-            self.send_to(gm, ServerToClientMessage::PushClue { clue: clue.clone() });
+    fn turn_away(sender: UnboundedSender<ServerToClientMessage>, message: ServerToClientMessage) {
+        sender.unbounded_send(message).ok();
+        sender.close_channel();
+    }
 
-            let mut current_challenge: Option<Challenge> = None;
-            let mut current_challenge_result: Option<Vec<u8>> = None;
-            let mut current_artifact_used: Option<ArtifactBoon> = None;
+    /// Passes a chat message on to everybody who is connected, including spectators.
+    fn chat(&mut self, player_id: Uuid, channel: ChatChannel, text: &str) {
+        let text = match chat_text(text) {
+            Some(text) if self.engine.players().contains_key(&player_id) => text,
+            _ => return,
+        };
+        if channel == ChatChannel::InCharacter && !self.engine.can_speak_in_character(&player_id) {
+            log::error!(
+                "Invalid message received: in-character chat from {}",
+                player_id
+            );
+            return;
+        }
+        let message = ChatMessage {
+            player_id,
+            channel,
+            text,
+        };
+        if self.chat.len() == CHAT_HISTORY {
+            self.chat.pop_front();
+        }
+        self.chat.push_back(message.clone());
+        let message = ServerToClientMessage::ChatMessage(message);
+        for sender in self.clients.values().flatten() {
+            sender.unbounded_send(message.clone()).ok();
+        }
+        self.spectators
+            .retain(|sender| sender.unbounded_send(message.clone()).is_ok());
+    }
 
-            let mut proceed = false;
+    /// Passes a whisper on to the recipient and back to the sender, nobody else gets to see it.
+    fn whisper(&mut self, from: Uuid, to: Recipient, text: &str) {
+        let text = match chat_text(text) {
+            Some(text) if self.engine.players().contains_key(&from) => text,
+            _ => return,
+        };
+        let (recipients, to_gm) = match to {
+            Recipient::Player(player_id) => (vec![player_id], false),
+            Recipient::Gm => (self.engine.gms(), true),
+        };
+        let players = self.engine.players();
+        let recipients: Vec<Uuid> = recipients
+            .into_iter()
+            .filter(|player_id| *player_id != from && players.contains_key(player_id))
+            .collect();
+        if recipients.is_empty() {
+            log::error!(
+                "Invalid message received: whisper from {} to {:?}",
+                from,
+                to
+            );
+            return;
+        }
+        for to in recipients {
+            let whisper = Whisper {
+                from,
+                to,
+                to_gm,
+                text: text.clone(),
+            };
+            let history = self.whispers.entry(pair(from, to)).or_default();
+            if history.len() == CHAT_HISTORY {
+                history.pop_front();
+            }
+            history.push_back(whisper.clone());
+            let message = ServerToClientMessage::Whisper(whisper);
+            for sender in [to, from]
+                .iter()
+                .filter_map(|player_id| self.clients.get(player_id))
+                .flatten()
+            {
+                sender.unbounded_send(message.clone()).ok();
+            }
+        }
+    }
 
-            while !proceed {
-                let waiting_for = if let Some(challenge) = &current_challenge {
-                    challenge.player_id
-                } else {
-                    gm
-                };
-                for (&player_id, (player, _)) in self.players.iter_mut() {
-                    player.ready = player_id != waiting_for;
-                }
+    /// Sends the chat so far to the client of the player that just connected, along with their whispers.
+    fn replay_chat(&self, player_id: Uuid) {
+        if !self.engine.players().contains_key(&player_id) {
+            return;
+        }
+        if let Some(sender) = self
+            .clients
+            .get(&player_id)
+            .and_then(|senders| senders.last())
+        {
+            for message in &self.chat {
+                let message = ServerToClientMessage::ChatMessage(message.clone());
+                sender.unbounded_send(message).ok();
+            }
+            let whispers = self
+                .whispers
+                .iter()
+                .filter(|((a, b), _)| *a == player_id || *b == player_id)
+                .flat_map(|(_, whispers)| whispers);
+            for whisper in whispers {
+                sender
+                    .unbounded_send(ServerToClientMessage::Whisper(whisper.clone()))
+                    .ok();
+            }
+        }
+    }
 
-                let (with_challenge, without_challenge): (Vec<Uuid>, Vec<Uuid>) =
-                    if let Some(challenge) = current_challenge.as_ref() {
-                        self.players.keys().partition(|id| {
-                            if **id == gm {
-                                true
-                            } else {
-                                **id == challenge.player_id
-                            }
-                        })
-                    } else {
-                        (Vec::new(), self.players.keys().cloned().collect())
+    /// Starts playing the given player on the server and connects it like any other client.
+    fn attach_bot(&mut self, bot_id: Uuid) {
+        let (sender, mut receiver) = unbounded();
+        let game = self.sender.clone();
+        spawn(async move {
+            let mut bot = Bot::new(bot_id);
+            let mut dice = SeededDice::from_entropy();
+            while let Some(message) = receiver.next().await {
+                for message in bot.react(&message, &mut dice) {
+                    let message = InternalMessage::Message {
+                        player_id: bot_id,
+                        message,
                     };
-
-                if !with_challenge.is_empty() {
-                    self.push_state_to(
-                        with_challenge,
-                        GameState::Room {
-                            room_idx: room,
-                            gm,
-                            successes,
-                            failures,
-                            challenge: current_challenge.clone(),
-                            known_clues: self.known_clues(room),
-                        },
-                    );
-                }
-                self.push_state_to(
-                    without_challenge,
-                    GameState::Room {
-                        room_idx: room,
-                        gm,
-                        successes,
-                        failures,
-                        challenge: None,
-                        known_clues: self.known_clues(room),
-                    },
-                );
-
-                let event = self.receiver.next().await;
-                log::info!("Received event {:?}", event);
-
-                match event {
-                    None => {
-                        log::error!("Game failed");
-                        return Err(Error::NoPlayers);
-                    }
-                    Some(InternalMessage::AddClient { player_id, sender }) => {
-                        if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                            let client_idx = senders.len();
-                            senders.push(sender);
-                            if player_id == gm {
-                                self.send_to_client(
-                                    player_id,
-                                    client_idx,
-                                    ServerToClientMessage::PushClue { clue: clue.clone() },
-                                );
-                            }
-                            if let Some(current_challenge) = &current_challenge {
-                                if current_challenge.player_id == player_id {
-                                    let (artifact_boon, artifact_used) = self
-                                        .players
-                                        .get(&player_id)
-                                        .map(|(player, _)| {
-                                            (
-                                                player
-                                                    .stats
-                                                    .as_ref()
-                                                    .map(|stats| stats.artifact_boon),
-                                                player.artifact_used,
-                                            )
-                                        })
-                                        .unwrap_or((None, true));
-                                    if let Some(challenge_result) = &current_challenge_result {
-                                        let can_use_artifact = artifact_boon
-                                            .map(|artifact_boon| {
-                                                !artifact_used
-                                                    && Self::check_can_use_artifact(
-                                                        &challenge_result,
-                                                        artifact_boon,
-                                                    )
-                                            })
-                                            .unwrap_or(false);
-                                        self.send_to_client(
-                                            player_id,
-                                            client_idx,
-                                            ServerToClientMessage::ChallengeResult(
-                                                ChallengeResult {
-                                                    possession: Self::possessed_dice(
-                                                        &challenge_result,
-                                                    ),
-                                                    success: Self::check_success(
-                                                        &challenge_result,
-                                                        current_artifact_used,
-                                                    ),
-                                                    can_use_artifact,
-                                                    rolls: challenge_result.clone(),
-                                                },
-                                            ),
-                                        );
-                                    }
-                                }
-                            }
-                        } else {
-                            sender
-                                .unbounded_send(ServerToClientMessage::GameIsOngoing)
-                                .ok();
-                            sender.close_channel();
-                        }
+                    if game.unbounded_send(message).is_err() {
+                        return;
                     }
-                    Some(InternalMessage::RemoveClient { player_id }) => {
-                        if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                            senders.drain_filter(|sender| sender.is_closed());
-                        }
-                    }
-                    Some(InternalMessage::Message { player_id, message }) => match message {
-                        ClientToServerMessage::RejectClue if player_id == gm => {
-                            if room > 0
-                                && self.clues.len() > self.players.len()
-                                && successes + failures == 0
-                            {
-                                // clue doesn't fit with existing lore, remove it
-                                self.clues.remove(room);
-                                clue = self.clues[room].1.clone();
-                                self.send_to(
-                                    gm,
-                                    ServerToClientMessage::PushClue { clue: clue.clone() },
-                                );
-                            } else {
-                                // either there's no existing lore yet, or we don't have any more clues left to discard
-                                self.send_to(
-                                    player_id,
-                                    ServerToClientMessage::ClueRejectionRejected,
-                                );
-                            }
-                        }
-                        ClientToServerMessage::OfferChallenge { challenge }
-                            if successes < SUCCESSES_NEEDED =>
-                        {
-                            if player_id == gm && challenge.player_id != gm {
-                                if let Some((player, _)) = self.players.get(&challenge.player_id) {
-                                    if player.condition != Condition::Dead
-                                        && player.mental_condition != MentalCondition::Possessed
-                                    {
-                                        current_challenge = Some(challenge.clone());
-                                    }
-                                }
-                            } else {
-                                log::error!(
-                                    "Invalid message received: OfferChallenge from {} for {}",
-                                    player_id,
-                                    challenge.player_id
-                                );
-                            }
-                        }
-                        ClientToServerMessage::ChallengeAccepted => {
-                            if let Some((player, _)) = self.players.get_mut(&player_id) {
-                                if let Some(challenge) = &current_challenge {
-                                    if challenge.player_id == player_id {
-                                        let dice_count = player
-                                            .stats
-                                            .as_ref()
-                                            .unwrap()
-                                            .attributes
-                                            .get(&challenge.attribute)
-                                            .unwrap()
-                                            + if challenge.speciality_applies { 1 } else { 0 }
-                                            + if challenge.reputation_applies { 1 } else { 0 };
-                                        let mut can_use_artifact = false;
-                                        let results = Self::roll_d6(dice_count as _);
-                                        let success = Self::check_success(&results, None);
-                                        let possession = Self::possessed_dice(&results);
-                                        if success && !possession {
-                                            successes += 1;
-                                            current_challenge = None;
-                                        } else if !player.artifact_used {
-                                            let artifact_boon =
-                                                player.stats.as_ref().unwrap().artifact_boon;
-                                            if possession && artifact_boon == ArtifactBoon::Reroll {
-                                                can_use_artifact = true;
-                                            } else if !success {
-                                                // check whether the artifact could make a difference
-                                                match artifact_boon {
-                                                    ArtifactBoon::SuccessOnFive
-                                                        if !results.contains(&5) =>
-                                                    {
-                                                        // can't use artifact
-                                                    }
-                                                    ArtifactBoon::SuccessOnDoubles => {
-                                                        let mut results = results.clone();
-                                                        results.sort_unstable();
-                                                        let (_, duplicates) =
-                                                            results.partition_dedup();
-                                                        can_use_artifact = !duplicates.is_empty();
-                                                    }
-                                                    _ => {
-                                                        can_use_artifact = true;
-                                                    }
-                                                }
-                                            }
-                                        } else if success && possession {
-                                            // Nothing the player can do about this result
-                                            successes += 1;
-                                            current_challenge = None;
-                                            player.mental_condition =
-                                                player.mental_condition.take_hit();
-                                        }
-                                        if !success
-                                            || (!player.artifact_used
-                                                && player.stats.as_ref().unwrap().artifact_boon
-                                                    == ArtifactBoon::Reroll
-                                                && possession)
-                                        {
-                                            // the player can either use the artifact (if possible) or choose to
-                                            // take a hit to avoid the failure
-                                            // if it's just about possession, only the reroll artifact can help
-                                            current_challenge_result = Some(results.clone());
-                                        }
-                                        self.send_to(
-                                            player_id,
-                                            ServerToClientMessage::ChallengeResult(
-                                                ChallengeResult {
-                                                    rolls: results,
-                                                    success,
-                                                    possession,
-                                                    can_use_artifact,
-                                                },
-                                            ),
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        ClientToServerMessage::ChallengeRejected => {
-                            if (player_id == gm
-                                || current_challenge
-                                    .as_ref()
-                                    .map(|challenge| challenge.player_id)
-                                    == Some(player_id))
-                                && current_challenge_result.is_none()
-                            {
-                                current_challenge = None;
-                                self.send_to(player_id, ServerToClientMessage::AbortedChallenge);
-                                if player_id != gm {
-                                    self.send_to(gm, ServerToClientMessage::AbortedChallenge);
-                                }
-                            }
-                        }
-                        ClientToServerMessage::AcceptFate => {
-                            if current_challenge
-                                .as_ref()
-                                .map(|challenge| challenge.player_id)
-                                == Some(player_id)
-                            {
-                                if let Some(current_challenge_result) =
-                                    current_challenge_result.take()
-                                {
-                                    if Self::check_success(
-                                        &current_challenge_result,
-                                        current_artifact_used.take(),
-                                    ) {
-                                        successes += 1;
-                                    } else {
-                                        failures += 1;
-                                    }
-                                    if Self::possessed_dice(&current_challenge_result) {
-                                        if let Some((player, _)) = self.players.get_mut(&player_id)
-                                        {
-                                            player.mental_condition =
-                                                player.mental_condition.take_hit();
-                                        }
-                                    }
-                                    current_challenge = None;
-                                } else {
-                                    log::error!("Accepted fate, but there was no current challenge result available.");
-                                }
-                            } else {
-                                log::error!(
-                                    "Accepted fate by someone who wasn't challenged at the moment."
-                                );
-                            }
-                        }
-                        ClientToServerMessage::TakeWound => {
-                            if let Some((player, _)) = self.players.get_mut(&player_id) {
-                                if let Some(challenge_result) = current_challenge_result.take() {
-                                    player.condition = player.condition.take_hit();
-                                    if Self::possessed_dice(&challenge_result) {
-                                        player.mental_condition =
-                                            player.mental_condition.take_hit();
-                                    }
-                                    successes += 1;
-                                    current_challenge = None;
-                                    current_artifact_used = None;
-                                }
-                            }
-                        }
-                        ClientToServerMessage::UseArtifact => {
-                            if current_challenge
-                                .as_ref()
-                                .map(|challenge| challenge.player_id == player_id)
-                                .unwrap_or(false)
-                            {
-                                if let Some((player, _)) = self.players.get_mut(&player_id) {
-                                    if let Some(challenge_result) = current_challenge_result.take()
-                                    {
-                                        player.artifact_used = true;
-                                        let results =
-                                            match player.stats.as_ref().unwrap().artifact_boon {
-                                                ArtifactBoon::Reroll => {
-                                                    Self::roll_d6(challenge_result.len())
-                                                }
-                                                ArtifactBoon::RollWithPlusTwo => challenge_result
-                                                    .into_iter()
-                                                    .chain(Self::roll_d6(2).into_iter())
-                                                    .collect(),
-                                                ArtifactBoon::SuccessOnFive
-                                                | ArtifactBoon::SuccessOnDoubles => {
-                                                    challenge_result
-                                                }
-                                            };
-                                        let success = Self::check_success(
-                                            &results,
-                                            Some(player.stats.as_ref().unwrap().artifact_boon),
-                                        );
-                                        let possession = Self::possessed_dice(&results);
-                                        if success {
-                                            successes += 1;
-                                            current_challenge = None;
-                                            if possession {
-                                                player.mental_condition =
-                                                    player.mental_condition.take_hit();
-                                            }
-                                        } else {
-                                            current_artifact_used =
-                                                Some(player.stats.as_ref().unwrap().artifact_boon);
-                                            current_challenge_result = Some(results.clone());
-                                        }
-                                        self.send_to(
-                                            player_id,
-                                            ServerToClientMessage::ChallengeResult(
-                                                ChallengeResult {
-                                                    possession,
-                                                    rolls: results,
-                                                    success,
-                                                    can_use_artifact: false,
-                                                },
-                                            ),
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        ClientToServerMessage::ReadyForGame if player_id == gm => {
-                            if successes >= SUCCESSES_NEEDED {
-                                proceed = true;
-                            }
-                        }
-                        _ => {}
-                    },
-                }
-                if failures >= FAILURES_NEEDED {
-                    return Ok(false);
-                }
-                let alive_players: Vec<Uuid> = self
-                    .players
-                    .iter()
-                    .filter_map(|(&player_id, (player, _))| {
-                        if player.condition != Condition::Dead
-                            && player.mental_condition != MentalCondition::Possessed
-                        {
-                            Some(player_id)
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-                if alive_players.is_empty() // everybody is dead
-                    || (alive_players.len() == 1 && gms[if proceed { room+1 } else { room }..].contains(&alive_players[0]))
-                // there's only a single player left and that player is supposed to GM the current or a future room
-                {
-                    return Ok(false);
                 }
             }
-        }
-
-        Ok(true)
+        });
+        self.handle(Event::Connect { player_id: bot_id }, Some((bot_id, sender)));
     }
-    async fn face_ancient_evil(&mut self) -> Result<bool, Error> {
-        let mut gms: HashSet<_> = self
-            .players
-            .iter()
-            .filter(|(_, (player, _))| {
-                player.condition == Condition::Dead
-                    || player.mental_condition == MentalCondition::Possessed
+
+    /// Tells the engine about clients of the player that went away.
+    fn remove_closed(&mut self, player_id: Uuid) {
+        let closed = self
+            .clients
+            .get_mut(&player_id)
+            .map(|senders| {
+                let before = senders.len();
+                senders.retain(|sender| !sender.is_closed());
+                before - senders.len()
             })
-            .map(|(&player_id, _)| player_id)
-            .collect();
-        if gms.is_empty() {
-            let mut rng = rand::thread_rng();
-            let player_ids: Vec<Uuid> = self.players.keys().cloned().collect();
-            gms.insert(player_ids[rng.gen_range(0, player_ids.len())]);
+            .unwrap_or(0);
+        for _ in 0..closed {
+            self.handle(disconnect(player_id), None);
         }
-        log::debug!("GMs are now {:?}", gms);
-        let target_successes = (self.players.len() + 1) / 2; // rounded up
-        let mut successes = 0;
-        let mut remaining_clues = self.known_clues(self.players.len()).clone();
-
-        let mut current_challenge: Option<(Challenge, usize)> = None;
-        let mut current_challenge_result: Option<Vec<u8>> = None;
-        let mut current_artifact_used: Option<ArtifactBoon> = None;
-
-        while successes < target_successes {
-            let waiting_for = if let Some((challenge, _)) = &current_challenge {
-                std::iter::once(challenge.player_id).collect()
-            } else {
-                gms.clone()
-            };
-            for (player_id, (player, _)) in self.players.iter_mut() {
-                player.ready = !waiting_for.contains(player_id);
-            }
-
-            let (with_challenge, without_challenge): (Vec<Uuid>, Vec<Uuid>) =
-                self.players.keys().partition(|id| {
-                    if gms.contains(*id) {
-                        true
-                    } else if let Some((challenge, _)) = &current_challenge {
-                        **id == challenge.player_id
-                    } else {
-                        false
-                    }
-                });
-
-            if !with_challenge.is_empty() {
-                self.push_state_to(
-                    with_challenge,
-                    GameState::FinalBattle {
-                        remaining_clues: remaining_clues.clone(),
-                        gms: gms.clone(),
-                        successes,
-                        target_successes,
-                        challenge: current_challenge
-                            .as_ref()
-                            .map(|(challenge, _)| challenge.clone()),
-                        chosen_clue: current_challenge
-                            .as_ref()
-                            .map(|(_, chosen_clue)| *chosen_clue),
-                    },
-                );
-            }
-            self.push_state_to(
-                without_challenge,
-                GameState::FinalBattle {
-                    remaining_clues: remaining_clues.clone(),
-                    gms: gms.clone(),
-                    successes,
-                    target_successes,
-                    challenge: None,
-                    chosen_clue: None,
-                },
-            );
+    }
 
-            let event = self.receiver.next().await;
-            log::info!("Received event {:?}", event);
+    /// Returns whether the game was abandoned or over long enough to shut it down.
+    fn reap(&mut self, idle_timeout: Duration, recap_timeout: Duration) -> bool {
+        if let Some(finished_at) = self.finished_at {
+            return finished_at.elapsed() >= recap_timeout;
+        }
+        let player_ids: Vec<Uuid> = self.clients.keys().cloned().collect();
+        for player_id in player_ids {
+            self.remove_closed(player_id);
+        }
+        self.spectators.retain(|sender| !sender.is_closed());
+        // bots don't keep a game alive on their own
+        let engine = &self.engine;
+        if self
+            .clients
+            .iter()
+            .any(|(player_id, senders)| !senders.is_empty() && !engine.is_bot(player_id))
+            || !self.spectators.is_empty()
+        {
+            self.idle_since = None;
+            return false;
+        }
+        let idle_since = *self.idle_since.get_or_insert_with(Instant::now);
+        if idle_since.elapsed() < idle_timeout {
+            return false;
+        }
+        log::info!(
+            "Game {} was abandoned {:?} ago",
+            self.name,
+            idle_since.elapsed()
+        );
+        self.remove_snapshot();
+        true
+    }
 
+    /// Feeds an event to the engine and carries out the effects.
+    ///
+    /// `connecting` is the client that caused a `Connect` event.
+    fn handle(
+        &mut self,
+        event: Event,
+        mut connecting: Option<(Uuid, UnboundedSender<ServerToClientMessage>)>,
+    ) {
+        let mut events = vec![event];
+        let mut checkpoint = false;
+        let mut finished = false;
+        let mut state_changed = false;
+        while let Some(event) = events.pop() {
+            let mut dice = RecordingDice::new(&mut self.dice);
+            let effects = self.engine.apply(event.clone(), &mut dice);
+            let rolls = dice.into_entries();
             match event {
-                None => {
-                    log::error!("Game failed");
-                    return Err(Error::NoPlayers);
+                // most ticks only count down, the seconds are added up instead of filling the journal
+                Event::Tick { seconds } if effects.is_empty() && rolls.is_empty() => {
+                    self.unjournaled_seconds += seconds;
+                    continue;
                 }
-                Some(InternalMessage::AddClient { player_id, sender }) => {
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        let client_idx = senders.len();
-                        senders.push(sender);
-                        if let Some((current_challenge, _)) = &current_challenge {
-                            if current_challenge.player_id == player_id {
-                                let (artifact_boon, artifact_used) = self
-                                    .players
-                                    .get(&player_id)
-                                    .map(|(player, _)| {
-                                        (
-                                            player.stats.as_ref().map(|stats| stats.artifact_boon),
-                                            player.artifact_used,
-                                        )
-                                    })
-                                    .unwrap_or((None, true));
-                                if let Some(challenge_result) = &current_challenge_result {
-                                    let can_use_artifact = artifact_boon
-                                        .map(|artifact_boon| {
-                                            !artifact_used
-                                                && Self::check_can_use_artifact(
-                                                    &challenge_result,
-                                                    artifact_boon,
-                                                )
-                                        })
-                                        .unwrap_or(false);
-                                    self.send_to_client(
-                                        player_id,
-                                        client_idx,
-                                        ServerToClientMessage::ChallengeResult(ChallengeResult {
-                                            possession: Self::possessed_dice(&challenge_result),
-                                            success: Self::check_success(
-                                                &challenge_result,
-                                                current_artifact_used,
-                                            ),
-                                            can_use_artifact,
-                                            rolls: challenge_result.clone(),
-                                        }),
-                                    );
-                                }
-                            }
+                Event::Message { player_id, message } => self.record(&Entry::Event {
+                    event: Event::Message {
+                        player_id,
+                        message: message.redacted(),
+                    },
+                }),
+                event => self.record(&Entry::Event { event }),
+            }
+            for entry in rolls {
+                self.record(&entry);
+            }
+            for effect in effects {
+                match effect {
+                    Effect::Send { player_id, message } => {
+                        if let ServerToClientMessage::PushState { .. } = message {
+                            state_changed = true;
                         }
-                    } else {
-                        sender
-                            .unbounded_send(ServerToClientMessage::GameIsOngoing)
-                            .ok();
-                        sender.close_channel();
-                    }
-                }
-                Some(InternalMessage::RemoveClient { player_id }) => {
-                    if let Some((_, senders)) = self.players.get_mut(&player_id) {
-                        senders.drain_filter(|sender| sender.is_closed());
-                    }
-                }
-                Some(InternalMessage::Message { player_id, message }) => match message {
-                    ClientToServerMessage::OfferChallengeFinal {
-                        challenge,
-                        clue_idx,
-                    } => {
-                        if gms.contains(&player_id)
-                            && !gms.contains(&challenge.player_id)
-                            && clue_idx < remaining_clues.len()
-                        {
-                            if let Some((player, _)) = self.players.get(&challenge.player_id) {
-                                if player.condition != Condition::Dead
-                                    && player.mental_condition != MentalCondition::Possessed
-                                {
-                                    current_challenge = Some((challenge, clue_idx));
-                                }
+                        if let Some((connecting_id, sender)) = &connecting {
+                            if *connecting_id == player_id {
+                                sender.unbounded_send(message.clone()).ok();
                             }
                         }
-                    }
-                    ClientToServerMessage::ChallengeAccepted => {
-                        if let Some((player, _)) = self.players.get(&player_id) {
-                            if let Some((challenge, clue_idx)) = &current_challenge {
-                                if challenge.player_id == player_id {
-                                    let dice_count =
-                                        if let Some((player, _)) = self.players.get(&player_id) {
-                                            player
-                                                .stats
-                                                .as_ref()
-                                                .unwrap()
-                                                .attributes
-                                                .get(&challenge.attribute)
-                                                .unwrap()
-                                                + if challenge.speciality_applies { 1 } else { 0 }
-                                                + if challenge.reputation_applies { 1 } else { 0 }
-                                        } else {
-                                            0
-                                        };
-                                    let mut can_use_artifact = false;
-                                    let results = Self::roll_d6(dice_count as _);
-                                    let success = Self::check_success(&results, None);
-                                    let possession = Self::possessed_dice(&results);
-                                    if success && !possession {
-                                        successes += 1;
-                                        remaining_clues.remove(*clue_idx);
-                                        current_challenge = None;
-                                    } else if !player.artifact_used {
-                                        let artifact_boon =
-                                            player.stats.as_ref().unwrap().artifact_boon;
-                                        if possession && artifact_boon == ArtifactBoon::Reroll {
-                                            can_use_artifact = true;
-                                        } else if !success {
-                                            // check whether the artifact could make a difference
-                                            match artifact_boon {
-                                                ArtifactBoon::SuccessOnFive
-                                                    if !results.contains(&5) =>
-                                                {
-                                                    // can't use artifact
-                                                }
-                                                ArtifactBoon::SuccessOnDoubles => {
-                                                    let mut results = results.clone();
-                                                    results.sort_unstable();
-                                                    let (_, duplicates) = results.partition_dedup();
-                                                    can_use_artifact = !duplicates.is_empty();
-                                                }
-                                                _ => {
-                                                    can_use_artifact = true;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    if !success
-                                        || (!player.artifact_used
-                                            && player.stats.as_ref().unwrap().artifact_boon
-                                                == ArtifactBoon::Reroll
-                                            && possession)
-                                    {
-                                        // the player can either use the artifact (if possible) or choose to
-                                        // take a hit to avoid the failure
-                                        // if it's just about possession, only the reroll artifact can help
-                                        current_challenge_result = Some(results.clone());
-                                    }
-                                    self.send_to(
-                                        player_id,
-                                        ServerToClientMessage::ChallengeResult(ChallengeResult {
-                                            rolls: results,
-                                            success,
-                                            possession,
-                                            can_use_artifact,
-                                        }),
-                                    );
-                                }
-                            }
+                        if let Some(senders) = self.clients.get_mut(&player_id) {
+                            let before = senders.len();
+                            senders.retain(|sender| sender.unbounded_send(message.clone()).is_ok());
+                            let failed = before - senders.len();
+                            events.extend((0..failed).map(|_| disconnect(player_id)));
                         }
                     }
-                    ClientToServerMessage::ChallengeRejected => {
-                        if (gms.contains(&player_id)
-                            || current_challenge
-                                .as_ref()
-                                .map(|(challenge, _)| challenge.player_id)
-                                == Some(player_id))
-                            && current_challenge_result.is_none()
-                        {
-                            current_challenge = None;
-                            self.send_to(player_id, ServerToClientMessage::AbortedChallenge);
-                            for gm in &gms {
-                                if *gm != player_id {
-                                    self.send_to(*gm, ServerToClientMessage::AbortedChallenge);
-                                }
-                            }
+                    Effect::Reply(message) => {
+                        if let Some((_, sender)) = &connecting {
+                            sender.unbounded_send(message).ok();
                         }
                     }
-                    ClientToServerMessage::UseArtifact => {
-                        if current_challenge
-                            .as_ref()
-                            .map(|(challenge, _)| challenge.player_id == player_id)
-                            .unwrap_or(false)
-                        {
-                            if let Some((_, clue_idx)) = &current_challenge {
-                                if let Some(challenge_result) = current_challenge_result.take() {
-                                    if let Some((player, _)) = self.players.get_mut(&player_id) {
-                                        player.artifact_used = true;
-                                        let results =
-                                            match player.stats.as_ref().unwrap().artifact_boon {
-                                                ArtifactBoon::Reroll => {
-                                                    Self::roll_d6(challenge_result.len())
-                                                }
-                                                ArtifactBoon::RollWithPlusTwo => challenge_result
-                                                    .into_iter()
-                                                    .chain(Self::roll_d6(2).into_iter())
-                                                    .collect(),
-                                                ArtifactBoon::SuccessOnFive
-                                                | ArtifactBoon::SuccessOnDoubles => {
-                                                    challenge_result
-                                                }
-                                            };
-                                        let success = Self::check_success(
-                                            &results,
-                                            Some(player.stats.as_ref().unwrap().artifact_boon),
-                                        );
-                                        let possession = Self::possessed_dice(&results);
-                                        if success {
-                                            successes += 1;
-                                            remaining_clues.remove(*clue_idx);
-                                            current_challenge_result = None;
-                                            current_challenge = None;
-                                            if possession {
-                                                player.mental_condition =
-                                                    player.mental_condition.take_hit();
-                                            }
-                                        } else {
-                                            current_artifact_used =
-                                                Some(player.stats.as_ref().unwrap().artifact_boon);
-                                        }
-                                        self.send_to(
-                                            player_id,
-                                            ServerToClientMessage::ChallengeResult(
-                                                ChallengeResult {
-                                                    possession,
-                                                    rolls: results,
-                                                    success,
-                                                    can_use_artifact: false,
-                                                },
-                                            ),
-                                        );
-                                    }
-                                }
-                            }
+                    Effect::Close => {
+                        if let Some((_, sender)) = connecting.take() {
+                            sender.close_channel();
                         }
                     }
-                    ClientToServerMessage::TakeWound => {
-                        if let Some((challenge, clue_idx)) = &current_challenge {
-                            if challenge.player_id == player_id {
-                                if let Some((player, _)) = self.players.get_mut(&player_id) {
-                                    successes += 1;
-                                    remaining_clues.remove(*clue_idx);
-
-                                    player.condition = player.condition.take_hit();
-                                    current_challenge_result = None;
-                                    current_challenge = None;
-                                }
-                            }
-                        }
+                    Effect::DropClients { player_id } => {
+                        self.clients.remove(&player_id);
                     }
-                    ClientToServerMessage::AcceptFate => {
-                        if let Some(current_challenge_result) = current_challenge_result.take() {
-                            if let Some((_, clue_idx)) = current_challenge.take() {
-                                if Self::check_success(
-                                    &current_challenge_result,
-                                    current_artifact_used.take(),
-                                ) {
-                                    successes += 1;
-                                }
-                                remaining_clues.remove(clue_idx);
-
-                                if Self::possessed_dice(&current_challenge_result) {
-                                    if let Some((player, _)) = self.players.get_mut(&player_id) {
-                                        player.mental_condition =
-                                            player.mental_condition.take_hit();
-                                    }
-                                }
+                    Effect::Checkpoint => checkpoint = true,
+                    Effect::Finished { victory } => {
+                        log::info!("Game {} is over, victory = {}", self.name, victory);
+                        finished = true;
+                        if let Some(recap) = self.engine.recap() {
+                            let message = ServerToClientMessage::Recap(recap);
+                            for spectator in &self.spectators {
+                                spectator.unbounded_send(message.clone()).ok();
                             }
                         }
                     }
-                    _ => {}
-                },
-            }
-            if successes < target_successes
-                && remaining_clues.len() < (target_successes - successes)
-            {
-                // unwinnable situation
-                return Ok(false);
-            }
-
-            let alive_players = self.players.iter().filter(|(player_id, (player, _))| {
-                !gms.contains(*player_id)
-                    && player.condition != Condition::Dead
-                    && player.mental_condition != MentalCondition::Possessed
-            });
-
-            if alive_players.count() == 0 {
-                return Ok(false);
+                }
             }
         }
-
-        Ok(true)
-    }
-    async fn end(&mut self, victory: bool) -> Result<(), Error> {
-        self.push_state_all(if victory {
-            GameState::Victory
-        } else {
-            GameState::Failure
-        });
-        Ok(())
+        if let Some((player_id, sender)) = connecting {
+            self.clients.entry(player_id).or_default().push(sender);
+        }
+        if state_changed && !self.spectators.is_empty() {
+            let message = self.engine.public_state();
+            self.spectators
+                .retain(|sender| sender.unbounded_send(message.clone()).is_ok());
+        }
+        if finished {
+            // nothing left to resume
+            self.remove_snapshot();
+            self.finished_at = Some(Instant::now());
+        } else if checkpoint {
+            self.save();
+        }
     }
 }
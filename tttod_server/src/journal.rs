@@ -0,0 +1,174 @@
+use crate::{snapshot::file_name, Error};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+};
+use tttod_data::{Dice, Event, GameEngine};
+
+/// A single line of a journal file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum Entry {
+    /// The game was created or restored from a snapshot, `engine` is the state it started out with.
+    Start {
+        name: String,
+        seed: u64,
+        engine: GameEngine,
+    },
+    /// The dice were replaced through the debug API.
+    Seed {
+        seed: u64,
+    },
+    Event {
+        event: Event,
+    },
+    /// Dice rolled while handling the preceding event.
+    Roll {
+        dice: Vec<u8>,
+    },
+    /// A number picked while handling the preceding event, for example for a shuffle.
+    Pick {
+        upper: usize,
+        value: usize,
+    },
+}
+
+/// Append-only log of everything that happened to a game, one JSON object per line.
+#[derive(Debug)]
+pub struct Journal(File);
+
+impl Journal {
+    pub fn open(path: &Path, name: &str) -> Result<Self, Error> {
+        fs::create_dir_all(path)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.join(format!("{}.jsonl", file_name(name))))?;
+        Ok(Self(file))
+    }
+
+    pub fn write(&mut self, entry: &Entry) -> Result<(), Error> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        // a single write per line, so that a crash never leaves half an entry in the middle of the file
+        self.0.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Writes down everything the wrapped dice produce.
+pub struct RecordingDice<'a, D: Dice> {
+    dice: &'a mut D,
+    entries: Vec<Entry>,
+}
+
+impl<'a, D: Dice> RecordingDice<'a, D> {
+    pub fn new(dice: &'a mut D) -> Self {
+        Self {
+            dice,
+            entries: Vec::new(),
+        }
+    }
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+}
+
+impl<'a, D: Dice> Dice for RecordingDice<'a, D> {
+    fn roll_d6(&mut self, count: usize) -> Vec<u8> {
+        let dice = self.dice.roll_d6(count);
+        self.entries.push(Entry::Roll { dice: dice.clone() });
+        dice
+    }
+    fn pick(&mut self, upper: usize) -> usize {
+        let value = self.dice.pick(upper);
+        self.entries.push(Entry::Pick { upper, value });
+        value
+    }
+}
+
+/// Hands out the results written down in a journal instead of rolling.
+#[derive(Default)]
+struct ReplayDice {
+    entries: VecDeque<Entry>,
+    diverged: bool,
+}
+
+impl Dice for ReplayDice {
+    fn roll_d6(&mut self, count: usize) -> Vec<u8> {
+        match self.entries.pop_front() {
+            Some(Entry::Roll { dice }) if dice.len() == count => dice,
+            _ => {
+                self.diverged = true;
+                vec![1; count]
+            }
+        }
+    }
+    fn pick(&mut self, upper: usize) -> usize {
+        match self.entries.pop_front() {
+            Some(Entry::Pick {
+                upper: recorded_upper,
+                value,
+            }) if recorded_upper == upper => value,
+            _ => {
+                self.diverged = true;
+                0
+            }
+        }
+    }
+}
+
+/// Feeds a journal through the rules and prints the state of the game after every event.
+///
+/// Returns the game as it was at the end of the journal.
+pub fn replay(path: &Path) -> Result<GameEngine, Error> {
+    let mut engine = GameEngine::default();
+    let mut dice = ReplayDice::default();
+    let mut pending: Option<(usize, Event)> = None;
+    let lines = BufReader::new(File::open(path)?).lines();
+    // one extra round without an entry to process the last event
+    for (line_idx, line) in lines.map(Some).chain(std::iter::once(None)).enumerate() {
+        let entry = match line {
+            Some(line) => Some(serde_json::from_str::<Entry>(&line?)?),
+            None => None,
+        };
+        if let Some(Entry::Roll { .. }) | Some(Entry::Pick { .. }) = entry {
+            dice.entries.extend(entry);
+            continue;
+        }
+        if let Some((event_line, event)) = pending.take() {
+            println!("{}: {}", event_line, serde_json::to_string(&event)?);
+            engine.apply(event, &mut dice);
+            if dice.diverged || !dice.entries.is_empty() {
+                println!("    dice don't match the journal, the rules must have changed since it was written");
+            }
+            dice = ReplayDice::default();
+            println!("    {}", serde_json::to_string(&engine.game_state())?);
+        }
+        match entry {
+            Some(Entry::Start {
+                name,
+                seed,
+                engine: start,
+            }) => {
+                println!(
+                    "{}: game {} started with dice seed {}",
+                    line_idx + 1,
+                    name,
+                    seed
+                );
+                engine = start;
+            }
+            Some(Entry::Seed { seed }) => {
+                println!("{}: dice seed changed to {}", line_idx + 1, seed);
+            }
+            Some(Entry::Event { event }) => pending = Some((line_idx + 1, event)),
+            _ => (),
+        }
+    }
+    Ok(engine)
+}
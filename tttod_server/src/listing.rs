@@ -0,0 +1,45 @@
+use crate::{game::InternalMessage, Error, Game};
+use actix_web::{get, web, HttpResponse};
+use futures::{channel::mpsc::unbounded, future::join_all, StreamExt};
+use tttod_data::GameInfo;
+
+/// Asks a game for its summary, `None` if it is unlisted or already shut down.
+async fn game_info(game: Game) -> Option<GameInfo> {
+    let (sender, mut receiver) = unbounded();
+    game.as_ref()
+        .unbounded_send(InternalMessage::GetInfo { sender })
+        .ok()?;
+    receiver.next().await.flatten()
+}
+
+#[get("/api/games")]
+pub async fn list_games(games: web::Data<crate::Games>) -> Result<HttpResponse, Error> {
+    let games: Vec<Game> = games
+        .lock()
+        .map_err(|_| Error::MutexPoisoned)?
+        .values()
+        .cloned()
+        .collect();
+    let mut infos: Vec<GameInfo> = join_all(games.into_iter().map(game_info))
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    infos.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(HttpResponse::Ok().json(infos))
+}
+
+#[get("/api/games/{game_name}")]
+pub async fn get_game(
+    web::Path(game_name): web::Path<String>,
+    games: web::Data<crate::Games>,
+) -> Result<HttpResponse, Error> {
+    let game = games
+        .lock()
+        .map_err(|_| Error::MutexPoisoned)?
+        .get(&game_name)
+        .cloned()
+        .ok_or(Error::NotFound)?;
+    let info = game_info(game).await.ok_or(Error::NotFound)?;
+    Ok(HttpResponse::Ok().json(info))
+}
@@ -1,12 +1,12 @@
-#![feature(drain_filter, map_into_keys_values, slice_partition_dedup)]
 #![allow(clippy::single_match, clippy::naive_bytecount)]
 use actix_files::{Files, NamedFile};
 use actix_service::fn_service;
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse},
-    middleware, App, HttpServer,
+    middleware, web, App, HttpServer,
 };
 use futures_util::future::{err, ok};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
 use std::{
     collections::HashMap,
     net::SocketAddr,
@@ -17,13 +17,22 @@ use std::{
 use structopt::StructOpt;
 
 mod config;
+mod debug;
+mod decks;
 use config::Config;
+use decks::DeckLibrary;
 mod error;
 pub use error::Error;
-mod clues;
-pub use clues::Question;
 mod game;
 pub use game::Game;
+mod journal;
+mod listing;
+mod reaper;
+mod session;
+mod snapshot;
+use snapshot::Snapshot;
+#[cfg(test)]
+mod tests;
 mod websocket;
 
 type Games = Arc<Mutex<HashMap<String, Game>>>;
@@ -40,12 +49,66 @@ struct Opt {
     #[structopt(short, long)]
     /// URL base for redirects
     base: Option<String>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Feeds a game journal through the rules and prints the state of the game after every step
+    Replay {
+        /// Path to the journal file
+        journal: PathBuf,
+    },
+}
+
+/// Everything the API consists of, the app data (`Config`, `Games` and `DeckLibrary`) has to be added separately.
+fn services(cfg: &mut web::ServiceConfig) {
+    cfg.service(session::join)
+        .service(websocket::spectate)
+        .service(websocket::index)
+        .service(listing::list_games)
+        .service(listing::get_game)
+        .service(decks::list_decks)
+        .service(debug::get_seed)
+        .service(debug::set_seed);
+}
+
+/// The games a previous server instance left behind in `storage.path`.
+fn restore_games(config: &Config, decks: &DeckLibrary) -> HashMap<String, Game> {
+    let mut games = HashMap::new();
+    if let Some(storage) = &config.storage {
+        match Snapshot::load_all(&storage.path) {
+            Err(err) => log::error!(
+                "Failed restoring games from {}: {}",
+                storage.path.display(),
+                err
+            ),
+            Ok(snapshots) => {
+                for snapshot in snapshots {
+                    log::info!("Restoring game {}", snapshot.name);
+                    games.insert(
+                        snapshot.name.clone(),
+                        Game::restore(snapshot, config, decks),
+                    );
+                }
+            }
+        }
+    }
+    games
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     let opt = Opt::from_args();
-    let (config, logging_config) = match Config::parse(&opt.config) {
+    if let Some(Command::Replay { journal }) = &opt.command {
+        if let Err(err) = journal::replay(journal) {
+            eprintln!("Failed replaying `{}`: {}", journal.display(), err);
+            std::process::exit(-1);
+        }
+        return Ok(());
+    }
+    let (mut config, logging_config) = match Config::parse(&opt.config) {
         Err(err) => {
             eprintln!("Error in config file `{}`: {}", opt.config.display(), err);
             std::process::exit(-1);
@@ -56,19 +119,55 @@ async fn main() -> std::io::Result<()> {
         eprintln!("log4rs: {}", e);
         std::process::exit(-1);
     }
+    if !config.rules.is_valid() {
+        eprintln!(
+            "Error in config file `{}`: the rules can't be played with",
+            opt.config.display()
+        );
+        std::process::exit(-1);
+    }
+    if let Some(secret) = &config.session.secret {
+        if session::PLACEHOLDER_SECRETS.contains(&secret.trim().to_lowercase().as_str()) {
+            eprintln!(
+                "Error in config file `{}`: `session.secret` is still an example value, set a random string of your own or remove it",
+                opt.config.display()
+            );
+            std::process::exit(-1);
+        }
+    } else {
+        log::warn!("No session secret configured, tokens won't survive a restart");
+        config.session.secret = Some(thread_rng().sample_iter(&Alphanumeric).take(32).collect());
+    }
     let address = opt
         .address
         .or(config.server.address)
         .unwrap_or_else(|| SocketAddr::from_str("127.0.0.1:8081").unwrap());
 
-    let games: Games = Arc::new(Mutex::new(HashMap::new()));
+    let decks = match &config.decks {
+        None => DeckLibrary::default(),
+        Some(decks) => DeckLibrary::load_all(&decks.path).unwrap_or_else(|err| {
+            log::error!(
+                "Failed loading decks from {}: {}",
+                decks.path.display(),
+                err
+            );
+            DeckLibrary::default()
+        }),
+    };
+
+    let games: Games = Arc::new(Mutex::new(restore_games(&config, &decks)));
+    actix_web::rt::spawn(reaper::run(games.clone(), config.reaper.clone()));
 
     HttpServer::new(move || {
         let mut app = App::new()
             .data(config.clone())
             .data(games.clone())
-            .wrap(middleware::Logger::default())
-            .service(websocket::index);
+            .data(decks.clone())
+            // without the query string, which contains passwords and session tokens
+            .wrap(middleware::Logger::new(
+                r#"%a "%U" %s %b "%{Referer}i" "%{User-Agent}i" %T"#,
+            ))
+            .configure(services);
         if let Some(path) = config.server.static_path.as_deref() {
             let mut index = path.to_owned();
             index.push(config.server.index.as_deref().unwrap_or("index.html"));
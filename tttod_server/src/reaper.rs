@@ -0,0 +1,40 @@
+use crate::{config::Reaper, game::InternalMessage, Games};
+use actix_web::rt::time::interval;
+use std::time::Duration;
+
+/// Removes games from `games` once they have shut down, and asks the rest to check whether they were abandoned or over.
+pub async fn run(games: Games, config: Reaper) {
+    let idle_timeout = config.idle_timeout();
+    let recap_timeout = config.recap_timeout();
+    // checking more often than every minute would only make the logs noisier
+    let mut ticks = interval(
+        idle_timeout
+            .min(recap_timeout)
+            .min(Duration::from_secs(60))
+            .max(Duration::from_secs(1)),
+    );
+    loop {
+        ticks.tick().await;
+        let mut games = match games.lock() {
+            Ok(games) => games,
+            Err(_) => {
+                log::error!("Games mutex poisoned, stopping the reaper");
+                return;
+            }
+        };
+        games.retain(|name, game| {
+            let alive = !game.as_ref().is_closed()
+                && game
+                    .as_ref()
+                    .unbounded_send(InternalMessage::Reap {
+                        idle_timeout,
+                        recap_timeout,
+                    })
+                    .is_ok();
+            if !alive {
+                log::info!("Removing game {}", name);
+            }
+            alive
+        });
+    }
+}
@@ -0,0 +1,58 @@
+use crate::Config;
+use actix_web::{post, web, HttpResponse};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tttod_data::SessionToken;
+use uuid::Uuid;
+
+/// Example values for the secret, which must not make it into a real installation.
+pub const PLACEHOLDER_SECRETS: &[&str] = &["change me", "changeme", "secret"];
+
+/// Hands out and checks the tokens that prove a client owns a player id.
+#[derive(Clone)]
+pub struct Sessions {
+    secret: Vec<u8>,
+}
+
+impl Sessions {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            secret: config
+                .session
+                .secret
+                .clone()
+                .unwrap_or_default()
+                .into_bytes(),
+        }
+    }
+
+    fn mac(&self, player_id: &Uuid) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(player_id.as_bytes());
+        mac
+    }
+
+    pub fn token(&self, player_id: &Uuid) -> String {
+        base64::encode_config(
+            self.mac(player_id).finalize().into_bytes(),
+            base64::URL_SAFE_NO_PAD,
+        )
+    }
+
+    pub fn verify(&self, player_id: &Uuid, token: &str) -> bool {
+        // the comparison takes the same time no matter how much of the token was right
+        base64::decode_config(token, base64::URL_SAFE_NO_PAD)
+            .is_ok_and(|token| self.mac(player_id).verify_slice(&token).is_ok())
+    }
+}
+
+/// Creates a new player id, along with the token needed to connect to games as that player.
+#[post("/api/join")]
+pub async fn join(config: web::Data<Config>) -> HttpResponse {
+    let player_id = Uuid::new_v4();
+    HttpResponse::Ok().json(SessionToken {
+        player_id,
+        token: Sessions::new(&config).token(&player_id),
+    })
+}
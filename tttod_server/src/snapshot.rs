@@ -0,0 +1,60 @@
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tttod_data::GameEngine;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub engine: GameEngine,
+}
+
+/// Game names are chosen by the players, so they have to be escaped before using them as a file name.
+pub fn file_name(name: &str) -> String {
+    url::form_urlencoded::byte_serialize(name.as_bytes()).collect()
+}
+
+impl Snapshot {
+    fn path(storage: &Path, name: &str) -> PathBuf {
+        storage.join(format!("{}.json", file_name(name)))
+    }
+
+    pub fn save(&self, storage: &Path) -> Result<(), Error> {
+        fs::create_dir_all(storage)?;
+        let path = Self::path(storage, &self.name);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(self)?)?;
+        // rename is atomic, so a crash while writing never leaves a truncated snapshot behind
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    pub fn remove(storage: &Path, name: &str) -> Result<(), Error> {
+        match fs::remove_file(Self::path(storage, name)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn load_all(storage: &Path) -> Result<Vec<Self>, Error> {
+        fs::create_dir_all(storage)?;
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(storage)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            match fs::read(&path)
+                .map_err(Error::from)
+                .and_then(|data| serde_json::from_slice(&data).map_err(Error::from))
+            {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(err) => log::error!("Failed loading snapshot {}: {}", path.display(), err),
+            }
+        }
+        Ok(snapshots)
+    }
+}
@@ -0,0 +1,35 @@
+use super::{answer, config, start, start_game};
+use tttod_data::{ClientToServerMessage, GameState};
+
+#[actix_rt::test]
+async fn server_rolls_characters_until_the_player_is_ready() {
+    let server = start(config(&[("roll", 1)]));
+    let mut clients = start_game(&server, "roll").await;
+    for client in &mut clients {
+        answer(client).await;
+    }
+    let client = &mut clients[0];
+    client
+        .expect_state("character creation", |state| {
+            *state == GameState::CharacterCreation
+        })
+        .await;
+
+    client.send(ClientToServerMessage::RollCharacter).await;
+    client.settle().await;
+    let (players, _) = client.state().unwrap();
+    let player = &players[&client.player_id];
+    let rolled = player.stats.clone().unwrap();
+    assert!(rolled.is_valid());
+    // the player still gets to change it
+    assert!(!player.ready);
+
+    client.send(ClientToServerMessage::ReadyForGame).await;
+    client.send(ClientToServerMessage::RollCharacter).await;
+    client.settle().await;
+    let (players, _) = client.state().unwrap();
+    let player = &players[&client.player_id];
+    assert!(player.ready);
+    assert_eq!(player.stats.as_ref().unwrap().name, rolled.name);
+    assert_eq!(player.stats.as_ref().unwrap().attributes, rolled.attributes);
+}
@@ -0,0 +1,142 @@
+use super::{config, enter_temple, gm_and_player, settle_all, start, TestClient};
+use tttod_data::{
+    ChatChannel, ChatMessage, ClientToServerMessage, Recipient, ServerToClientMessage, Whisper,
+};
+
+fn chat_messages(received: &[ServerToClientMessage]) -> Vec<ChatMessage> {
+    received
+        .iter()
+        .filter_map(|message| match message {
+            ServerToClientMessage::ChatMessage(message) => Some(message.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn whispers(received: &[ServerToClientMessage]) -> Vec<Whisper> {
+    received
+        .iter()
+        .filter_map(|message| match message {
+            ServerToClientMessage::Whisper(whisper) => Some(whisper.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[actix_rt::test]
+async fn chat_is_relayed_and_replayed() {
+    let server = start(config(&[]));
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        clients.push(TestClient::join(&server, "chat").await);
+    }
+    settle_all(&mut clients).await;
+
+    clients[0]
+        .send(ClientToServerMessage::Chat {
+            channel: ChatChannel::OutOfCharacter,
+            text: "  Can everybody hear me?  ".to_owned(),
+        })
+        .await;
+    settle_all(&mut clients).await;
+    clients[1]
+        .send(ClientToServerMessage::Chat {
+            channel: ChatChannel::InCharacter,
+            text: " ".to_owned(),
+        })
+        .await;
+    clients[1]
+        .send(ClientToServerMessage::Chat {
+            channel: ChatChannel::InCharacter,
+            text: "A".repeat(1000),
+        })
+        .await;
+    settle_all(&mut clients).await;
+
+    let expected = vec![
+        ChatMessage {
+            player_id: clients[0].player_id,
+            channel: ChatChannel::OutOfCharacter,
+            text: "Can everybody hear me?".to_owned(),
+        },
+        ChatMessage {
+            player_id: clients[1].player_id,
+            channel: ChatChannel::InCharacter,
+            text: "A".repeat(500),
+        },
+    ];
+    for client in &clients {
+        assert_eq!(chat_messages(&client.received), expected);
+    }
+
+    let mut latecomer = TestClient::join(&server, "chat").await;
+    latecomer.settle().await;
+    assert_eq!(chat_messages(&latecomer.received), expected);
+}
+
+#[actix_rt::test]
+async fn whispers_only_reach_the_pair() {
+    let server = start(config(&[]));
+    let mut clients = Vec::new();
+    for _ in 0..3 {
+        clients.push(TestClient::join(&server, "whispers").await);
+    }
+    settle_all(&mut clients).await;
+    let recipient = clients[1].player_id;
+
+    clients[0]
+        .send(ClientToServerMessage::Whisper {
+            to: Recipient::Player(recipient),
+            text: "Don't trust the professor".to_owned(),
+        })
+        .await;
+    // nobody is GM in the lobby
+    clients[0]
+        .send(ClientToServerMessage::Whisper {
+            to: Recipient::Gm,
+            text: "Anybody there?".to_owned(),
+        })
+        .await;
+    settle_all(&mut clients).await;
+
+    let expected = vec![Whisper {
+        from: clients[0].player_id,
+        to: recipient,
+        to_gm: false,
+        text: "Don't trust the professor".to_owned(),
+    }];
+    assert_eq!(whispers(&clients[0].received), expected);
+    assert_eq!(whispers(&clients[1].received), expected);
+    assert!(whispers(&clients[2].received).is_empty());
+
+    let mut reconnected =
+        TestClient::connect(&server, "whispers", clients[1].player_id, &clients[1].token).await;
+    reconnected.settle().await;
+    assert_eq!(whispers(&reconnected.received), expected);
+}
+
+#[actix_rt::test]
+async fn whispers_find_the_gm() {
+    let server = start(config(&[]));
+    let mut clients = enter_temple(&server, "gm-whispers").await;
+    let (gm, player) = gm_and_player(&clients);
+
+    clients[player]
+        .send(ClientToServerMessage::Whisper {
+            to: Recipient::Gm,
+            text: "I have a bad feeling about this".to_owned(),
+        })
+        .await;
+    settle_all(&mut clients).await;
+
+    let expected = vec![Whisper {
+        from: clients[player].player_id,
+        to: clients[gm].player_id,
+        to_gm: true,
+        text: "I have a bad feeling about this".to_owned(),
+    }];
+    assert_eq!(whispers(&clients[gm].received), expected);
+    assert_eq!(whispers(&clients[player].received), expected);
+    let bystander = 3 - gm - player;
+    assert!(whispers(&clients[bystander].received).is_empty());
+}
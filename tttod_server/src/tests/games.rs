@@ -0,0 +1,410 @@
+use super::{config, enter_temple, enter_temple_with, lose_game, settle_all, start, TestClient};
+use std::collections::HashSet;
+use tttod_data::{
+    ArtifactBoon, Attribute, Challenge, ChallengeResult, ClientToServerMessage, Condition,
+    GameState, MentalCondition, ServerToClientMessage, PROTOCOL_VERSION,
+};
+use uuid::Uuid;
+
+/// Games are tried with these seeds until everything the rules can do has happened at least once.
+const SEEDS: std::ops::Range<u64> = 1..16;
+/// Upper bound of the decisions in a single game, so that a stuck game fails instead of hanging
+const MAX_STEPS: usize = 500;
+
+/// Everything that should have happened in at least one of the games.
+const COVERAGE: &[&str] = &[
+    "clue_kept",
+    "clue_replaced",
+    "possession",
+    "artifact",
+    "take_wound",
+    "accept_fate",
+    "final_battle",
+    "game_over",
+];
+
+fn possessed(rolls: &[u8]) -> bool {
+    rolls.iter().filter(|&&die| die == 1).count() > 1
+        || rolls.iter().filter(|&&die| die == 2).count() > 1
+}
+
+/// A challenge with as many dice as possible, to make possession likely.
+fn heroic_challenge(player_id: Uuid) -> Challenge {
+    Challenge {
+        player_id,
+        attribute: Attribute::Heroic,
+        speciality_applies: true,
+        reputation_applies: true,
+    }
+}
+
+/// What the driver of a game waits to see after a decision.
+enum Expectation {
+    Condition(Uuid, Condition),
+    MentalCondition(Uuid, MentalCondition),
+}
+
+/// Plays a game with three clients, checking the messages along the way.
+///
+/// Returns what happened, see [`COVERAGE`].
+async fn play_game(server: &actix_web::test::TestServer, game_name: &str) -> HashSet<&'static str> {
+    let mut coverage = HashSet::new();
+    let mut clients = enter_temple_with(
+        server,
+        game_name,
+        &[
+            ArtifactBoon::Reroll,
+            ArtifactBoon::RollWithPlusTwo,
+            ArtifactBoon::SuccessOnFive,
+        ],
+    )
+    .await;
+
+    let player_ids: Vec<Uuid> = clients.iter().map(|client| client.player_id).collect();
+    let idx_of = |player_id: &Uuid| player_ids.iter().position(|id| id == player_id).unwrap();
+
+    let mut rooms_with_rejected_clue = HashSet::new();
+    // results the server waits for a decision about, by client
+    let mut pending: Vec<Option<ChallengeResult>> = vec![None; clients.len()];
+    let mut accepted = vec![false; clients.len()];
+    let mut after_artifact = vec![false; clients.len()];
+    let mut expectation = None;
+
+    for _ in 0..MAX_STEPS {
+        let received = settle_all(&mut clients).await;
+        for (idx, messages) in received.into_iter().enumerate() {
+            for message in messages {
+                if let ServerToClientMessage::ChallengeResult(result) = message {
+                    assert_eq!(result.possession, possessed(&result.rolls));
+                    if after_artifact[idx] {
+                        assert!(!result.can_use_artifact);
+                    } else {
+                        // Heroic 3, speciality and reputation
+                        assert_eq!(result.rolls.len(), 5);
+                        assert_eq!(result.success, result.rolls.contains(&6));
+                    }
+                    if result.possession {
+                        coverage.insert("possession");
+                    }
+                    after_artifact[idx] = false;
+                    accepted[idx] = false;
+                    pending[idx] =
+                        Some(result).filter(|result| !result.success || result.can_use_artifact);
+                }
+            }
+        }
+
+        let (players, state) = clients[0].state().unwrap();
+        let players = players.clone();
+        match expectation.take() {
+            Some(Expectation::Condition(player_id, condition)) => {
+                assert_eq!(players[&player_id].condition, condition)
+            }
+            Some(Expectation::MentalCondition(player_id, mental_condition)) => {
+                assert_eq!(players[&player_id].mental_condition, mental_condition)
+            }
+            None => {}
+        }
+
+        // the challenge is only known to the ones involved, the rest is public
+        let (gms, challenge_state) = match state.clone() {
+            GameState::Victory | GameState::Failure => {
+                coverage.insert("game_over");
+                let end = state.clone();
+                for client in clients.iter() {
+                    assert_eq!(client.state().unwrap().1, &end);
+                }
+                return coverage;
+            }
+            GameState::Room {
+                gm,
+                room_idx,
+                successes,
+                failures,
+                ..
+            } => {
+                let gm_idx = idx_of(&gm);
+                if rooms_with_rejected_clue.insert(room_idx) {
+                    clients[gm_idx]
+                        .send(ClientToServerMessage::RejectClue)
+                        .await;
+                    let answer = clients[gm_idx]
+                        .expect("an answer to rejecting the clue", |message| {
+                            matches!(
+                                message,
+                                ServerToClientMessage::PushClue { .. }
+                                    | ServerToClientMessage::ClueRejectionRejected
+                            )
+                        })
+                        .await;
+                    if let ServerToClientMessage::PushClue { .. } = answer {
+                        // only later clues can contradict what the players already know
+                        assert!(room_idx > 0);
+                        coverage.insert("clue_replaced");
+                    } else {
+                        coverage.insert("clue_kept");
+                    }
+                    continue;
+                }
+                if successes >= 3 {
+                    clients[gm_idx]
+                        .send(ClientToServerMessage::ReadyForGame)
+                        .await;
+                    continue;
+                }
+                assert!(failures < 3);
+                (vec![gm], clients[gm_idx].state().unwrap().1.clone())
+            }
+            GameState::FinalBattle { gms, .. } => {
+                coverage.insert("final_battle");
+                let mut gms: Vec<Uuid> = gms.into_iter().collect();
+                gms.sort();
+                let state = clients[idx_of(&gms[0])].state().unwrap().1.clone();
+                (gms, state)
+            }
+            state => panic!("Unexpected state {:?} during the temple", state),
+        };
+        let challenge = match &challenge_state {
+            GameState::Room { challenge, .. } | GameState::FinalBattle { challenge, .. } => {
+                challenge.clone()
+            }
+            _ => unreachable!(),
+        };
+
+        match challenge {
+            None => {
+                let target = player_ids
+                    .iter()
+                    .find(|player_id| {
+                        let player = &players[player_id];
+                        !gms.contains(player_id)
+                            && player.condition != Condition::Dead
+                            && player.mental_condition != MentalCondition::Possessed
+                    })
+                    .cloned()
+                    .expect("the server should have ended the game without anybody left");
+                let message = match &challenge_state {
+                    GameState::Room { .. } => ClientToServerMessage::OfferChallenge {
+                        challenge: heroic_challenge(target),
+                    },
+                    _ => ClientToServerMessage::OfferChallengeFinal {
+                        challenge: heroic_challenge(target),
+                        clue_idx: 0,
+                    },
+                };
+                clients[idx_of(&gms[0])].send(message).await;
+            }
+            Some(challenge) => {
+                let idx = idx_of(&challenge.player_id);
+                let player = &players[&challenge.player_id];
+                match pending[idx].take() {
+                    None if !accepted[idx] => {
+                        accepted[idx] = true;
+                        clients[idx]
+                            .send(ClientToServerMessage::ChallengeAccepted)
+                            .await;
+                    }
+                    None => {}
+                    Some(result) if result.can_use_artifact => {
+                        coverage.insert("artifact");
+                        after_artifact[idx] = true;
+                        clients[idx].send(ClientToServerMessage::UseArtifact).await;
+                    }
+                    Some(result) => {
+                        if player.condition == Condition::Hale {
+                            coverage.insert("take_wound");
+                            expectation = Some(Expectation::Condition(
+                                challenge.player_id,
+                                Condition::Wounded,
+                            ));
+                            clients[idx].send(ClientToServerMessage::TakeWound).await;
+                        } else {
+                            coverage.insert("accept_fate");
+                            if result.possession {
+                                expectation = Some(Expectation::MentalCondition(
+                                    challenge.player_id,
+                                    player.mental_condition.take_hit(),
+                                ));
+                            }
+                            clients[idx].send(ClientToServerMessage::AcceptFate).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    panic!("Game {} didn't end after {} steps", game_name, MAX_STEPS);
+}
+
+#[actix_rt::test]
+async fn games_go_through_every_phase() {
+    let names: Vec<String> = SEEDS.map(|seed| format!("full-{}", seed)).collect();
+    let seeds: Vec<(&str, u64)> = names.iter().map(String::as_str).zip(SEEDS).collect();
+    let server = start(config(&seeds));
+    let mut coverage = HashSet::new();
+    for name in &names {
+        coverage.extend(play_game(&server, name).await);
+        if COVERAGE.iter().all(|what| coverage.contains(what)) {
+            return;
+        }
+    }
+    let missing: Vec<_> = COVERAGE
+        .iter()
+        .filter(|what| !coverage.contains(*what))
+        .collect();
+    panic!("None of the games covered {:?}", missing);
+}
+
+#[actix_rt::test]
+async fn kick_vote_removes_player() {
+    let server = start(config(&[]));
+    let mut clients = Vec::new();
+    for _ in 0..4 {
+        clients.push(TestClient::join(&server, "kick").await);
+    }
+    let kicked = clients[3].player_id;
+
+    clients[0]
+        .send(ClientToServerMessage::VoteKickPlayer { player_id: kicked })
+        .await;
+    settle_all(&mut clients).await;
+    match clients[1].state().unwrap().1 {
+        GameState::PlayerSelection {
+            player_kick_votes, ..
+        } => assert_eq!(player_kick_votes[&kicked].len(), 1),
+        state => panic!("Unexpected state {:?} in the lobby", state),
+    }
+
+    for client in clients[1..3].iter_mut() {
+        client
+            .send(ClientToServerMessage::VoteKickPlayer { player_id: kicked })
+            .await;
+    }
+    clients[3].expect_closed().await;
+    settle_all(&mut clients[..3]).await;
+    for client in &clients[..3] {
+        let (players, _) = client.state().unwrap();
+        assert_eq!(players.len(), 3);
+        assert!(!players.contains_key(&kicked));
+    }
+}
+
+#[actix_rt::test]
+async fn everybody_sees_who_is_online() {
+    let server = start(config(&[]));
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        clients.push(TestClient::join(&server, "presence").await);
+    }
+    settle_all(&mut clients).await;
+    let (players, _) = clients[0].state().unwrap();
+    assert!(players.values().all(|player| player.online));
+
+    let leaving = clients.pop().unwrap();
+    let (leaving_id, token) = (leaving.player_id, leaving.token.clone());
+    drop(leaving);
+    clients[0]
+        .expect("offline player", |message| match message {
+            ServerToClientMessage::PushState { players, .. } => {
+                !players[&leaving_id].online && players[&leaving_id].last_seen.is_some()
+            }
+            _ => false,
+        })
+        .await;
+
+    clients.push(TestClient::connect(&server, "presence", leaving_id, &token).await);
+    clients[0]
+        .expect("returning player", |message| match message {
+            ServerToClientMessage::PushState { players, .. } => players[&leaving_id].online,
+            _ => false,
+        })
+        .await;
+}
+
+#[actix_rt::test]
+async fn outdated_client_is_told_to_reload() {
+    let server = start(config(&[]));
+    let mut client = TestClient::join(&server, "handshake").await;
+    client
+        .expect("Welcome", |message| {
+            matches!(message, ServerToClientMessage::Welcome)
+        })
+        .await;
+
+    let mut outdated =
+        TestClient::open(&server, "handshake", client.player_id, &client.token).await;
+    outdated
+        .send(ClientToServerMessage::Hello {
+            protocol_version: PROTOCOL_VERSION + 1,
+            client_build: "from the future".to_owned(),
+        })
+        .await;
+    outdated
+        .expect("IncompatibleClient", |message| {
+            matches!(
+                message,
+                ServerToClientMessage::IncompatibleClient { protocol_version }
+                    if *protocol_version == PROTOCOL_VERSION
+            )
+        })
+        .await;
+    outdated.expect_closed().await;
+}
+
+#[actix_rt::test]
+async fn invalid_session_is_turned_away() {
+    let server = start(config(&[]));
+    let mut client = TestClient::connect(&server, "sessions", Uuid::new_v4(), "forged").await;
+    client
+        .expect("InvalidSession", |message| {
+            matches!(message, ServerToClientMessage::InvalidSession)
+        })
+        .await;
+    client.expect_closed().await;
+}
+
+#[actix_rt::test]
+async fn finished_game_waits_for_its_players_to_come_back() {
+    let mut config = config(&[("recap", 1)]);
+    config.rules.failures_needed = 1;
+    let server = start(config);
+    let mut clients = enter_temple(&server, "recap").await;
+    lose_game(&mut clients).await;
+
+    let leaving = clients.pop().unwrap();
+    let (player_id, token) = (leaving.player_id, leaving.token.clone());
+    drop(leaving);
+    let mut client = TestClient::connect(&server, "recap", player_id, &token).await;
+    client
+        .expect(
+            "the recap",
+            |message| matches!(message, ServerToClientMessage::Recap(recap) if !recap.victory),
+        )
+        .await;
+    client
+        .expect_state("the end of the game", |state| *state == GameState::Failure)
+        .await;
+}
+
+#[actix_rt::test]
+async fn players_still_see_the_password_after_coming_back() {
+    let server = start(config(&[]));
+    let mut client = TestClient::join(&server, "private").await;
+    client
+        .send(ClientToServerMessage::SetPassword {
+            password: Some("sesame".to_owned()),
+        })
+        .await;
+    client.settle().await;
+    let (player_id, token) = (client.player_id, client.token.clone());
+    drop(client);
+
+    let mut client = TestClient::connect(&server, "private", player_id, &token).await;
+    client.settle().await;
+    match client.state().unwrap().1 {
+        GameState::PlayerSelection { password, .. } => {
+            assert_eq!(password.as_deref(), Some("sesame"))
+        }
+        state => panic!("expected the lobby, got {:?}", state),
+    }
+}
@@ -0,0 +1,51 @@
+use super::{config, start, start_game};
+use tttod_data::{ClientToServerMessage, Deck, ServerToClientMessage};
+
+#[actix_rt::test]
+async fn inspiration_comes_from_the_table_of_the_question() {
+    let server = start(config(&[("inspiration", 1)]));
+    let mut clients = start_game(&server, "inspiration").await;
+    let client = &mut clients[0];
+    let questions = client
+        .received
+        .iter()
+        .find_map(|message| match message {
+            ServerToClientMessage::Questions { questions } => Some(questions.clone()),
+            _ => None,
+        })
+        .unwrap();
+    let deck = Deck::default();
+
+    for (idx, (question, _)) in questions.iter().enumerate() {
+        client
+            .send(ClientToServerMessage::RollInspiration { question_idx: idx })
+            .await;
+        match client
+            .expect("inspiration", |message| {
+                matches!(message, ServerToClientMessage::Inspiration { .. })
+            })
+            .await
+        {
+            ServerToClientMessage::Inspiration {
+                question_idx,
+                prompt,
+            } => {
+                assert_eq!(question_idx, idx);
+                assert!(deck.inspiration[question].contains(&prompt));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // nothing to be inspired about
+    client
+        .send(ClientToServerMessage::RollInspiration {
+            question_idx: questions.len(),
+        })
+        .await;
+    assert!(!client
+        .settle()
+        .await
+        .iter()
+        .any(|message| matches!(message, ServerToClientMessage::Inspiration { .. })));
+}
@@ -0,0 +1,25 @@
+use super::{config, enter_temple, lose_game, settle_all, start, TempDir};
+use crate::{config::Journal, journal::replay};
+use tttod_data::GameState;
+
+#[actix_rt::test]
+async fn replaying_the_journal_ends_where_the_game_did() {
+    let journal = TempDir::new();
+    let mut config = config(&[]);
+    config.journal = Some(Journal {
+        path: journal.0.clone(),
+    });
+    let server = start(config);
+    let mut clients = enter_temple(&server, "replayed").await;
+    lose_game(&mut clients).await;
+    settle_all(&mut clients).await;
+    let (players, state) = clients[0].state().unwrap();
+    assert_eq!(state, &GameState::Failure);
+
+    let engine = replay(&journal.0.join("replayed.jsonl")).unwrap();
+    assert_eq!(&engine.game_state(), state);
+    assert_eq!(
+        serde_json::to_value(engine.players()).unwrap(),
+        serde_json::to_value(players).unwrap()
+    );
+}
@@ -0,0 +1,45 @@
+use super::{config, start, start_game, TestClient};
+use tttod_data::{ClientToServerMessage, GameInfo, RuleSettings};
+
+#[actix_rt::test]
+async fn list_shows_which_games_can_be_joined() {
+    let config = config(&[]);
+    let rules = config.rules;
+    let server = start(config);
+    let mut open = TestClient::join(&server, "open").await;
+    let mut hidden = TestClient::join(&server, "hidden").await;
+    hidden
+        .send(ClientToServerMessage::SetUnlisted { unlisted: true })
+        .await;
+    let mut crowded = TestClient::join(&server, "crowded").await;
+    crowded
+        .send(ClientToServerMessage::SetRules {
+            rules: RuleSettings {
+                min_players: 1,
+                max_players: 1,
+                ..rules
+            },
+        })
+        .await;
+    let _ongoing = start_game(&server, "ongoing").await;
+    for client in [&mut open, &mut hidden, &mut crowded] {
+        client.settle().await;
+    }
+
+    let games: Vec<GameInfo> = server
+        .get("/api/games")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let joinable: Vec<(&str, bool)> = games
+        .iter()
+        .map(|game| (game.name.as_str(), game.joinable))
+        .collect();
+    assert_eq!(
+        joinable,
+        vec![("crowded", false), ("ongoing", false), ("open", true)]
+    );
+}
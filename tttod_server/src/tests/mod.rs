@@ -0,0 +1,425 @@
+//! Harness that runs the app on a random port and plays games against it over websockets.
+use crate::{journal::Entry, restore_games, services, Config, DeckLibrary, Games};
+use actix_codec::Framed;
+use actix_web::{test, App};
+use awc::{
+    ws::{Codec, Frame, Message},
+    BoxedSocket,
+};
+use futures::{future::join_all, SinkExt, StreamExt};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tttod_data::{
+    ArtifactBoon, Attribute, Challenge, ClientToServerMessage, GameState, Player, PlayerStats,
+    ServerToClientMessage, SessionToken, PROTOCOL_VERSION,
+};
+use uuid::Uuid;
+
+mod characters;
+mod chat;
+mod games;
+mod inspiration;
+mod journal;
+mod listing;
+mod odds;
+mod passwords;
+mod recap;
+mod snapshots;
+mod spectators;
+mod takeovers;
+mod timers;
+
+/// A client is considered up to date once the server didn't send anything for this long
+const QUIET: Duration = Duration::from_millis(50);
+/// Longest a client waits for a message it expects
+const PATIENCE: Duration = Duration::from_secs(5);
+
+/// A config without logging, storage or journal, using the given dice seeds by game name.
+fn config(seeds: &[(&str, u64)]) -> Config {
+    let mut config: Config =
+        serde_yaml::from_str("logging: {}\nserver: {}\nsession:\n  secret: test\n").unwrap();
+    config.dice.seeds = seeds
+        .iter()
+        .map(|(name, seed)| (name.to_string(), *seed))
+        .collect();
+    config
+}
+
+/// A directory of its own for the files a test has the server write, removed once the test is done.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("tttod-test-{}", Uuid::new_v4())))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+/// Everything journaled to `dir`, which only holds the journal of a single game.
+fn journal_entries(dir: &Path) -> Vec<Entry> {
+    let file = fs::read_dir(dir).unwrap().next().unwrap().unwrap();
+    fs::read_to_string(file.path())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn start(config: Config) -> test::TestServer {
+    let decks = DeckLibrary::default();
+    // like the real server, picking up where a previous one left off
+    let games: Games = Arc::new(Mutex::new(restore_games(&config, &decks)));
+    test::start(move || {
+        App::new()
+            .data(config.clone())
+            .data(games.clone())
+            .data(decks.clone())
+            .configure(services)
+    })
+}
+
+/// A websocket connection to a game, which remembers everything the server sent.
+struct TestClient {
+    player_id: Uuid,
+    token: String,
+    framed: Framed<BoxedSocket, Codec>,
+    received: Vec<ServerToClientMessage>,
+    closed: bool,
+}
+
+impl TestClient {
+    /// Gets a new player id from the server and connects with it.
+    async fn join(server: &test::TestServer, game_name: &str) -> Self {
+        let session = Self::session(server).await;
+        Self::connect(server, game_name, session.player_id, &session.token).await
+    }
+
+    /// Like [`TestClient::join`], for a game that has a password.
+    async fn join_with_password(
+        server: &test::TestServer,
+        game_name: &str,
+        password: &str,
+    ) -> Self {
+        let session = Self::session(server).await;
+        let path = format!(
+            "/api/{}/{}/ws?token={}&password={}",
+            game_name, session.player_id, session.token, password
+        );
+        let mut client = Self::open_path(server, &path, session.player_id, &session.token).await;
+        client.hello().await;
+        client
+    }
+
+    /// Watches a game without taking part, the player id of a spectator is nil.
+    async fn spectate(server: &test::TestServer, game_name: &str) -> Self {
+        let path = format!("/api/{}/spectate/ws", game_name);
+        let mut client = Self::open_path(server, &path, Uuid::nil(), "").await;
+        client.hello().await;
+        client
+    }
+
+    async fn connect(
+        server: &test::TestServer,
+        game_name: &str,
+        player_id: Uuid,
+        token: &str,
+    ) -> Self {
+        let mut client = Self::open(server, game_name, player_id, token).await;
+        client.hello().await;
+        client
+    }
+
+    async fn session(server: &test::TestServer) -> SessionToken {
+        server
+            .post("/api/join")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap()
+    }
+
+    /// Opens the websocket without the handshake.
+    async fn open(
+        server: &test::TestServer,
+        game_name: &str,
+        player_id: Uuid,
+        token: &str,
+    ) -> Self {
+        let path = format!("/api/{}/{}/ws?token={}", game_name, player_id, token);
+        Self::open_path(server, &path, player_id, token).await
+    }
+
+    async fn open_path(
+        server: &test::TestServer,
+        path: &str,
+        player_id: Uuid,
+        token: &str,
+    ) -> Self {
+        let (_, framed) = awc::Client::new()
+            .ws(server.url(path))
+            .connect()
+            .await
+            .unwrap();
+        Self {
+            player_id,
+            token: token.to_owned(),
+            framed,
+            received: Vec::new(),
+            closed: false,
+        }
+    }
+
+    async fn hello(&mut self) {
+        self.send(ClientToServerMessage::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            client_build: "test".to_owned(),
+        })
+        .await;
+    }
+
+    async fn send(&mut self, message: ClientToServerMessage) {
+        let json = message.into_json().unwrap();
+        self.framed.send(Message::Text(json)).await.unwrap();
+    }
+
+    /// `None` if nothing came in for `wait`, or the server closed the connection.
+    async fn next(&mut self, wait: Duration) -> Option<ServerToClientMessage> {
+        while !self.closed {
+            match actix_rt::time::timeout(wait, self.framed.next()).await {
+                Err(_) => return None,
+                Ok(Some(Ok(Frame::Text(text)))) => {
+                    let message: ServerToClientMessage = serde_json::from_slice(&text)
+                        .unwrap_or_else(|err| panic!("Unknown message {:?}: {}", text, err));
+                    self.received.push(message.clone());
+                    return Some(message);
+                }
+                // like a browser, so that the heartbeat doesn't drop idle clients
+                Ok(Some(Ok(Frame::Ping(ping)))) => {
+                    self.framed.send(Message::Pong(ping)).await.ok();
+                }
+                Ok(Some(Ok(Frame::Pong(_)))) => {}
+                Ok(_) => self.closed = true,
+            }
+        }
+        None
+    }
+
+    /// Reads everything the server has to say at the moment.
+    async fn settle(&mut self) -> Vec<ServerToClientMessage> {
+        let mut messages = Vec::new();
+        while let Some(message) = self.next(QUIET).await {
+            messages.push(message);
+        }
+        messages
+    }
+
+    /// Skips messages until one matches, panics if none comes.
+    async fn expect(
+        &mut self,
+        what: &str,
+        matches: impl Fn(&ServerToClientMessage) -> bool,
+    ) -> ServerToClientMessage {
+        loop {
+            match self.next(PATIENCE).await {
+                Some(message) if matches(&message) => return message,
+                Some(_) => {}
+                None => panic!("{} never received {}", self.player_id, what),
+            }
+        }
+    }
+
+    async fn expect_state(&mut self, what: &str, matches: impl Fn(&GameState) -> bool) {
+        self.expect(what, |message| match message {
+            ServerToClientMessage::PushState { game_state, .. } => matches(game_state),
+            _ => false,
+        })
+        .await;
+    }
+
+    async fn expect_closed(&mut self) {
+        while self.next(PATIENCE).await.is_some() {}
+        assert!(self.closed, "{} wasn't disconnected", self.player_id);
+    }
+
+    /// The last state the server pushed.
+    fn state(&self) -> Option<(&HashMap<Uuid, Player>, &GameState)> {
+        self.received
+            .iter()
+            .rev()
+            .find_map(|message| match message {
+                ServerToClientMessage::PushState {
+                    players,
+                    game_state,
+                    ..
+                } => Some((players, game_state)),
+                _ => None,
+            })
+    }
+}
+
+/// Lets all clients catch up, returns what each of them received.
+async fn settle_all(clients: &mut [TestClient]) -> Vec<Vec<ServerToClientMessage>> {
+    join_all(clients.iter_mut().map(|client| client.settle())).await
+}
+
+/// Three players who made it through the lobby, still on their questions.
+async fn start_game(server: &test::TestServer, game_name: &str) -> Vec<TestClient> {
+    let mut clients = Vec::new();
+    for _ in 0..3 {
+        clients.push(TestClient::join(server, game_name).await);
+    }
+    for (idx, client) in clients.iter_mut().enumerate() {
+        client
+            .send(ClientToServerMessage::SetPlayerName {
+                name: format!("Player {}", idx),
+            })
+            .await;
+        client.send(ClientToServerMessage::ReadyForGame).await;
+    }
+    for client in &mut clients {
+        let questions = match client
+            .expect("questions", |message| {
+                matches!(message, ServerToClientMessage::Questions { .. })
+            })
+            .await
+        {
+            ServerToClientMessage::Questions { questions } => questions,
+            _ => unreachable!(),
+        };
+        assert_eq!(questions.len(), 2);
+        assert!(questions.iter().all(|(_, answer)| answer.is_none()));
+    }
+    clients
+}
+
+async fn answer(client: &mut TestClient) {
+    client
+        .send(ClientToServerMessage::Answers {
+            answers: vec!["Gold".to_owned(), "Silver".to_owned()],
+        })
+        .await;
+    client.send(ClientToServerMessage::ReadyForGame).await;
+}
+
+fn character(name: &str, artifact_boon: ArtifactBoon) -> PlayerStats {
+    PlayerStats {
+        name: name.to_owned(),
+        attributes: [
+            (Attribute::Heroic, 3),
+            (Attribute::Booksmart, 1),
+            (Attribute::Streetwise, 1),
+        ]
+        .iter()
+        .cloned()
+        .collect(),
+        artifact_name: "Whip".to_owned(),
+        artifact_origin: "Found it".to_owned(),
+        artifact_boon,
+        ..Default::default()
+    }
+}
+
+/// Three players in the first room of the temple.
+async fn enter_temple(server: &test::TestServer, game_name: &str) -> Vec<TestClient> {
+    enter_temple_with(server, game_name, &[ArtifactBoon::SuccessOnFive; 3]).await
+}
+
+/// Three players in the first room of the temple, whose characters have the given artifacts.
+async fn enter_temple_with(
+    server: &test::TestServer,
+    game_name: &str,
+    boons: &[ArtifactBoon; 3],
+) -> Vec<TestClient> {
+    let mut clients = start_game(server, game_name).await;
+    for client in &mut clients {
+        answer(client).await;
+    }
+    for (client, &artifact_boon) in clients.iter_mut().zip(boons) {
+        client
+            .expect_state("character creation", |state| {
+                *state == GameState::CharacterCreation
+            })
+            .await;
+        client
+            .send(ClientToServerMessage::SetCharacter {
+                stats: character("Indy", artifact_boon),
+            })
+            .await;
+        client.send(ClientToServerMessage::ReadyForGame).await;
+    }
+    for client in &mut clients {
+        client
+            .expect_state("character introduction", |state| {
+                *state == GameState::CharacterIntroduction
+            })
+            .await;
+        client.send(ClientToServerMessage::ReadyForGame).await;
+    }
+    for client in &mut clients {
+        client
+            .expect_state("the first room", |state| {
+                matches!(state, GameState::Room { room_idx: 0, .. })
+            })
+            .await;
+    }
+    clients
+}
+
+/// The GM of the current room and somebody else.
+fn gm_and_player(clients: &[TestClient]) -> (usize, usize) {
+    let gm = match clients[0].state().unwrap().1 {
+        GameState::Room { gm, .. } => *gm,
+        state => panic!("Unexpected state {:?} in the temple", state),
+    };
+    let gm = clients
+        .iter()
+        .position(|client| client.player_id == gm)
+        .unwrap();
+    (gm, (gm + 1) % clients.len())
+}
+
+/// Challenges one of the players on a single die until the room fails, with rules that make that end the game.
+async fn lose_game(clients: &mut [TestClient]) {
+    let (gm, player) = gm_and_player(clients);
+    let challenge = Challenge {
+        player_id: clients[player].player_id,
+        attribute: Attribute::Streetwise,
+        speciality_applies: false,
+        reputation_applies: false,
+    };
+    while !matches!(clients[0].state().unwrap().1, GameState::Failure) {
+        clients[gm]
+            .send(ClientToServerMessage::OfferChallenge {
+                challenge: challenge.clone(),
+            })
+            .await;
+        clients[player]
+            .send(ClientToServerMessage::ChallengeAccepted)
+            .await;
+        let result = clients[player]
+            .expect("the result of the challenge", |message| {
+                matches!(message, ServerToClientMessage::ChallengeResult(_))
+            })
+            .await;
+        if let ServerToClientMessage::ChallengeResult(result) = result {
+            if !result.success {
+                clients[player]
+                    .send(ClientToServerMessage::AcceptFate)
+                    .await;
+            }
+        }
+        settle_all(clients).await;
+    }
+}
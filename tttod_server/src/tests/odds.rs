@@ -0,0 +1,50 @@
+use super::{config, enter_temple, gm_and_player, start, TestClient};
+use tttod_data::{
+    Attribute, Challenge, ChallengeOdds, ClientToServerMessage, ServerToClientMessage,
+};
+
+async fn expect_odds(client: &mut TestClient) -> ChallengeOdds {
+    match client
+        .expect("the odds", |message| {
+            matches!(message, ServerToClientMessage::ChallengeOdds(_))
+        })
+        .await
+    {
+        ServerToClientMessage::ChallengeOdds(odds) => odds,
+        _ => unreachable!(),
+    }
+}
+
+#[actix_rt::test]
+async fn challenged_player_sees_the_odds() {
+    let server = start(config(&[]));
+    let mut clients = enter_temple(&server, "odds").await;
+    let (gm, player) = gm_and_player(&clients);
+    let (player_id, token) = (clients[player].player_id, clients[player].token.clone());
+
+    clients[gm]
+        .send(ClientToServerMessage::OfferChallenge {
+            challenge: Challenge {
+                player_id,
+                attribute: Attribute::Heroic,
+                speciality_applies: true,
+                reputation_applies: true,
+            },
+        })
+        .await;
+    let odds = expect_odds(&mut clients[player]).await;
+    // Heroic 3, speciality and reputation
+    assert_eq!(odds.dice, 5);
+    assert!((odds.roll.success - (1.0 - (5.0f64 / 6.0).powi(5))).abs() < 1e-9);
+    // succeeding on a 5 as well can only help
+    let with_artifact = odds.with_artifact.unwrap();
+    assert!(with_artifact.success > odds.roll.success);
+    for chances in &[odds.roll, with_artifact] {
+        assert!((0.0..=1.0 + 1e-9).contains(&chances.success));
+        assert!((0.0..=1.0 + 1e-9).contains(&chances.possession));
+    }
+
+    // somebody who comes back while deciding gets them again
+    let mut client = TestClient::connect(&server, "odds", player_id, &token).await;
+    assert_eq!(expect_odds(&mut client).await, odds);
+}
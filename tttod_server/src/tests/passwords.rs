@@ -0,0 +1,70 @@
+use super::{config, journal_entries, start, TempDir, TestClient};
+use crate::{config::Journal, journal::Entry};
+use tttod_data::{ClientToServerMessage, Event, ServerToClientMessage, REDACTED};
+
+#[actix_rt::test]
+async fn journal_keeps_the_password_secret() {
+    let journal = TempDir::new();
+    let mut config = config(&[]);
+    config.journal = Some(Journal {
+        path: journal.0.clone(),
+    });
+    let server = start(config);
+    let mut client = TestClient::join(&server, "secret").await;
+    client
+        .send(ClientToServerMessage::SetPassword {
+            password: Some("sesame".to_owned()),
+        })
+        .await;
+    client.settle().await;
+
+    let entries = journal_entries(&journal.0);
+    for entry in &entries {
+        assert!(!serde_json::to_string(entry).unwrap().contains("sesame"));
+    }
+    // still enough to replay the game, which only cares whether there is a password
+    assert!(entries.iter().any(|entry| matches!(
+        entry,
+        Entry::Event {
+            event: Event::Message {
+                message: ClientToServerMessage::SetPassword { password: Some(password) },
+                ..
+            },
+        } if password == REDACTED
+    )));
+}
+
+#[actix_rt::test]
+async fn wrong_password_is_not_mistaken_for_a_full_game() {
+    let mut config = config(&[]);
+    config.rules.min_players = 1;
+    config.rules.max_players = 1;
+    let server = start(config);
+    let mut host = TestClient::join(&server, "guarded").await;
+    host.send(ClientToServerMessage::SetPassword {
+        password: Some("sesame".to_owned()),
+    })
+    .await;
+    host.settle().await;
+
+    let mut intruder = TestClient::join_with_password(&server, "guarded", "open").await;
+    intruder.expect_closed().await;
+    assert!(matches!(
+        intruder.received.as_slice(),
+        [
+            ServerToClientMessage::Welcome,
+            ServerToClientMessage::WrongPassword
+        ]
+    ));
+
+    // knowing the password only gets as far as the seats
+    let mut latecomer = TestClient::join_with_password(&server, "guarded", "sesame").await;
+    latecomer.expect_closed().await;
+    assert!(matches!(
+        latecomer.received.as_slice(),
+        [
+            ServerToClientMessage::Welcome,
+            ServerToClientMessage::GameIsFull
+        ]
+    ));
+}
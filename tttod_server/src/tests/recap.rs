@@ -0,0 +1,42 @@
+use super::{config, enter_temple, lose_game, start};
+use tttod_data::ServerToClientMessage;
+
+#[actix_rt::test]
+async fn everybody_gets_a_recap_of_the_game() {
+    let mut config = config(&[("recap", 1)]);
+    config.rules.failures_needed = 1;
+    let server = start(config);
+    let mut clients = enter_temple(&server, "recap").await;
+    lose_game(&mut clients).await;
+
+    for client in &clients {
+        let recap = client
+            .received
+            .iter()
+            .find_map(|message| match message {
+                ServerToClientMessage::Recap(recap) => Some(recap),
+                _ => None,
+            })
+            .expect("a recap once the game is over");
+        assert!(!recap.victory);
+        assert_eq!(recap.players.len(), clients.len());
+        assert_eq!(recap.clues.len(), 6);
+        assert_eq!(recap.history.rooms.len(), 1);
+        let challenges = &recap.history.rooms[0].challenges;
+        assert!(!challenges.is_empty());
+        assert!(challenges.iter().all(|record| record.outcome.is_some()));
+
+        let markdown = recap.to_markdown();
+        let html = recap.to_html();
+        for answer in &["Gold", "Silver"] {
+            assert!(markdown.contains(answer));
+            assert!(html.contains(answer));
+        }
+        for idx in 0..clients.len() {
+            let heading = format!("### Indy, played by Player {}", idx);
+            assert!(markdown.contains(&heading));
+            assert!(html.contains(&format!("<h3>Indy, played by Player {}</h3>", idx)));
+        }
+        assert!(markdown.contains("Room 1, GMed by Indy"));
+    }
+}
@@ -0,0 +1,46 @@
+use super::{config, enter_temple, gm_and_player, start, TempDir, TestClient};
+use crate::config::Storage;
+use tttod_data::ServerToClientMessage;
+
+#[actix_rt::test]
+async fn restarted_server_resumes_the_game() {
+    let storage = TempDir::new();
+    let mut config = config(&[]);
+    config.storage = Some(Storage {
+        path: storage.0.clone(),
+    });
+    let server = start(config.clone());
+    let mut clients = enter_temple(&server, "restored").await;
+    let (_, player) = gm_and_player(&clients);
+    let client = clients.swap_remove(player);
+    let (player_id, token) = (client.player_id, client.token.clone());
+    let (players, state) = client.state().unwrap();
+    let (players, state) = (players.clone(), state.clone());
+    drop(clients);
+    drop(client);
+    server.stop().await;
+
+    let server = start(config);
+    let mut client = TestClient::connect(&server, "restored", player_id, &token).await;
+    match client
+        .expect("the state of the game", |message| {
+            matches!(message, ServerToClientMessage::PushState { .. })
+        })
+        .await
+    {
+        ServerToClientMessage::PushState {
+            players: restored_players,
+            game_state,
+            ..
+        } => {
+            assert_eq!(game_state, state);
+            assert_eq!(restored_players.len(), players.len());
+            for (id, player) in &players {
+                let restored = &restored_players[id];
+                assert_eq!(restored.name, player.name);
+                assert_eq!(restored.condition, player.condition);
+            }
+        }
+        _ => unreachable!(),
+    }
+}
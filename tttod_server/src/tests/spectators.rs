@@ -0,0 +1,135 @@
+use super::{config, enter_temple, gm_and_player, settle_all, start, TestClient};
+use tttod_data::{
+    Attribute, Challenge, ClientToServerMessage, GameInfo, GameState, ServerToClientMessage,
+};
+
+/// Plays through the rooms with a single success each, challenging the player after the GM.
+async fn reach_final_battle(clients: &mut [TestClient]) {
+    loop {
+        settle_all(clients).await;
+        let (gm, player) = match clients[0].state().unwrap().1 {
+            GameState::FinalBattle { .. } => return,
+            GameState::Room { successes: 1, .. } => {
+                let (gm, _) = gm_and_player(clients);
+                clients[gm].send(ClientToServerMessage::ReadyForGame).await;
+                continue;
+            }
+            GameState::Room { .. } => gm_and_player(clients),
+            state => panic!(
+                "Unexpected state {:?} on the way to the final battle",
+                state
+            ),
+        };
+        clients[gm]
+            .send(ClientToServerMessage::OfferChallenge {
+                challenge: Challenge {
+                    player_id: clients[player].player_id,
+                    attribute: Attribute::Heroic,
+                    speciality_applies: true,
+                    reputation_applies: true,
+                },
+            })
+            .await;
+        clients[player]
+            .send(ClientToServerMessage::ChallengeAccepted)
+            .await;
+        let result = clients[player]
+            .expect("the result of the challenge", |message| {
+                matches!(message, ServerToClientMessage::ChallengeResult(_))
+            })
+            .await;
+        if let ServerToClientMessage::ChallengeResult(result) = result {
+            if !result.success || result.can_use_artifact {
+                clients[player]
+                    .send(ClientToServerMessage::AcceptFate)
+                    .await;
+            }
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn spectator_only_sees_what_is_public() {
+    let mut config = config(&[("watched", 1)]);
+    config.rules.successes_needed = 1;
+    config.rules.max_players = 3;
+    let server = start(config);
+    let mut clients = enter_temple(&server, "watched").await;
+    reach_final_battle(&mut clients).await;
+
+    // a challenge is waiting for the player to accept it
+    let (gms, remaining_clues) = match clients[0].state().unwrap().1 {
+        GameState::FinalBattle {
+            gms,
+            remaining_clues,
+            ..
+        } => (gms.clone(), remaining_clues.len()),
+        _ => unreachable!(),
+    };
+    assert!(remaining_clues > 0);
+    let gm = clients
+        .iter()
+        .position(|client| gms.contains(&client.player_id))
+        .unwrap();
+    let player = clients
+        .iter()
+        .position(|client| !gms.contains(&client.player_id))
+        .unwrap();
+    let challenge = Challenge {
+        player_id: clients[player].player_id,
+        attribute: Attribute::Heroic,
+        speciality_applies: true,
+        reputation_applies: true,
+    };
+    clients[gm]
+        .send(ClientToServerMessage::OfferChallengeFinal {
+            challenge: challenge.clone(),
+            clue_idx: 0,
+        })
+        .await;
+    settle_all(&mut clients).await;
+    match clients[player].state().unwrap().1 {
+        GameState::FinalBattle {
+            challenge: offered, ..
+        } => assert_eq!(offered.as_ref(), Some(&challenge)),
+        state => panic!("expected the final battle, got {:?}", state),
+    }
+
+    // the seats are all taken, which doesn't keep anybody from watching
+    let mut spectator = TestClient::spectate(&server, "watched").await;
+    spectator.settle().await;
+    for message in &spectator.received {
+        match message {
+            ServerToClientMessage::Welcome | ServerToClientMessage::ChatMessage(_) => {}
+            ServerToClientMessage::PushState {
+                players,
+                game_state:
+                    GameState::FinalBattle {
+                        challenge,
+                        chosen_clue,
+                        ..
+                    },
+                ..
+            } => {
+                assert_eq!(players.len(), 3);
+                assert!(challenge.is_none());
+                assert!(chosen_clue.is_none());
+            }
+            message => panic!("spectator received {:?}", message),
+        }
+    }
+    assert!(spectator
+        .received
+        .iter()
+        .any(|message| matches!(message, ServerToClientMessage::PushState { .. })));
+
+    let info: GameInfo = server
+        .get("/api/games/watched")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!((info.players, info.max_players), (3, 3));
+}
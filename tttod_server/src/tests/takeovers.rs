@@ -0,0 +1,64 @@
+use super::{config, enter_temple, gm_and_player, settle_all, start, TestClient};
+use tttod_data::{ClientToServerMessage, GameState, ServerToClientMessage};
+
+#[actix_rt::test]
+async fn replacement_of_the_gm_gets_the_clue() {
+    let server = start(config(&[]));
+    let mut clients = enter_temple(&server, "takeover").await;
+    let (gm, _) = gm_and_player(&clients);
+    let clue = clients[gm]
+        .received
+        .iter()
+        .rev()
+        .find_map(|message| match message {
+            ServerToClientMessage::PushClue { clue } => Some(clue.clone()),
+            _ => None,
+        })
+        .unwrap();
+    let departed_id = clients.remove(gm).player_id;
+    settle_all(&mut clients).await;
+
+    let mut replacement = TestClient::join(&server, "takeover").await;
+    replacement
+        .expect("the departed GM", |message| match message {
+            ServerToClientMessage::Takeovers {
+                offline_players, ..
+            } => offline_players.contains(&departed_id),
+            _ => false,
+        })
+        .await;
+    replacement
+        .send(ClientToServerMessage::RequestTakeover {
+            player_id: departed_id,
+        })
+        .await;
+    for client in &mut clients {
+        client
+            .expect("the takeover request", |message| match message {
+                ServerToClientMessage::Takeovers { requests, .. } => requests
+                    .iter()
+                    .any(|request| request.replacement_id == replacement.player_id),
+                _ => false,
+            })
+            .await;
+        client
+            .send(ClientToServerMessage::ApproveTakeover {
+                player_id: departed_id,
+            })
+            .await;
+    }
+
+    let received = replacement
+        .expect("the clue", |message| {
+            matches!(message, ServerToClientMessage::PushClue { .. })
+        })
+        .await;
+    assert!(
+        matches!(received, ServerToClientMessage::PushClue { clue: received } if received == clue)
+    );
+    replacement.settle().await;
+    match replacement.state().unwrap().1 {
+        GameState::Room { gm, .. } => assert_eq!(*gm, replacement.player_id),
+        state => panic!("expected a room, got {:?}", state),
+    }
+}
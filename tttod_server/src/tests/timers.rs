@@ -0,0 +1,156 @@
+use super::{answer, config, journal_entries, settle_all, start, start_game, TempDir};
+use crate::{config::Journal, journal::Entry};
+use tttod_data::{
+    ClientToServerMessage, Event, GameState, PhaseTimers, PlayerStats, SeededDice,
+    ServerToClientMessage, TimeoutAction,
+};
+
+#[actix_rt::test]
+async fn time_running_out_readies_players() {
+    let mut config = config(&[]);
+    config.rules.timers = PhaseTimers {
+        action: TimeoutAction::AutoReady,
+        define_evil: Some(1),
+        ..Default::default()
+    };
+    let server = start(config);
+    let mut clients = start_game(&server, "timers").await;
+
+    answer(&mut clients[0]).await;
+    let stalled = [clients[1].player_id, clients[2].player_id];
+    clients[0]
+        .expect("countdowns", |message| match message {
+            ServerToClientMessage::PushState { countdowns, .. } => {
+                countdowns.len() == 2 && stalled.iter().all(|id| countdowns.contains_key(id))
+            }
+            _ => false,
+        })
+        .await;
+    for client in &mut clients {
+        client
+            .expect_state("character creation", |state| {
+                *state == GameState::CharacterCreation
+            })
+            .await;
+    }
+    settle_all(&mut clients).await;
+    // creating a character has no time limit
+    let countdowns = clients[0]
+        .received
+        .iter()
+        .rev()
+        .find_map(|message| match message {
+            ServerToClientMessage::PushState { countdowns, .. } => Some(countdowns),
+            _ => None,
+        });
+    assert!(countdowns.unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn others_vote_to_skip_a_stalled_player() {
+    let mut config = config(&[]);
+    config.rules.timers = PhaseTimers {
+        action: TimeoutAction::SkipVote,
+        create_character: Some(1),
+        ..Default::default()
+    };
+    let server = start(config);
+    let mut clients = start_game(&server, "skip").await;
+    for client in &mut clients {
+        answer(client).await;
+    }
+    for client in &mut clients {
+        client
+            .expect_state("character creation", |state| {
+                *state == GameState::CharacterCreation
+            })
+            .await;
+    }
+
+    let mut dice = SeededDice::new(1);
+    for client in &mut clients[..2] {
+        client
+            .send(ClientToServerMessage::SetCharacter {
+                stats: PlayerStats::random(&mut dice),
+            })
+            .await;
+        client.send(ClientToServerMessage::ReadyForGame).await;
+    }
+    let stalled_id = clients[2].player_id;
+    clients[0]
+        .expect("skip vote", |message| match message {
+            ServerToClientMessage::PushState { countdowns, .. } => countdowns
+                .get(&stalled_id)
+                .is_some_and(|countdown| countdown.skip_votes.is_some()),
+            _ => false,
+        })
+        .await;
+
+    // the stalled player doesn't get a say, and one vote out of two isn't enough
+    clients[2]
+        .send(ClientToServerMessage::VoteSkip {
+            player_id: stalled_id,
+        })
+        .await;
+    clients[0]
+        .send(ClientToServerMessage::VoteSkip {
+            player_id: stalled_id,
+        })
+        .await;
+    settle_all(&mut clients).await;
+    assert_eq!(clients[0].state().unwrap().1, &GameState::CharacterCreation);
+
+    clients[1]
+        .send(ClientToServerMessage::VoteSkip {
+            player_id: stalled_id,
+        })
+        .await;
+    for client in &mut clients {
+        client
+            .expect_state("introductions", |state| {
+                matches!(state, GameState::CharacterIntroduction)
+            })
+            .await;
+    }
+    settle_all(&mut clients).await;
+    let (players, _) = clients[2].state().unwrap();
+    assert!(players[&stalled_id].stats.as_ref().unwrap().is_valid());
+}
+
+#[actix_rt::test]
+async fn journal_skips_ticks_that_only_count_down() {
+    let journal = TempDir::new();
+    let mut config = config(&[]);
+    config.journal = Some(Journal {
+        path: journal.0.clone(),
+    });
+    config.rules.timers = PhaseTimers {
+        define_evil: Some(3),
+        ..Default::default()
+    };
+    let server = start(config);
+    let mut clients = start_game(&server, "journal").await;
+    clients[0]
+        .expect("time to run out", |message| match message {
+            ServerToClientMessage::PushState { countdowns, .. } => {
+                countdowns.len() == 3
+                    && countdowns
+                        .values()
+                        .all(|countdown| countdown.seconds_left == 0)
+            }
+            _ => false,
+        })
+        .await;
+
+    let ticks: Vec<u64> = journal_entries(&journal.0)
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Entry::Event {
+                event: Event::Tick { seconds },
+            } => Some(seconds),
+            _ => None,
+        })
+        .collect();
+    // the quiet seconds, then the one in which time ran out
+    assert_eq!(ticks, vec![2, 1]);
+}
@@ -1,20 +1,125 @@
-use crate::{game::InternalMessage, Error, Game};
-use actix::{Actor, AsyncContext, StreamHandler};
+use crate::{
+    game::{Credentials, InternalMessage},
+    Config, DeckLibrary, Error, Game,
+};
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
 use actix_web::{get, web, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver};
-use tttod_data::ServerToClientMessage;
+use std::time::{Duration, Instant};
+use tttod_data::{ClientToServerMessage, ServerToClientMessage, PROTOCOL_VERSION};
 use uuid::Uuid;
 
+/// How often the server pings clients
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// A client that hasn't answered for this long is considered gone, even if the connection is still open
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(15);
+
 #[derive(Debug)]
 struct GameSocket {
-    player_id: Uuid,
+    /// `None` for spectators
+    player_id: Option<Uuid>,
     game: Game,
     receiver: Option<UnboundedReceiver<ServerToClientMessage>>,
+    /// When the client was last heard from
+    heartbeat: Instant,
+    /// Sent to the game once the client said hello, `None` afterwards
+    join: Option<InternalMessage>,
+}
+
+impl GameSocket {
+    fn new(
+        player_id: Option<Uuid>,
+        game: Game,
+        receiver: UnboundedReceiver<ServerToClientMessage>,
+        join: InternalMessage,
+    ) -> Self {
+        Self {
+            player_id,
+            game,
+            receiver: Some(receiver),
+            heartbeat: Instant::now(),
+            join: Some(join),
+        }
+    }
+
+    fn send(ctx: &mut <Self as Actor>::Context, message: ServerToClientMessage) {
+        match message.into_json() {
+            Ok(txt) => ctx.text(txt),
+            Err(err) => log::error!("Failed serializing message: {:?}", err),
+        }
+    }
+
+    /// Tells a client that doesn't speak our protocol to reload, and hangs up.
+    fn refuse(ctx: &mut <Self as Actor>::Context) {
+        Self::send(
+            ctx,
+            ServerToClientMessage::IncompatibleClient {
+                protocol_version: PROTOCOL_VERSION,
+            },
+        );
+        ctx.close(None);
+        ctx.stop();
+    }
+
+    fn hello(
+        &mut self,
+        protocol_version: u32,
+        client_build: &str,
+        join: InternalMessage,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        if protocol_version != PROTOCOL_VERSION {
+            log::info!(
+                "Turning away client build {} of {:?}, it speaks protocol {} instead of {}",
+                client_build,
+                self.player_id,
+                protocol_version,
+                PROTOCOL_VERSION
+            );
+            return Self::refuse(ctx);
+        }
+        log::info!(
+            "Client build {} of {:?} connected",
+            client_build,
+            self.player_id
+        );
+        Self::send(ctx, ServerToClientMessage::Welcome);
+        if let Err(err) = self.game.as_ref().unbounded_send(join) {
+            log::error!("Failed joining game: {:?}", err);
+            ctx.close(None);
+            ctx.stop();
+        }
+    }
 }
 
 impl Actor for GameSocket {
     type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |socket, ctx| {
+            // closed tabs and lost connections don't always say goodbye
+            if socket.heartbeat.elapsed() > CLIENT_TIMEOUT {
+                log::info!("Client of {:?} timed out", socket.player_id);
+                ctx.stop();
+            } else if socket.join.is_some() {
+                // clients from before the handshake never say hello
+                GameSocket::refuse(ctx);
+            } else {
+                ctx.ping(b"");
+            }
+        });
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        // otherwise the game only notices once sending to this client fails
+        if let Some(player_id) = self.player_id {
+            self.game
+                .as_ref()
+                .unbounded_send(InternalMessage::RemoveClient { player_id })
+                .ok();
+        }
+    }
 }
 
 impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for GameSocket {
@@ -24,22 +129,51 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for GameSocket {
         }
     }
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        self.heartbeat = Instant::now();
         match msg {
             Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
             Ok(ws::Message::Text(text)) => {
-                if let Ok(msg) = serde_json::from_str(&text) {
-                    if let Err(err) = self.game.as_ref().unbounded_send(InternalMessage::Message {
-                        player_id: self.player_id,
-                        message: msg,
-                    }) {
-                        log::error!("Failed sending message to game: {:?}", err);
-                        ctx.close(None);
+                let message = match serde_json::from_str(&text) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::warn!("Failed parsing message {:?}: {}", text, err);
+                        if self.join.is_some() {
+                            Self::refuse(ctx);
+                        }
+                        return;
+                    }
+                };
+                match (message, self.join.take()) {
+                    (
+                        ClientToServerMessage::Hello {
+                            protocol_version,
+                            client_build,
+                        },
+                        Some(join),
+                    ) => self.hello(protocol_version, &client_build, join, ctx),
+                    (_, Some(_)) => Self::refuse(ctx),
+                    (ClientToServerMessage::Hello { .. }, None) => {}
+                    (message, None) => {
+                        if let Some(player_id) = self.player_id {
+                            if let Err(err) = self
+                                .game
+                                .as_ref()
+                                .unbounded_send(InternalMessage::Message { player_id, message })
+                            {
+                                log::error!("Failed sending message to game: {:?}", err);
+                                ctx.close(None);
+                            }
+                        }
                     }
                 }
             }
             Ok(ws::Message::Binary(_)) => {
                 log::error!("Received unknown binary message!");
             }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
             _ => (),
         }
     }
@@ -47,56 +181,69 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for GameSocket {
 
 impl StreamHandler<ServerToClientMessage> for GameSocket {
     fn handle(&mut self, msg: ServerToClientMessage, ctx: &mut Self::Context) {
-        match msg.into_json() {
-            Ok(txt) => {
-                ctx.text(txt);
-            }
-            Err(err) => {
-                log::error!("Failed serializing message: {:?}", err);
-            }
-        }
+        Self::send(ctx, msg);
     }
 }
 
 #[get("/api/{game_name}/{player_id}/ws")]
 pub async fn index(
     web::Path((game_name, player_id)): web::Path<(String, Uuid)>,
+    web::Query(credentials): web::Query<Credentials>,
     games: web::Data<crate::Games>,
+    config: web::Data<Config>,
+    decks: web::Data<DeckLibrary>,
     req: HttpRequest,
     stream: web::Payload,
 ) -> Result<HttpResponse, Error> {
-    let mut game = {
-        games
-            .lock()
-            .map_err(|_| Error::MutexPoisoned)?
+    let game = {
+        let mut games = games.lock().map_err(|_| Error::MutexPoisoned)?;
+        let game = games
             .entry(game_name.clone())
-            .or_default()
-            .clone()
+            .or_insert_with(|| Game::new(game_name.clone(), &config, &decks));
+        // a game that shut down but wasn't reaped yet is started over
+        if game.as_ref().is_closed() {
+            *game = Game::new(game_name, &config, &decks);
+        }
+        game.clone()
     };
     let (sender, receiver) = unbounded();
-    if let Err(err) = game
-        .as_ref()
-        .unbounded_send(InternalMessage::AddClient { player_id, sender })
-    {
-        if err.is_disconnected() {
-            game = Game::default();
-            games
-                .lock()
-                .map_err(|_| Error::MutexPoisoned)?
-                .insert(game_name, game.clone());
-            game.as_ref()
-                .unbounded_send(err.into_inner())
-                .map_err(|err| Error::UnableToJoin(err.into_send_error()))?;
-        } else {
-            return Err(Error::UnableToJoin(err.into_send_error()));
-        }
+    let join = InternalMessage::AddClient {
+        player_id,
+        credentials,
+        sender,
+    };
+    Ok(ws::start(
+        GameSocket::new(Some(player_id), game, receiver, join),
+        &req,
+        stream,
+    )?)
+}
+
+#[get("/api/{game_name}/spectate/ws")]
+pub async fn spectate(
+    web::Path(game_name): web::Path<String>,
+    web::Query(credentials): web::Query<Credentials>,
+    games: web::Data<crate::Games>,
+    req: HttpRequest,
+    stream: web::Payload,
+) -> Result<HttpResponse, Error> {
+    // watching a game must not start a new one
+    let game = games
+        .lock()
+        .map_err(|_| Error::MutexPoisoned)?
+        .get(&game_name)
+        .cloned()
+        .ok_or(Error::NotFound)?;
+    if game.as_ref().is_closed() {
+        return Err(Error::NotFound);
     }
+    let (sender, receiver) = unbounded();
+    let join = InternalMessage::AddSpectator {
+        credentials,
+        sender,
+    };
     Ok(ws::start(
-        GameSocket {
-            player_id,
-            game,
-            receiver: Some(receiver),
-        },
+        GameSocket::new(None, game, receiver, join),
         &req,
         stream,
     )?)